@@ -0,0 +1,280 @@
+#!/usr/bin/env node
+import * as http from 'http';
+import dotenv from 'dotenv';
+import { performance } from 'perf_hooks';
+import { Config } from '../utils/Config';
+import { CodecRegistry } from '../core/CodecRegistry';
+
+dotenv.config();
+
+interface CliOptions {
+  socketPath: string | null;
+  host: string;
+  port: number;
+  token: string;
+  seconds: number;
+  width: number;
+  height: number;
+}
+
+const USAGE = `Usage: rdp-gateway <sessions|bench> <command> [options]
+
+Commands:
+  sessions list              List active sessions
+  sessions stats <id>        Show statistics for a session
+  sessions kill <id>         Disconnect a session
+  bench encode               Encode a synthetic desktop sequence with every codec
+
+Options:
+  --socket <path>            Use the control socket at this path (default when CONTROL_SOCKET_ENABLED=true)
+  --host <host>              Use the network API on this host instead of the control socket
+  --port <port>              Network API port (default: SERVER_PORT or 4000)
+  --token <token>            Admin token for the network API (default: RDP_ADMIN_TOKEN)
+  --seconds <n>              Benchmark duration per codec (default: 5)
+  --width <px>               Benchmark frame width (default: 1920)
+  --height <px>              Benchmark frame height (default: 1080)
+`;
+
+function parseArgs(argv: string[]): { positional: string[]; options: CliOptions } {
+  const positional: string[] = [];
+  const controlSocket = Config.getInstance().get('server').controlSocket;
+  const options: CliOptions = {
+    socketPath: controlSocket.enabled ? controlSocket.path : null,
+    host: '127.0.0.1',
+    port: parseInt(process.env.SERVER_PORT || '4000'),
+    token: process.env.RDP_ADMIN_TOKEN || '',
+    seconds: 5,
+    width: 1920,
+    height: 1080
+  };
+
+  for (let i = 0; i < argv.length; i++) {
+    const arg = argv[i];
+    if (arg === '--socket') {
+      options.socketPath = argv[++i];
+    } else if (arg === '--host') {
+      options.host = argv[++i];
+      options.socketPath = null;
+    } else if (arg === '--port') {
+      options.port = parseInt(argv[++i]);
+      options.socketPath = null;
+    } else if (arg === '--token') {
+      options.token = argv[++i];
+    } else if (arg === '--seconds') {
+      options.seconds = parseFloat(argv[++i]);
+    } else if (arg === '--width') {
+      options.width = parseInt(argv[++i]);
+    } else if (arg === '--height') {
+      options.height = parseInt(argv[++i]);
+    } else {
+      positional.push(arg);
+    }
+  }
+
+  return { positional, options };
+}
+
+function request(options: CliOptions, method: string, path: string): Promise<any> {
+  return new Promise((resolve, reject) => {
+    // The control socket authenticates by OS user, so no token is sent there
+    const target = options.socketPath
+      ? { socketPath: options.socketPath }
+      : { host: options.host, port: options.port, headers: { Authorization: `Bearer ${options.token}` } };
+
+    const req = http.request({
+      ...target,
+      method,
+      path: `/api/sessions${path}`
+    }, (res) => {
+      let body = '';
+      res.setEncoding('utf8');
+      res.on('data', (chunk) => {
+        body += chunk;
+      });
+      res.on('end', () => {
+        try {
+          const result = JSON.parse(body);
+          if (!result.success) {
+            reject(new Error(result.error || `Request failed with status ${res.statusCode}`));
+            return;
+          }
+          resolve(result);
+        } catch (error) {
+          reject(new Error(`Invalid response from gateway (status ${res.statusCode})`));
+        }
+      });
+    });
+
+    req.on('error', reject);
+    req.end();
+  });
+}
+
+function formatDuration(milliseconds: number): string {
+  const seconds = Math.floor(milliseconds / 1000);
+  const hours = Math.floor(seconds / 3600);
+  const minutes = Math.floor((seconds % 3600) / 60);
+  return `${hours}h${String(minutes).padStart(2, '0')}m${String(seconds % 60).padStart(2, '0')}s`;
+}
+
+async function listSessions(options: CliOptions): Promise<void> {
+  const result = await request(options, 'GET', '/');
+  if (result.sessions.length === 0) {
+    console.log('No active sessions');
+    return;
+  }
+
+  const rows = result.sessions.map((session: any) => [
+    session.id,
+    session.status,
+    `${session.config.username}@${session.config.host}:${session.config.port}`,
+    session.config.quality,
+    formatDuration(Date.now() - new Date(session.startTime).getTime())
+  ]);
+  const header = ['ID', 'STATUS', 'TARGET', 'QUALITY', 'UPTIME'];
+  const widths = header.map((title, column) =>
+    Math.max(title.length, ...rows.map((row: string[]) => row[column].length)));
+
+  for (const row of [header, ...rows]) {
+    console.log(row.map((cell: string, column: number) => cell.padEnd(widths[column])).join('  '));
+  }
+}
+
+async function showStats(options: CliOptions, sessionId: string): Promise<void> {
+  const { stats } = await request(options, 'GET', `/${encodeURIComponent(sessionId)}/stats`);
+
+  console.log(`Session:     ${stats.sessionId}`);
+  console.log(`Status:      ${stats.status}`);
+  console.log(`Duration:    ${formatDuration(stats.duration)}`);
+  console.log(`Frames:      ${stats.frameCount} (${stats.fps} fps)`);
+  console.log(`Received:    ${stats.bytesReceived} bytes`);
+  console.log(`Sent:        ${stats.bytesSent} bytes`);
+  console.log(`Max bitrate: ${stats.maxBitrate} bps`);
+  console.log(`Adapted to:  ${stats.targetBitrate} bps, ${stats.effectiveQuality}, ${stats.targetFps} fps`);
+  if (stats.networkLink) {
+    console.log(`Host link:   ${stats.networkLink.type} (${stats.networkLink.interface || 'unknown'})`);
+  }
+  if (stats.pipeline) {
+    console.log(`Queues:      receive ${stats.pipeline.receive.depth}, send ${stats.pipeline.send.depth}`);
+    console.log(`Encode:      p95 ${Math.round(stats.pipeline.encode.time.p95)} ms, ratio ${stats.pipeline.encode.compressionRatio}:1, ${stats.pipeline.encode.pending} pending`);
+    console.log(`Dropped:     ${stats.pipeline.framesDropped} frames (${stats.pipeline.framesCoalesced} coalesced)`);
+    console.log(`Latency:     p50 ${Math.round(stats.pipeline.latency.p50)} ms, p95 ${Math.round(stats.pipeline.latency.p95)} ms`);
+    console.log(`Bandwidth:   ${stats.pipeline.bandwidth.estimate} bps estimated`);
+  }
+  if (stats.transport) {
+    const rtt = stats.transport.rtt === null ? 'unknown' : `${stats.transport.rtt} ms`;
+    console.log(`Client link: ${stats.transport.transport}, rtt ${rtt}, ${stats.transport.bytesOutPerSec} B/s out, ${stats.transport.drops} dropped`);
+  }
+  if (stats.input) {
+    console.log(`Input:       moves ${stats.input.move.forwarded}/${stats.input.move.received} forwarded, wheel ${stats.input.wheel.forwarded}/${stats.input.wheel.received} forwarded`);
+  }
+  if (stats.error) {
+    console.log(`Error:       ${stats.error}`);
+  }
+}
+
+async function killSession(options: CliOptions, sessionId: string): Promise<void> {
+  const result = await request(options, 'DELETE', `/${encodeURIComponent(sessionId)}`);
+  console.log(result.message);
+}
+
+// Desktop-like content: flat window areas with a band of "text" that
+// changes every frame, roughly what a user typing in an editor produces
+function syntheticFrame(width: number, height: number, index: number): Buffer {
+  const frame = Buffer.alloc(width * height * 4);
+  for (let y = 0; y < height; y++) {
+    for (let x = 0; x < width; x++) {
+      const offset = (y * width + x) * 4;
+      const inWindow = x > width / 8 && x < width * 7 / 8 && y > height / 8 && y < height * 7 / 8;
+      const textRow = Math.floor(y / 16) % 2 === 0 && (y + index) % 16 < 10;
+      const ink = inWindow && textRow && ((x * 7 + y * 13 + index * 31) % 11 < 4);
+      const value = ink ? 30 : inWindow ? 245 : 60 + Math.floor((y / height) * 80);
+      frame[offset] = value;
+      frame[offset + 1] = value;
+      frame[offset + 2] = inWindow ? value : value + 40;
+      frame[offset + 3] = 255;
+    }
+  }
+  return frame;
+}
+
+async function benchEncode(options: CliOptions): Promise<void> {
+  const { width, height, seconds } = options;
+  const registry = CodecRegistry.getInstance();
+
+  // Generated up front so frame synthesis is not part of the measurement
+  const frames = Array.from({ length: 30 }, (_, index) => syntheticFrame(width, height, index));
+  const rawBytes = width * height * 4;
+
+  console.log(`Encoding ${width}x${height} synthetic frames for ${seconds}s per codec\n`);
+  const rows: string[][] = [];
+
+  for (const name of registry.list()) {
+    const codec = registry.create(name);
+    let count = 0;
+    let bytes = 0;
+    const startedAt = performance.now();
+
+    while (performance.now() - startedAt < seconds * 1000) {
+      const encoded = await codec.encode(frames[count % frames.length], { width, height, raw: true, lossless: false });
+      bytes += encoded.length;
+      count++;
+    }
+
+    const elapsed = (performance.now() - startedAt) / 1000;
+    const averageBytes = bytes / count;
+    codec.shutdown?.();
+
+    rows.push([
+      name,
+      (count / elapsed).toFixed(1),
+      `${(averageBytes / 1024).toFixed(1)} KiB`,
+      `${(rawBytes / averageBytes).toFixed(1)}:1`,
+      `${((averageBytes * 8 * 30) / 1e6).toFixed(1)} Mbps`
+    ]);
+  }
+
+  // Every registered codec is lossless, so PSNR/SSIM would be identical
+  const header = ['CODEC', 'FPS', 'FRAME SIZE', 'RATIO', 'BITRATE @30FPS'];
+  const widths = header.map((title, column) => Math.max(title.length, ...rows.map(row => row[column].length)));
+  for (const row of [header, ...rows]) {
+    console.log(row.map((cell, column) => cell.padEnd(widths[column])).join('  '));
+  }
+}
+
+async function main(): Promise<void> {
+  const { positional, options } = parseArgs(process.argv.slice(2));
+  const [group, command, sessionId] = positional;
+
+  if (group === 'bench' && command === 'encode') {
+    await benchEncode(options);
+    return;
+  }
+
+  if (group !== 'sessions' || !command) {
+    console.error(USAGE);
+    process.exit(1);
+  }
+
+  switch (command) {
+    case 'list':
+      await listSessions(options);
+      break;
+    case 'stats':
+    case 'kill':
+      if (!sessionId) {
+        console.error(`Missing session id\n\n${USAGE}`);
+        process.exit(1);
+      }
+      await (command === 'stats' ? showStats(options, sessionId) : killSession(options, sessionId));
+      break;
+    default:
+      console.error(`Unknown command: ${command}\n\n${USAGE}`);
+      process.exit(1);
+  }
+}
+
+main().catch((error) => {
+  console.error(`Error: ${(error as Error).message}`);
+  process.exit(1);
+});
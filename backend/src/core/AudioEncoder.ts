@@ -0,0 +1,126 @@
+import ffmpeg from 'fluent-ffmpeg';
+import { EventEmitter } from 'events';
+import { PassThrough } from 'stream';
+
+// Opus always runs at 48 kHz; other host rates are resampled by ffmpeg
+export const OPUS_SAMPLE_RATE = 48000;
+// Short frames keep latency down; each packet is one frame
+export const OPUS_FRAME_DURATION = 20; // ms
+
+export interface AudioFormat {
+  sampleRate: number;
+  channels: number;
+}
+
+// Splits an Ogg stream back into the packets it carries
+class OggPacketReader {
+  private buffer = Buffer.alloc(0);
+  // Segments of a packet that continues on the next page
+  private partial: Buffer[] = [];
+
+  public push(chunk: Buffer): Buffer[] {
+    this.buffer = Buffer.concat([this.buffer, chunk]);
+    const packets: Buffer[] = [];
+
+    while (this.buffer.length >= 27) {
+      if (this.buffer.toString('ascii', 0, 4) !== 'OggS') {
+        throw new Error('Lost Ogg page sync');
+      }
+      const headerSize = 27 + this.buffer.readUInt8(26);
+      if (this.buffer.length < headerSize) break;
+
+      const lacing = this.buffer.subarray(27, headerSize);
+      const bodySize = lacing.reduce((sum, value) => sum + value, 0);
+      if (this.buffer.length < headerSize + bodySize) break;
+
+      let offset = headerSize;
+      for (const value of lacing) {
+        this.partial.push(this.buffer.subarray(offset, offset + value));
+        offset += value;
+        // A segment shorter than 255 bytes ends its packet
+        if (value < 255) {
+          packets.push(Buffer.concat(this.partial));
+          this.partial = [];
+        }
+      }
+      this.buffer = this.buffer.subarray(offset);
+    }
+    return packets;
+  }
+}
+
+// Encodes the host's 16-bit PCM to Opus through ffmpeg. Emits 'packet' with
+// each Opus packet, ready for a WebCodecs AudioDecoder, and 'error' if the
+// encoder fails (e.g. ffmpeg was built without libopus).
+export class OpusAudioEncoder extends EventEmitter {
+  private input = new PassThrough();
+  private output = new PassThrough();
+  private reader = new OggPacketReader();
+  private command: ffmpeg.FfmpegCommand;
+  private closed = false;
+  // Set while ffmpeg is not keeping up; PCM arriving meanwhile is dropped, as
+  // late audio is worse than a gap and the backlog would otherwise grow unbounded
+  private saturated = false;
+
+  constructor(public readonly format: AudioFormat, bitrate: number) {
+    super();
+
+    this.output.on('data', (chunk: Buffer) => {
+      try {
+        for (const packet of this.reader.push(chunk)) {
+          // Stream headers; the client is configured from 'audio:config' instead
+          const magic = packet.toString('ascii', 0, 8);
+          if (magic === 'OpusHead' || magic === 'OpusTags') continue;
+          this.emit('packet', packet);
+        }
+      } catch (error) {
+        this.fail(error as Error);
+      }
+    });
+
+    this.command = ffmpeg()
+      .input(this.input)
+      .inputFormat('s16le')
+      .inputOptions(['-ar', String(format.sampleRate), '-ac', String(format.channels)])
+      .audioCodec('libopus')
+      .audioBitrate(Math.round(bitrate / 1000))
+      .audioFrequency(OPUS_SAMPLE_RATE)
+      .outputOptions([
+        '-application', 'lowdelay',
+        '-frame_duration', String(OPUS_FRAME_DURATION),
+        // One page per packet, so nothing waits in the muxer
+        '-page_duration', String(OPUS_FRAME_DURATION * 1000),
+        '-flush_packets', '1'
+      ])
+      .format('ogg')
+      .on('error', (error: Error) => this.fail(error));
+    this.command.pipe(this.output, { end: true });
+  }
+
+  public write(pcm: Buffer): void {
+    if (this.closed) return;
+    if (this.saturated) return;
+
+    if (!this.input.write(pcm)) {
+      this.saturated = true;
+      this.input.once('drain', () => {
+        this.saturated = false;
+      });
+    }
+  }
+
+  public close(): void {
+    if (this.closed) return;
+
+    this.closed = true;
+    this.input.end();
+    this.command.kill('SIGTERM');
+  }
+
+  private fail(error: Error): void {
+    if (this.closed) return;
+
+    this.close();
+    this.emit('error', error);
+  }
+}
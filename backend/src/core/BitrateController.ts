@@ -0,0 +1,68 @@
+export interface CongestionSignals {
+  sendP95: number;
+  latencyP95: number;
+  framesDropped: number;
+  // Delivery rate measured from client acks in bits/s, 0 if unknown
+  estimatedBandwidth?: number;
+}
+
+export interface BitrateControllerOptions {
+  minBitrate: number;
+  congestionQueueTime: number;
+  congestionLatency: number;
+  recoveryPeriod: number;
+}
+
+// Multiplicative decrease on congestion, additive increase once the link has
+// been clear for a while; the user-selected quality is the ceiling
+export class BitrateController {
+  private bitrate: number;
+  private lastDropCount = 0;
+  private lastDecreaseAt = 0;
+  private clearSince: number;
+
+  constructor(private ceiling: number, private options: BitrateControllerOptions) {
+    this.bitrate = ceiling;
+    this.clearSince = Date.now();
+  }
+
+  public get target(): number {
+    return this.bitrate;
+  }
+
+  public setCeiling(ceiling: number): void {
+    this.ceiling = ceiling;
+    this.bitrate = Math.min(this.bitrate, ceiling);
+  }
+
+  // Returns the new target when it changed, otherwise null
+  public update(signals: CongestionSignals, now: number = Date.now()): number | null {
+    const newDrops = signals.framesDropped - this.lastDropCount;
+    this.lastDropCount = signals.framesDropped;
+
+    const congested = newDrops > 0
+      || signals.sendP95 >= this.options.congestionQueueTime
+      || signals.latencyP95 >= this.options.congestionLatency;
+
+    const previous = this.bitrate;
+
+    if (congested) {
+      this.clearSince = now;
+      // Give the previous decrease time to take effect before cutting again
+      if (now - this.lastDecreaseAt >= this.options.recoveryPeriod / 2) {
+        let next = Math.round(this.bitrate * 0.7);
+        // While congested, the delivery rate is what the link really carries
+        if (signals.estimatedBandwidth && signals.estimatedBandwidth > 0) {
+          next = Math.min(next, Math.round(signals.estimatedBandwidth * 0.9));
+        }
+        this.bitrate = Math.max(this.options.minBitrate, next);
+        this.lastDecreaseAt = now;
+      }
+    } else if (now - this.clearSince >= this.options.recoveryPeriod && this.bitrate < this.ceiling) {
+      this.bitrate = Math.min(this.ceiling, this.bitrate + Math.round(this.ceiling * 0.1));
+      this.clearSince = now;
+    }
+
+    return this.bitrate !== previous ? this.bitrate : null;
+  }
+}
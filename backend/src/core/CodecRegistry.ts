@@ -0,0 +1,93 @@
+import sharp from 'sharp';
+import * as zlib from 'zlib';
+import { promisify } from 'util';
+import { FrameScaler, FrameSize } from './FrameScaler';
+
+const deflate = promisify(zlib.deflate);
+
+export interface EncodeOptions extends FrameSize {
+  // Whether the payload is tightly packed 32-bit pixels of the given size
+  raw: boolean;
+  lossless: boolean;
+}
+
+// A frame encoder; one instance is created per session so codecs may keep state
+export interface FrameCodec {
+  readonly name: string;
+  encode(frame: Buffer, options: EncodeOptions): Promise<Buffer>;
+  requestKeyframe?(): void;
+  reconfigure?(size: FrameSize): void;
+  shutdown?(): void;
+}
+
+export type FrameCodecFactory = () => FrameCodec;
+
+class DeflateCodec implements FrameCodec {
+  public readonly name = 'deflate';
+
+  public encode(frame: Buffer, options: EncodeOptions): Promise<Buffer> {
+    const level = options.lossless ? zlib.constants.Z_BEST_COMPRESSION : zlib.constants.Z_DEFAULT_COMPRESSION;
+    return deflate(frame, { level });
+  }
+}
+
+class RawCodec implements FrameCodec {
+  public readonly name = 'raw';
+
+  public async encode(frame: Buffer): Promise<Buffer> {
+    return frame;
+  }
+}
+
+// Browsers decode PNG natively; payloads the host already encoded pass through
+class PngCodec implements FrameCodec {
+  public readonly name = 'png';
+
+  public async encode(frame: Buffer, options: EncodeOptions): Promise<Buffer> {
+    if (!options.raw) return frame;
+
+    return sharp(frame, {
+      raw: { width: options.width, height: options.height, channels: FrameScaler.BYTES_PER_PIXEL }
+    })
+      .png({ compressionLevel: options.lossless ? 9 : 6 })
+      .toBuffer();
+  }
+}
+
+export class CodecRegistry {
+  private static instance: CodecRegistry;
+  private factories: Map<string, FrameCodecFactory> = new Map();
+
+  private constructor() {
+    this.register('deflate', () => new DeflateCodec());
+    this.register('raw', () => new RawCodec());
+    this.register('png', () => new PngCodec());
+  }
+
+  public static getInstance(): CodecRegistry {
+    if (!CodecRegistry.instance) {
+      CodecRegistry.instance = new CodecRegistry();
+    }
+    return CodecRegistry.instance;
+  }
+
+  public register(name: string, factory: FrameCodecFactory): void {
+    this.factories.set(name, factory);
+  }
+
+  public create(name: string): FrameCodec {
+    const factory = this.factories.get(name);
+    if (!factory) {
+      throw new Error(`Unknown codec: ${name}`);
+    }
+    return factory();
+  }
+
+  public has(name: string): boolean {
+    return this.factories.has(name);
+  }
+
+  public list(): string[] {
+    return Array.from(this.factories.keys());
+  }
+}
@@ -0,0 +1,135 @@
+import express from 'express';
+import { createServer, Server } from 'http';
+import { connect } from 'net';
+import { dirname, join } from 'path';
+import { chmodSync, lstatSync, mkdtempSync, renameSync, rmdirSync, unlinkSync } from 'fs';
+import { Logger } from '../utils/Logger';
+import { AuthManager } from '../services/AuthManager';
+
+// Serves the REST API over a Unix domain socket. Access is controlled by the
+// OS: the socket is only connectable by the user running the gateway, so
+// requests on it are treated as the local admin. Windows named pipes are
+// open to every local user unless given an ACL, which Node cannot set, so
+// the channel is not available there.
+export class ControlChannel {
+  private logger = new Logger('ControlChannel');
+  private server: Server | null = null;
+
+  constructor(
+    private app: express.Application,
+    private authManager: AuthManager,
+    private socketPath: string
+  ) {}
+
+  public async start(): Promise<void> {
+    if (process.platform === 'win32') {
+      throw new Error('The control channel is not supported on Windows; use the network API instead');
+    }
+
+    await this.removeStaleSocket();
+    await this.listen();
+    this.logger.info(`Control channel listening on ${this.socketPath}`);
+  }
+
+  public stop(): void {
+    if (!this.server) return;
+
+    this.server.close();
+    this.server = null;
+
+    if (this.isSocket(this.socketPath)) {
+      unlinkSync(this.socketPath);
+    }
+  }
+
+  // A socket left by an unclean shutdown would make listen() fail. Only a
+  // socket nobody answers on is removed: anything else at the path is a
+  // mistake in the configuration or another gateway's live channel.
+  private removeStaleSocket(): Promise<void> {
+    let exists = true;
+    try {
+      lstatSync(this.socketPath);
+    } catch (error) {
+      exists = false;
+    }
+    if (!exists) return Promise.resolve();
+    if (!this.isSocket(this.socketPath)) {
+      return Promise.reject(new Error(`${this.socketPath} exists and is not a socket`));
+    }
+
+    return new Promise((resolve, reject) => {
+      const probe = connect(this.socketPath);
+      probe.once('connect', () => {
+        probe.destroy();
+        reject(new Error(`Another process is listening on ${this.socketPath}`));
+      });
+      probe.once('error', (error: NodeJS.ErrnoException) => {
+        if (error.code !== 'ECONNREFUSED') {
+          reject(error);
+          return;
+        }
+        unlinkSync(this.socketPath);
+        resolve();
+      });
+    });
+  }
+
+  // Binds inside a fresh owner-only directory, so no other user can connect
+  // before the socket's own mode is tightened, then moves it into place.
+  // Changing the umask instead would affect every file the process creates
+  // while listen() is pending.
+  private listen(): Promise<void> {
+    const directory = mkdtempSync(join(dirname(this.socketPath), '.control-'));
+    const bindPath = join(directory, 'socket');
+    const cleanup = () => {
+      try {
+        if (this.isSocket(bindPath)) unlinkSync(bindPath);
+        rmdirSync(directory);
+      } catch (error) {
+        this.logger.warn(`Could not remove ${directory}:`, error);
+      }
+    };
+
+    return new Promise((resolve, reject) => {
+      this.server = createServer((req, res) => {
+        // Never trust a caller-supplied token on this channel
+        const admin = this.authManager.getUser('admin');
+        if (!admin) {
+          res.statusCode = 503;
+          res.end(JSON.stringify({ success: false, error: 'Local admin user unavailable' }));
+          return;
+        }
+
+        req.headers.authorization = `Bearer ${this.authManager.generateToken(admin)}`;
+        this.app(req as any, res as any);
+      });
+
+      this.server.once('error', (error) => {
+        cleanup();
+        reject(error);
+      });
+
+      this.server.listen(bindPath, () => {
+        try {
+          chmodSync(bindPath, 0o600);
+          renameSync(bindPath, this.socketPath);
+          resolve();
+        } catch (error) {
+          this.server?.close();
+          this.server = null;
+          reject(error);
+        } finally {
+          cleanup();
+        }
+      });
+    });
+  }
+
+  private isSocket(path: string): boolean {
+    try {
+      return lstatSync(path).isSocket();
+    } catch (error) {
+      return false;
+    }
+  }
+}
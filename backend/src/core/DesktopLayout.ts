@@ -0,0 +1,35 @@
+import { RDPDisplay } from './RDPSessionManager';
+
+// Clients address a point on a display as 0..NORMALIZED_MAX on each axis,
+// independent of how the frame was scaled for them
+export const NORMALIZED_MAX = 65535;
+
+export interface DesktopPoint {
+  x: number;
+  y: number;
+}
+
+// Monitor positions may be negative (left of or above the primary); the
+// host's pointer coordinates start at the top-left of the whole layout
+export function desktopOrigin(displays: RDPDisplay[]): DesktopPoint {
+  if (displays.length === 0) return { x: 0, y: 0 };
+  return {
+    x: Math.min(...displays.map(display => display.x)),
+    y: Math.min(...displays.map(display => display.y))
+  };
+}
+
+// Maps a normalized point on one display into virtual desktop coordinates.
+// Display sizes are in physical pixels, so monitors with different DPI
+// scaling need no extra conversion.
+export function toDesktopPoint(displays: RDPDisplay[], displayId: number, x: number, y: number): DesktopPoint | undefined {
+  const display = displays.find(d => d.id === displayId);
+  if (!display) return undefined;
+
+  const origin = desktopOrigin(displays);
+  const clamp = (value: number) => Math.min(Math.max(value, 0), NORMALIZED_MAX) / NORMALIZED_MAX;
+  return {
+    x: display.x - origin.x + Math.round(clamp(x) * (display.width - 1)),
+    y: display.y - origin.y + Math.round(clamp(y) * (display.height - 1))
+  };
+}
@@ -0,0 +1,69 @@
+import sharp from 'sharp';
+import { ResolutionCap } from '../utils/Config';
+
+export interface FrameSize {
+  width: number;
+  height: number;
+}
+
+// Downscales raw captured frames before they are compressed, so a 4K desktop
+// can be streamed at a lower resolution. Runs on libvips; there is no GPU
+// path in the gateway.
+export class FrameScaler {
+  // Raw frames from the host are 32-bit pixels
+  public static readonly BYTES_PER_PIXEL = 4;
+
+  public static getTargetSize(source: FrameSize, cap: ResolutionCap): FrameSize {
+    let factor = cap.scale > 0 && cap.scale < 1 ? cap.scale : 1;
+
+    if (cap.maxWidth > 0) {
+      factor = Math.min(factor, cap.maxWidth / source.width);
+    }
+    if (cap.maxHeight > 0) {
+      factor = Math.min(factor, cap.maxHeight / source.height);
+    }
+
+    if (factor >= 1) {
+      return { width: source.width, height: source.height };
+    }
+
+    // Keep dimensions even; most video encoders require it
+    return {
+      width: Math.max(2, Math.floor(source.width * factor) & ~1),
+      height: Math.max(2, Math.floor(source.height * factor) & ~1)
+    };
+  }
+
+  public static isRawFrame(frame: Buffer, size: FrameSize): boolean {
+    return frame.length === size.width * size.height * FrameScaler.BYTES_PER_PIXEL;
+  }
+
+  // Copies rows out of a padded (pitch > width * 4) buffer into a tight one
+  public static removePadding(frame: Buffer, size: FrameSize, stride: number): Buffer {
+    const rowBytes = size.width * FrameScaler.BYTES_PER_PIXEL;
+    const packed = Buffer.allocUnsafe(rowBytes * size.height);
+    for (let row = 0; row < size.height; row++) {
+      frame.copy(packed, row * rowBytes, row * stride, row * stride + rowBytes);
+    }
+    return packed;
+  }
+
+  public static async scale(frame: Buffer, source: FrameSize, target: FrameSize): Promise<Buffer> {
+    return sharp(frame, {
+      raw: { width: source.width, height: source.height, channels: FrameScaler.BYTES_PER_PIXEL }
+    })
+      .resize(target.width, target.height, { fit: 'fill', kernel: sharp.kernel.cubic })
+      .raw()
+      .toBuffer();
+  }
+
+  public static async thumbnail(frame: Buffer, source: FrameSize, width: number, quality: number): Promise<Buffer> {
+    return sharp(frame, {
+      raw: { width: source.width, height: source.height, channels: FrameScaler.BYTES_PER_PIXEL }
+    })
+      .resize({ width: Math.min(width, source.width) })
+      .removeAlpha()
+      .jpeg({ quality })
+      .toBuffer();
+  }
+}
@@ -0,0 +1,186 @@
+type Modifier = 'ctrl' | 'alt' | 'shift' | 'win';
+
+// Windows virtual-key codes, generic and left/right specific
+const MODIFIER_KEYS: Record<Modifier, number[]> = {
+  ctrl: [0x11, 0xa2, 0xa3],
+  alt: [0x12, 0xa4, 0xa5],
+  shift: [0x10, 0xa0, 0xa1],
+  win: [0x5b, 0x5c]
+};
+
+const NAMED_KEYS: Record<string, number[]> = {
+  backspace: [0x08],
+  tab: [0x09],
+  enter: [0x0d],
+  pause: [0x13],
+  esc: [0x1b],
+  escape: [0x1b],
+  space: [0x20],
+  pageup: [0x21],
+  pagedown: [0x22],
+  end: [0x23],
+  home: [0x24],
+  left: [0x25],
+  up: [0x26],
+  right: [0x27],
+  down: [0x28],
+  printscreen: [0x2c],
+  insert: [0x2d],
+  del: [0x2e],
+  delete: [0x2e],
+  ...MODIFIER_KEYS
+};
+
+// PC/AT set 1 scan codes of the keys a policy can name, as the virtual key
+// for the plain and the E0/E1-prefixed scan code. Numpad keys that act as
+// navigation keys with Num Lock off count as those keys.
+const SCAN_CODE_KEYS: Record<number, [number | undefined, number | undefined]> = {
+  0x01: [0x1b, undefined],
+  0x0e: [0x08, undefined],
+  0x0f: [0x09, undefined],
+  0x1c: [0x0d, 0x0d],
+  0x1d: [0xa2, 0xa3],
+  0x2a: [0xa0, undefined],
+  0x36: [0xa1, undefined],
+  0x37: [undefined, 0x2c],
+  0x38: [0xa4, 0xa5],
+  0x39: [0x20, undefined],
+  0x45: [undefined, 0x13],
+  0x47: [0x24, 0x24],
+  0x48: [0x26, 0x26],
+  0x49: [0x21, 0x21],
+  0x4b: [0x25, 0x25],
+  0x4d: [0x27, 0x27],
+  0x4f: [0x23, 0x23],
+  0x50: [0x28, 0x28],
+  0x51: [0x22, 0x22],
+  0x52: [0x2d, 0x2d],
+  0x53: [0x2e, 0x2e],
+  0x5b: [0x5b, 0x5b],
+  0x5c: [0x5c, 0x5c]
+};
+
+const LETTER_SCAN_CODES = [
+  0x1e, 0x30, 0x2e, 0x20, 0x12, 0x21, 0x22, 0x23, 0x17, 0x24, 0x25, 0x26, 0x32,
+  0x31, 0x18, 0x19, 0x10, 0x13, 0x1f, 0x14, 0x16, 0x2f, 0x11, 0x2d, 0x15, 0x2c
+];
+const FUNCTION_KEY_SCAN_CODES = [
+  0x3b, 0x3c, 0x3d, 0x3e, 0x3f, 0x40, 0x41, 0x42, 0x43, 0x44, 0x57, 0x58,
+  0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x76
+];
+LETTER_SCAN_CODES.forEach((scanCode, index) => {
+  SCAN_CODE_KEYS[scanCode] = [0x41 + index, undefined];
+});
+// 1-9, then 0
+for (let digit = 0; digit < 10; digit++) {
+  SCAN_CODE_KEYS[0x02 + digit] = [digit === 9 ? 0x30 : 0x31 + digit, undefined];
+}
+FUNCTION_KEY_SCAN_CODES.forEach((scanCode, index) => {
+  SCAN_CODE_KEYS[scanCode] = [0x70 + index, undefined];
+});
+
+// The virtual key the host will take a scan code for, if a policy could name it
+export function virtualKeyForScanCode(scanCode: number, extended: boolean): number | undefined {
+  const keys = SCAN_CODE_KEYS[scanCode & 0xff];
+  return keys ? keys[extended ? 1 : 0] : undefined;
+}
+
+// A policy entry such as "win+r" or "ctrl+alt+del": the modifiers that must
+// be held and the key whose press is blocked. A lone modifier ("win") blocks
+// that key itself.
+export interface Hotkey {
+  entry: string;
+  modifiers: Modifier[];
+  keys: number[];
+}
+
+export function parseHotkey(entry: string): Hotkey | undefined {
+  const normalized = entry.toLowerCase().replace(/\s+/g, '');
+  const names = normalized.split('+');
+  const keyName = names.pop() as string;
+  const isModifier = (name: string) => Object.prototype.hasOwnProperty.call(MODIFIER_KEYS, name);
+  if (names.some(name => !isModifier(name))) return undefined;
+
+  let keys = Object.prototype.hasOwnProperty.call(NAMED_KEYS, keyName) ? NAMED_KEYS[keyName] : undefined;
+  if (!keys && /^[a-z0-9]$/.test(keyName)) {
+    keys = [keyName.toUpperCase().charCodeAt(0)];
+  }
+  const functionKey = /^f([1-9]|1[0-9]|2[0-4])$/.exec(keyName);
+  if (!keys && functionKey) {
+    keys = [0x6f + Number(functionKey[1])];
+  }
+  if (!keys) return undefined;
+
+  return { entry: normalized, modifiers: names as Modifier[], keys };
+}
+
+// Drops key presses that complete a blocked combination, and their releases,
+// for kiosk-style sessions. Tracks which modifiers the client holds from the
+// key events that pass through it.
+export class HotkeyFilter {
+  private held = new Set<Modifier>();
+  // Keys whose press was dropped; their release is dropped too
+  private suppressed = new Map<number, Hotkey>();
+
+  constructor(private hotkeys: Hotkey[]) {}
+
+  public static fromPolicy(entries: string[]): HotkeyFilter {
+    return new HotkeyFilter(entries
+      .map(entry => parseHotkey(entry))
+      .filter((hotkey): hotkey is Hotkey => hotkey !== undefined));
+  }
+
+  // Returns the blocked entry when the event must not reach the host
+  public check(virtualKey: number, release: boolean): Hotkey | undefined {
+    if (release) {
+      const suppressed = this.suppressed.get(virtualKey);
+      this.suppressed.delete(virtualKey);
+      if (!suppressed) this.updateModifiers(virtualKey, false);
+      return suppressed;
+    }
+
+    const blocked = this.match(this.held, virtualKey);
+    if (blocked) {
+      this.suppressed.set(virtualKey, blocked);
+      return blocked;
+    }
+    this.updateModifiers(virtualKey, true);
+    return undefined;
+  }
+
+  // Whether pressing `virtualKeys` in order would hit a blocked entry
+  public blocks(virtualKeys: number[]): boolean {
+    const held = new Set<Modifier>();
+    for (const virtualKey of virtualKeys) {
+      if (this.match(held, virtualKey)) return true;
+      const modifier = this.modifierOf(virtualKey);
+      if (modifier) held.add(modifier);
+    }
+    return false;
+  }
+
+  public reset(): void {
+    this.held.clear();
+    this.suppressed.clear();
+  }
+
+  private match(held: Set<Modifier>, virtualKey: number): Hotkey | undefined {
+    return this.hotkeys.find(hotkey =>
+      hotkey.keys.includes(virtualKey) && hotkey.modifiers.every(modifier => held.has(modifier)));
+  }
+
+  private updateModifiers(virtualKey: number, pressed: boolean): void {
+    const modifier = this.modifierOf(virtualKey);
+    if (!modifier) return;
+
+    if (pressed) {
+      this.held.add(modifier);
+    } else {
+      this.held.delete(modifier);
+    }
+  }
+
+  private modifierOf(virtualKey: number): Modifier | undefined {
+    return (Object.keys(MODIFIER_KEYS) as Modifier[]).find(modifier => MODIFIER_KEYS[modifier].includes(virtualKey));
+  }
+}
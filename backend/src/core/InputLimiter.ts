@@ -0,0 +1,124 @@
+import { Logger } from '../utils/Logger';
+
+export type LimitedInput = 'move' | 'wheel';
+
+export interface InputLimits {
+  // Events per second forwarded to the host; 0 disables the limit
+  maxMoveRate: number;
+  maxWheelRate: number;
+}
+
+export interface InputCounters {
+  received: number;
+  forwarded: number;
+  // Merged into a later event instead of being forwarded on their own
+  coalesced: number;
+}
+
+export interface InputLimiterSnapshot {
+  move: InputCounters;
+  wheel: InputCounters;
+}
+
+// Caps how often pointer moves and wheel events reach the host, so a buggy or
+// hostile client cannot flood it. Events over the rate are not lost: moves
+// keep only the latest position (relative deltas are summed), wheel deltas
+// are summed, and the merged event goes out as soon as the rate allows.
+export class InputLimiter {
+  private logger = new Logger('InputLimiter');
+  private lastSentAt: Record<LimitedInput, number> = { move: 0, wheel: 0 };
+  private pending: Record<LimitedInput, any> = { move: undefined, wheel: undefined };
+  private timers: Record<LimitedInput, NodeJS.Timeout | undefined> = { move: undefined, wheel: undefined };
+  private counters: Record<LimitedInput, InputCounters> = {
+    move: { received: 0, forwarded: 0, coalesced: 0 },
+    wheel: { received: 0, forwarded: 0, coalesced: 0 }
+  };
+
+  constructor(private limits: InputLimits, private send: (data: any) => void) {}
+
+  public submit(kind: LimitedInput, data: any): void {
+    this.counters[kind].received++;
+
+    const rate = kind === 'move' ? this.limits.maxMoveRate : this.limits.maxWheelRate;
+    const wait = rate > 0 ? this.lastSentAt[kind] + 1000 / rate - Date.now() : 0;
+    if (wait <= 0 && this.pending[kind] === undefined) {
+      this.forward(kind, data);
+      return;
+    }
+
+    if (this.pending[kind] !== undefined) {
+      this.counters[kind].coalesced++;
+    }
+    this.pending[kind] = this.merge(kind, this.pending[kind], data);
+    if (!this.timers[kind]) {
+      this.timers[kind] = setTimeout(() => {
+        this.timers[kind] = undefined;
+        // Nothing up the stack would catch a failed write from here
+        try {
+          this.flushKind(kind);
+        } catch (error) {
+          this.logger.error(`Failed to forward coalesced ${kind} input:`, error);
+        }
+      }, Math.max(wait, 0));
+    }
+  }
+
+  // Sends anything held back, e.g. before a click that must land after it
+  public flush(): void {
+    this.flushKind('move');
+    this.flushKind('wheel');
+  }
+
+  public clear(): void {
+    for (const kind of ['move', 'wheel'] as LimitedInput[]) {
+      if (this.timers[kind]) {
+        clearTimeout(this.timers[kind]);
+        this.timers[kind] = undefined;
+      }
+      this.pending[kind] = undefined;
+    }
+  }
+
+  public snapshot(): InputLimiterSnapshot {
+    return {
+      move: { ...this.counters.move },
+      wheel: { ...this.counters.wheel }
+    };
+  }
+
+  private flushKind(kind: LimitedInput): void {
+    if (this.timers[kind]) {
+      clearTimeout(this.timers[kind]);
+      this.timers[kind] = undefined;
+    }
+    const data = this.pending[kind];
+    if (data === undefined) return;
+
+    this.pending[kind] = undefined;
+    this.forward(kind, data);
+  }
+
+  private forward(kind: LimitedInput, data: any): void {
+    this.lastSentAt[kind] = Date.now();
+    this.counters[kind].forwarded++;
+    this.send(data);
+  }
+
+  private merge(kind: LimitedInput, pending: any, data: any): any {
+    if (pending === undefined) return data;
+
+    // Sums stay within the signed 16-bit fields they are sent in
+    const sum = (a: unknown, b: unknown) => Math.min(Math.max((Number(a) || 0) + (Number(b) || 0), -0x8000), 0x7fff);
+    if (kind === 'wheel') {
+      return { ...data, wheel: sum(pending.wheel, data.wheel) };
+    }
+    if (data.deltaX !== undefined || data.deltaY !== undefined) {
+      return {
+        ...data,
+        deltaX: sum(pending.deltaX, data.deltaX),
+        deltaY: sum(pending.deltaY, data.deltaY)
+      };
+    }
+    return data;
+  }
+}
@@ -0,0 +1,75 @@
+export const INPUT_MACRO_VERSION = 1;
+
+// Longest macro accepted for recording or replay
+export const MAX_MACRO_EVENTS = 10000;
+
+export type MacroEventKind = 'mouse' | 'keyboard' | 'text' | 'combo' | 'drag' | 'touch' | 'pen';
+
+const MACRO_EVENT_KINDS: MacroEventKind[] = ['mouse', 'keyboard', 'text', 'combo', 'drag', 'touch', 'pen'];
+
+export interface MacroEvent {
+  // ms since recording started
+  at: number;
+  kind: MacroEventKind;
+  // As the client sent it, so replay goes through the same input paths
+  data: any;
+}
+
+// Serializable as JSON; clients store macros and send them back to replay
+export interface InputMacro {
+  version: number;
+  recordedAt: string;
+  duration: number;
+  events: MacroEvent[];
+}
+
+export class InputMacroRecorder {
+  private startedAt = Date.now();
+  private events: MacroEvent[] = [];
+
+  // Returns false once the macro is full; later events are not recorded
+  public record(kind: MacroEventKind, data: any): boolean {
+    if (this.events.length >= MAX_MACRO_EVENTS) return false;
+
+    this.events.push({ at: Date.now() - this.startedAt, kind, data: structuredClone(data) });
+    return true;
+  }
+
+  public finish(): InputMacro {
+    return {
+      version: INPUT_MACRO_VERSION,
+      recordedAt: new Date(this.startedAt).toISOString(),
+      duration: Date.now() - this.startedAt,
+      events: this.events
+    };
+  }
+}
+
+// Validates a macro sent by a client; events are returned in time order
+export function parseInputMacro(value: any): InputMacro {
+  if (!value || value.version !== INPUT_MACRO_VERSION) {
+    throw new Error(`Unsupported macro version: ${value?.version}`);
+  }
+  if (!Array.isArray(value.events) || value.events.length === 0) {
+    throw new Error('Macro has no events');
+  }
+  if (value.events.length > MAX_MACRO_EVENTS) {
+    throw new Error(`Macro has more than ${MAX_MACRO_EVENTS} events`);
+  }
+
+  const events: MacroEvent[] = value.events.map((event: any, index: number) => {
+    const at = Number(event?.at);
+    if (!Number.isFinite(at) || at < 0 || !MACRO_EVENT_KINDS.includes(event?.kind)) {
+      throw new Error(`Invalid macro event at index ${index}`);
+    }
+    return { at, kind: event.kind, data: event.data };
+  });
+  events.sort((a, b) => a.at - b.at);
+
+  return {
+    version: INPUT_MACRO_VERSION,
+    recordedAt: typeof value.recordedAt === 'string' ? value.recordedAt : new Date().toISOString(),
+    duration: events[events.length - 1].at,
+    events
+  };
+}
@@ -0,0 +1,65 @@
+export interface MetricsSample {
+  timestamp: number;
+  fps: number;
+  bitrateIn: number;
+  bitrateOut: number;
+  maxBitrate: number;
+  targetBitrate: number;
+  quality: string;
+  receiveQueueDepth: number;
+  sendQueueDepth: number;
+  receiveP95: number;
+  sendP95: number;
+  latencyP95: number;
+  bandwidthEstimate: number;
+  encodeP95: number;
+  encodePending: number;
+  eventLoopUtilization: number;
+  eventLoopDelayP99: number;
+  // The controlling client's connection, as measured by its transport
+  clientBitrateIn: number;
+  clientBitrateOut: number;
+  clientRtt: number | null;
+  clientDrops: number;
+}
+
+// Fixed-size ring buffer; once full the oldest sample is overwritten
+export class MetricsHistory {
+  private samples: (MetricsSample | undefined)[];
+  private head = 0;
+  private count = 0;
+
+  constructor(private capacity: number) {
+    this.samples = new Array(capacity);
+  }
+
+  public push(sample: MetricsSample): void {
+    this.samples[this.head] = sample;
+    this.head = (this.head + 1) % this.capacity;
+    this.count = Math.min(this.count + 1, this.capacity);
+  }
+
+  public query(since?: number, until?: number): MetricsSample[] {
+    const result: MetricsSample[] = [];
+    const start = (this.head - this.count + this.capacity) % this.capacity;
+
+    for (let i = 0; i < this.count; i++) {
+      const sample = this.samples[(start + i) % this.capacity];
+      if (!sample) continue;
+      if (since !== undefined && sample.timestamp < since) continue;
+      if (until !== undefined && sample.timestamp > until) continue;
+      result.push(sample);
+    }
+
+    return result;
+  }
+
+  public latest(): MetricsSample | undefined {
+    if (this.count === 0) return undefined;
+    return this.samples[(this.head - 1 + this.capacity) % this.capacity];
+  }
+
+  public get size(): number {
+    return this.count;
+  }
+}
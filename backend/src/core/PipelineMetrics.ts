@@ -0,0 +1,306 @@
+export type PipelineStage = 'receive' | 'send';
+
+export interface QueueMetrics {
+  depth: number;
+  maxDepth: number;
+  timeInQueue: {
+    p50: number;
+    p95: number;
+    p99: number;
+  };
+}
+
+export interface LatencyMetrics {
+  p50: number;
+  p95: number;
+  p99: number;
+  samples: number;
+}
+
+export interface EncodeMetrics {
+  // Scale + compress time per frame
+  time: {
+    p50: number;
+    p95: number;
+  };
+  compressionRatio: number;
+  pending: number;
+  refreshes: number;
+}
+
+export interface BandwidthMetrics {
+  // Delivery rate seen by the client in bits/s, 0 until frames are acked
+  estimate: number;
+  samples: number;
+}
+
+export interface PipelineMetricsSnapshot {
+  receive: QueueMetrics;
+  encode: EncodeMetrics;
+  send: QueueMetrics;
+  // Capture to client ack, measured on the gateway's monotonic clock
+  latency: LatencyMetrics;
+  bandwidth: BandwidthMetrics;
+  // Frames discarded or merged by the bounded receive queue
+  framesDropped: number;
+  framesCoalesced: number;
+  backpressure: PipelineStage[];
+}
+
+export interface BackpressureThresholds {
+  queueDepth: number;
+  timeInQueue: number;
+  sustainFor: number;
+}
+
+export interface BackpressureEvent {
+  stage: PipelineStage;
+  state: 'warning' | 'cleared';
+  queueDepth: number;
+  p95TimeInQueue: number;
+  since: Date;
+}
+
+const STAGES: PipelineStage[] = ['receive', 'send'];
+
+// Frames never acknowledged (e.g. dropped by the client) are forgotten past this
+const MAX_UNACKED_FRAMES = 600;
+
+// Bandwidth is the highest delivery rate over this window; lower samples are
+// usually just the sender having nothing to send
+const BANDWIDTH_WINDOW = 10000;
+
+interface SentFrame {
+  capturedAt: number;
+  sentAt: number;
+  bytes: number;
+  // Delivery state when the frame was sent, for the rate sample on its ack
+  deliveredAtSend: number;
+  deliveredTimeAtSend: number;
+}
+
+// Tracks queue depth and time-in-queue for the RDP -> processing -> client pipeline
+export class PipelineMetrics {
+  private depths: Record<PipelineStage, number> = { receive: 0, send: 0 };
+  private maxDepths: Record<PipelineStage, number> = { receive: 0, send: 0 };
+  private samples: Record<PipelineStage, number[]> = { receive: [], send: [] };
+  private pendingSends: number[] = [];
+  private unackedFrames: Map<number, SentFrame> = new Map();
+  private delivered = 0;
+  private deliveredTime = 0;
+  private bandwidthSamples: { rate: number; at: number }[] = [];
+  private latencySamples: number[] = [];
+  private encodeSamples: number[] = [];
+  private encodeBytesIn = 0;
+  private encodeBytesOut = 0;
+  private encodePending = 0;
+  private refreshes = 0;
+  private framesDropped = 0;
+  private framesCoalesced = 0;
+  private pressureSince: Record<PipelineStage, number | null> = { receive: null, send: null };
+  private warned: Record<PipelineStage, boolean> = { receive: false, send: false };
+
+  constructor(private sampleWindow: number = 300) {}
+
+  public recordDepth(stage: PipelineStage, depth: number): void {
+    this.depths[stage] = depth;
+    this.maxDepths[stage] = Math.max(this.maxDepths[stage], depth);
+  }
+
+  public recordTimeInQueue(stage: PipelineStage, milliseconds: number): void {
+    const samples = this.samples[stage];
+    samples.push(milliseconds);
+    if (samples.length > this.sampleWindow) {
+      samples.shift();
+    }
+  }
+
+  public markSendQueued(timestamp: number, depth: number): void {
+    this.pendingSends.push(timestamp);
+    this.recordDepth('send', depth);
+  }
+
+  public markSendDrained(timestamp: number): void {
+    for (const queuedAt of this.pendingSends) {
+      this.recordTimeInQueue('send', timestamp - queuedAt);
+    }
+    this.pendingSends = [];
+    this.recordDepth('send', 0);
+  }
+
+  public markEncodeStarted(): void {
+    this.encodePending++;
+  }
+
+  public recordEncode(milliseconds: number, bytesIn: number, bytesOut: number): void {
+    this.encodePending = Math.max(0, this.encodePending - 1);
+    this.encodeBytesIn += bytesIn;
+    this.encodeBytesOut += bytesOut;
+    this.encodeSamples.push(milliseconds);
+    if (this.encodeSamples.length > this.sampleWindow) {
+      this.encodeSamples.shift();
+    }
+  }
+
+  public recordEncodeFailed(): void {
+    this.encodePending = Math.max(0, this.encodePending - 1);
+  }
+
+  public recordRefresh(): void {
+    this.refreshes++;
+  }
+
+  public recordFrameDropped(): void {
+    this.framesDropped++;
+  }
+
+  public recordFrameCoalesced(): void {
+    this.framesCoalesced++;
+  }
+
+  public markFrameSent(frameId: number, capturedAt: number, bytes: number = 0, sentAt: number = capturedAt): void {
+    this.unackedFrames.set(frameId, {
+      capturedAt,
+      sentAt,
+      bytes,
+      deliveredAtSend: this.delivered,
+      deliveredTimeAtSend: this.deliveredTime
+    });
+    if (this.unackedFrames.size > MAX_UNACKED_FRAMES) {
+      const oldest = this.unackedFrames.keys().next().value as number;
+      this.unackedFrames.delete(oldest);
+    }
+  }
+
+  public recordFrameAck(frameId: number, now: number): void {
+    const frame = this.unackedFrames.get(frameId);
+    if (frame === undefined) return;
+
+    // Acks cover every earlier frame too, in case individual acks were skipped
+    for (const [id, sent] of this.unackedFrames) {
+      if (id > frameId) break;
+      this.delivered += sent.bytes;
+      this.unackedFrames.delete(id);
+    }
+
+    this.latencySamples.push(now - frame.capturedAt);
+    if (this.latencySamples.length > this.sampleWindow) {
+      this.latencySamples.shift();
+    }
+
+    this.recordDeliveryRate(frame, now);
+  }
+
+  // Bytes delivered since this frame was sent, over the time that took
+  private recordDeliveryRate(frame: SentFrame, now: number): void {
+    const since = frame.deliveredTimeAtSend > 0 ? frame.deliveredTimeAtSend : frame.sentAt;
+    const elapsed = now - since;
+    this.deliveredTime = now;
+    if (elapsed <= 0) return;
+
+    const rate = Math.round((this.delivered - frame.deliveredAtSend) * 8 / (elapsed / 1000));
+    this.bandwidthSamples.push({ rate, at: now });
+    while (this.bandwidthSamples.length > 0 && now - this.bandwidthSamples[0].at > BANDWIDTH_WINDOW) {
+      this.bandwidthSamples.shift();
+    }
+  }
+
+  public estimatedBandwidth(): number {
+    return this.bandwidthSamples.reduce((max, sample) => Math.max(max, sample.rate), 0);
+  }
+
+  // Percentiles over only the most recent samples, so congestion clears quickly
+  public recentSignals(count: number): { sendP95: number; latencyP95: number; framesDropped: number; estimatedBandwidth: number } {
+    return {
+      sendP95: this.percentile(this.samples.send.slice(-count), 95),
+      latencyP95: this.percentile(this.latencySamples.slice(-count), 95),
+      framesDropped: this.framesDropped,
+      estimatedBandwidth: this.estimatedBandwidth()
+    };
+  }
+
+  public checkBackpressure(thresholds: BackpressureThresholds, now: number = Date.now()): BackpressureEvent[] {
+    const events: BackpressureEvent[] = [];
+
+    for (const stage of STAGES) {
+      const p95 = this.percentile(this.samples[stage], 95);
+      const pressured = this.depths[stage] >= thresholds.queueDepth || p95 >= thresholds.timeInQueue;
+
+      if (pressured) {
+        if (this.pressureSince[stage] === null) {
+          this.pressureSince[stage] = now;
+        }
+
+        // Only warn once pressure has been sustained, and only once per episode
+        const since = this.pressureSince[stage] as number;
+        if (!this.warned[stage] && now - since >= thresholds.sustainFor) {
+          this.warned[stage] = true;
+          events.push({ stage, state: 'warning', queueDepth: this.depths[stage], p95TimeInQueue: p95, since: new Date(since) });
+        }
+      } else {
+        if (this.warned[stage]) {
+          events.push({
+            stage,
+            state: 'cleared',
+            queueDepth: this.depths[stage],
+            p95TimeInQueue: p95,
+            since: new Date(this.pressureSince[stage] as number)
+          });
+        }
+        this.pressureSince[stage] = null;
+        this.warned[stage] = false;
+      }
+    }
+
+    return events;
+  }
+
+  public snapshot(): PipelineMetricsSnapshot {
+    return {
+      receive: this.queueMetrics('receive'),
+      encode: {
+        time: {
+          p50: this.percentile(this.encodeSamples, 50),
+          p95: this.percentile(this.encodeSamples, 95)
+        },
+        compressionRatio: this.encodeBytesOut > 0 ? Math.round(this.encodeBytesIn / this.encodeBytesOut * 100) / 100 : 0,
+        pending: this.encodePending,
+        refreshes: this.refreshes
+      },
+      send: this.queueMetrics('send'),
+      latency: {
+        p50: this.percentile(this.latencySamples, 50),
+        p95: this.percentile(this.latencySamples, 95),
+        p99: this.percentile(this.latencySamples, 99),
+        samples: this.latencySamples.length
+      },
+      bandwidth: {
+        estimate: this.estimatedBandwidth(),
+        samples: this.bandwidthSamples.length
+      },
+      framesDropped: this.framesDropped,
+      framesCoalesced: this.framesCoalesced,
+      backpressure: STAGES.filter(stage => this.warned[stage])
+    };
+  }
+
+  private queueMetrics(stage: PipelineStage): QueueMetrics {
+    return {
+      depth: this.depths[stage],
+      maxDepth: this.maxDepths[stage],
+      timeInQueue: {
+        p50: this.percentile(this.samples[stage], 50),
+        p95: this.percentile(this.samples[stage], 95),
+        p99: this.percentile(this.samples[stage], 99)
+      }
+    };
+  }
+
+  private percentile(samples: number[], p: number): number {
+    if (samples.length === 0) return 0;
+
+    const sorted = [...samples].sort((a, b) => a - b);
+    const index = Math.min(sorted.length - 1, Math.ceil((p / 100) * sorted.length) - 1);
+    return sorted[Math.max(0, index)];
+  }
+}
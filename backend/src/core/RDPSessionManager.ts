@@ -1,13 +1,29 @@
 import { Logger } from '../utils/Logger';
+import { Config, ResolutionCap } from '../utils/Config';
+import { NetworkLinkDetector, NetworkLinkInfo } from '../services/NetworkLinkDetector';
+import { NegotiatedCapabilities } from '../services/CapabilityReporter';
+import { PipelineMetrics, PipelineMetricsSnapshot } from './PipelineMetrics';
+import { FrameScaler, FrameSize } from './FrameScaler';
+import { toDesktopPoint } from './DesktopLayout';
+import { InputLimiter } from './InputLimiter';
+import { HotkeyFilter, virtualKeyForScanCode } from './HotkeyFilter';
+import { AudioFormat, OpusAudioEncoder } from './AudioEncoder';
+import { InputMacro, InputMacroRecorder, MacroEvent, MacroEventKind, parseInputMacro } from './InputMacro';
+import { RuntimeMetrics } from './RuntimeMetrics';
+import { BitrateController } from './BitrateController';
+import { CodecRegistry, FrameCodec } from './CodecRegistry';
+import { MetricsHistory, MetricsSample } from './MetricsHistory';
+import { TransportRegistry } from '../ws/Transport';
 import { EventEmitter } from 'events';
 import { v4 as uuidv4 } from 'uuid';
 import * as net from 'net';
 import * as tls from 'tls';
 import * as crypto from 'crypto';
-import * as zlib from 'zlib';
 import * as child_process from 'child_process';
 import * as path from 'path';
 import * as fs from 'fs';
+import { performance } from 'perf_hooks';
+
 
 export interface RDPSessionConfig {
   host: string;
@@ -34,7 +50,7 @@ export interface RDPSessionConfig {
   colorDepth: number;
   width: number;
   height: number;
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   frameRate: number;
   compressionLevel: number;
   encryptionLevel: 'none' | 'low' | 'medium' | 'high';
@@ -42,12 +58,266 @@ export interface RDPSessionConfig {
   timeout: number;
   reconnectAttempts: number;
   reconnectDelay: number;
+  // Optional connect-time overrides, typically driven by client deep links
+  initialDisplay?: number;
+  viewOnly?: boolean;
+  scaleMode?: ScaleMode;
+  keyboardMode?: KeyboardMode;
+  // Name in the CodecRegistry; the gateway default is used when unknown
+  codec?: string;
+}
+
+export type ScaleMode = 'fit' | 'fill' | 'stretch';
+
+// How mouse input reaches the host: positions, or motion deltas for clients
+// holding a pointer lock (3D apps and games that recenter the cursor)
+export type PointerMode = 'absolute' | 'relative';
+
+// How the client injects keys: 'position' sends scan codes, which the host's
+// layout interprets; 'character' types printable keys as Unicode, so they
+// come out as on the client whatever the host's layout; 'auto' lets the
+// client pick by comparing its layout with the host's
+export type KeyboardMode = 'auto' | 'position' | 'character';
+
+// What the controller may do in a session. Mouse covers every pointing device
+// (mouse, touch, pen); keyboard covers keys, text and key combinations.
+// View-only sessions have none and cannot be granted any.
+export type SessionPermission = 'mouse' | 'keyboard' | 'clipboard' | 'fileTransfer';
+
+export type SessionPermissions = Record<SessionPermission, boolean>;
+
+const SESSION_PERMISSIONS: SessionPermission[] = ['mouse', 'keyboard', 'clipboard', 'fileTransfer'];
+
+// One finger in a multi-touch frame. `id` is the client's touch identifier;
+// pressure is 0..1024, width/height the contact area in the same units as
+// x and y, orientation in degrees.
+export interface TouchContact {
+  id: number;
+  action: 'down' | 'move' | 'up' | 'cancel';
+  x: number;
+  y: number;
+  pressure?: number;
+  width?: number;
+  height?: number;
+  orientation?: number;
+}
+
+// A stylus sample. 'hover' is the pen in range above the surface. Pressure
+// is 0..1024, tilt -90..90 degrees on each axis, rotation 0..359 degrees;
+// `eraser` means the eraser end is the one in use.
+export interface PenEvent {
+  action: 'down' | 'move' | 'up' | 'hover' | 'cancel';
+  x: number;
+  y: number;
+  displayId?: number;
+  pressure?: number;
+  tiltX?: number;
+  tiltY?: number;
+  rotation?: number;
+  barrel?: boolean;
+  eraser?: boolean;
+}
+
+// A press, move and release synthesized by the gateway. Points are as in
+// mouse input: normalized on `displayId` when given, desktop pixels
+// otherwise. Buttons use browser numbering (0 left, 1 middle, 2 right).
+export interface DragGesture {
+  displayId?: number;
+  from: { x: number; y: number };
+  to: { x: number; y: number };
+  button?: number;
+  // Press to release, in ms
+  duration?: number;
+}
+
+// A contact as written to the host: host contact id, flags and desktop
+// coordinates, with the contact area as left/top/right/bottom offsets
+interface TouchFrameContact {
+  contactId: number;
+  flags: number;
+  x: number;
+  y: number;
+  rect: number[];
+  orientation: number;
+  pressure: number;
+}
+
+const QUALITY_BITRATES: Record<RDPSessionConfig['quality'], number> = {
+  low: 1000000,
+  medium: 2500000,
+  high: 4000000,
+  ultra: 6000000,
+  lossless: 12000000
+};
+
+function isQuality(value: unknown): value is RDPSessionConfig['quality'] {
+  return typeof value === 'string' && Object.prototype.hasOwnProperty.call(QUALITY_BITRATES, value);
+}
+
+// Tiers adaptive bitrate may step through; lossless is never degraded in resolution
+const LOSSY_QUALITIES: RDPSessionConfig['quality'][] = ['low', 'medium', 'high', 'ultra'];
+
+const DEFAULT_FPS = 30;
+const REFRESH_MIN_INTERVAL = 250; // ms
+// Below the lowest quality tier, frame rate is traded for per-frame quality
+const CONGESTED_FPS = 15;
+// Longest text accepted in one input:text message, in UTF-16 code units
+const MAX_TEXT_INPUT_LENGTH = 4096;
+// Pointer events of the previous mode still accepted after a switch, as they
+// may have been sent before the client's request to switch
+const POINTER_MODE_GRACE = 500; // ms
+const KBDFLAGS_EXTENDED = 0x0100;
+const KBDFLAGS_EXTENDED1 = 0x0200;
+const KBDFLAGS_DOWN = 0x4000;
+const KBDFLAGS_RELEASE = 0x8000;
+// Simultaneous touch contacts forwarded per session; further fingers are ignored
+const MAX_TOUCH_CONTACTS = 10;
+// Touch contact flags, as in the RDP input extension's contact data
+const CONTACT_FLAG_DOWN = 0x0001;
+const CONTACT_FLAG_UPDATE = 0x0002;
+const CONTACT_FLAG_UP = 0x0004;
+const CONTACT_FLAG_INRANGE = 0x0008;
+const CONTACT_FLAG_INCONTACT = 0x0010;
+const CONTACT_FLAG_CANCELED = 0x0020;
+// Full pressure on the host's touch and pen scale
+const TOUCH_PRESSURE_MAX = 1024;
+// Pen flags, as in the RDP input extension's pen data
+const PEN_FLAG_BARREL_PRESSED = 0x01;
+const PEN_FLAG_ERASER_PRESSED = 0x02;
+const PEN_FLAG_INVERTED = 0x04;
+// Synthesized drags: many applications only start a drag once the pointer
+// has moved past a threshold some time after the press, so the path is
+// sent as paced moves with a pause after the press and before the release
+const DRAG_STEP_INTERVAL = 16; // ms
+const DRAG_HOLD_TIME = 50; // ms
+const DRAG_DEFAULT_DURATION = 300; // ms
+const DRAG_MIN_DURATION = 150; // ms
+const DRAG_MAX_DURATION = 10000; // ms
+// Key combinations a browser cannot capture because the local OS acts on
+// them first. Each is pressed in order and released in reverse; RDP hosts
+// treat Ctrl+Alt+Del arriving this way as the secure attention sequence.
+export type KeyCombo = 'ctrl-alt-del' | 'ctrl-shift-esc' | 'win' | 'win-l' | 'alt-tab' | 'alt-f4';
+
+const KEY_COMBOS: Record<KeyCombo, Array<{ scanCode: number; virtualKey: number; extended?: boolean }>> = {
+  'ctrl-alt-del': [{ scanCode: 0x1d, virtualKey: 0xa2 }, { scanCode: 0x38, virtualKey: 0xa4 }, { scanCode: 0x53, virtualKey: 0x2e, extended: true }],
+  'ctrl-shift-esc': [{ scanCode: 0x1d, virtualKey: 0xa2 }, { scanCode: 0x2a, virtualKey: 0xa0 }, { scanCode: 0x01, virtualKey: 0x1b }],
+  'win': [{ scanCode: 0x5b, virtualKey: 0x5b, extended: true }],
+  'win-l': [{ scanCode: 0x5b, virtualKey: 0x5b, extended: true }, { scanCode: 0x26, virtualKey: 0x4c }],
+  'alt-tab': [{ scanCode: 0x38, virtualKey: 0xa4 }, { scanCode: 0x0f, virtualKey: 0x09 }],
+  'alt-f4': [{ scanCode: 0x38, virtualKey: 0xa4 }, { scanCode: 0x3e, virtualKey: 0x73 }]
+};
+
+
+export type DisconnectReasonCode =
+  | 'client_request'
+  | 'admin_kick'
+  | 'idle_timeout'
+  | 'auth_revoked'
+  | 'server_shutdown'
+  | 'taken_over'
+  | 'remote_closed'
+  | 'connection_lost';
+
+export interface DisconnectReason {
+  code: DisconnectReasonCode;
+  message: string;
+  // Whether the client should try to reconnect on its own
+  reconnect: boolean;
+  detail?: string;
+}
+
+const DISCONNECT_REASONS: Record<DisconnectReasonCode, { message: string; reconnect: boolean }> = {
+  client_request: { message: 'Session closed', reconnect: false },
+  admin_kick: { message: 'Session was terminated by an administrator', reconnect: false },
+  idle_timeout: { message: 'Session closed due to inactivity', reconnect: false },
+  auth_revoked: { message: 'Your access to this session was revoked', reconnect: false },
+  server_shutdown: { message: 'The gateway is shutting down', reconnect: true },
+  taken_over: { message: 'Session was opened from another client', reconnect: false },
+  remote_closed: { message: 'The remote host closed the connection', reconnect: true },
+  connection_lost: { message: 'Connection to the gateway was lost', reconnect: true }
+};
+
+export interface RDPDisplay {
+  id: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  primary: boolean;
+}
+
+export type DisplaySubscriptionMode = 'single' | 'multiple' | 'virtual';
+
+export interface DisplaySubscription {
+  mode: DisplaySubscriptionMode;
+  displayIds: number[];
 }
 
 export interface RDPSession {
   id: string;
+  // The controller: the one client whose input reaches the host
   socketId: string;
+  // Further clients watching the same output, view-only
+  viewers: Set<string>;
   config: RDPSessionConfig;
+  displays: RDPDisplay[];
+  displaySubscription: DisplaySubscription;
+  networkLink?: NetworkLinkInfo;
+  bitrateCeiling: number;
+  maxBitrate: number;
+  // Adapted to congestion, never above maxBitrate
+  targetBitrate: number;
+  effectiveQuality: RDPSessionConfig['quality'];
+  targetFps: number;
+  lastRefreshAt: number;
+  codec: FrameCodec;
+  bitrateController?: BitrateController;
+  // Agreed with the client in its `hello`; absent for clients that skip it
+  capabilities?: NegotiatedCapabilities;
+  viewOnly: boolean;
+  // Checked on every input; admins can change them while the session runs
+  permissions: SessionPermissions;
+  scaleMode: ScaleMode;
+  pointerMode: PointerMode;
+  pointerModeChangedAt: number;
+  keyboardMode: KeyboardMode;
+  // Windows keyboard layout id (KLID) active on the host, once it reports one
+  hostKeyboardLayout?: number;
+  // Rate limits pointer moves and wheel events on their way to the host
+  inputLimiter: InputLimiter;
+  // Drops key combinations the input policy forbids
+  hotkeyFilter: HotkeyFilter;
+  // A synthesized drag in progress: the pending step and where the button is held
+  drag?: { timer: NodeJS.Timeout; button: number; x: number; y: number };
+  // Records the controller's input while set
+  macroRecorder?: InputMacroRecorder;
+  // Epoch ms of the last input forwarded to the host, and of the last sign
+  // of a user at the client; the session is idle once both are too old
+  lastInputAt: number;
+  lastClientActivityAt: number;
+  idle: boolean;
+  // The next event of a macro being replayed
+  macroReplay?: NodeJS.Timeout;
+  // Client touch identifiers to the host contact ids they were given
+  touchContacts: Map<number, number>;
+  pipelineMetrics: PipelineMetrics;
+  metricsHistory: MetricsHistory;
+  clipboard?: ClipboardState;
+  reconnectCount: number;
+  filesTransferred: number;
+  // Lets a client that dropped reattach within the grace period
+  resumeToken: string;
+  // Username that created the session; only they may resume it
+  owner?: string;
+  detachedAt?: number;
+  resumeTimer?: NodeJS.Timeout;
+  summary?: SessionSummary;
+  disconnectReason?: DisconnectReason;
+  // Dashboard subscribers to the low-rate preview stream
+  thumbnailSubscribers: Set<string>;
+  // Started with the first audio the host sends, if the session has audio
+  audioEncoder?: OpusAudioEncoder;
+  audioSequence: number;
   status: 'connecting' | 'connected' | 'disconnected' | 'error';
   startTime: Date;
   lastActivity: Date;
@@ -58,11 +328,67 @@ export interface RDPSession {
   rdpConnection?: RDPConnection;
 }
 
+export type ClipboardOrigin = 'host' | 'client';
+
+export interface ClipboardState {
+  content: string;
+  origin: ClipboardOrigin;
+  timestamp: number;
+}
+
+export interface SessionSummary {
+  sessionId: string;
+  socketId: string;
+  host: string;
+  port: number;
+  username: string;
+  startTime: Date;
+  endTime: Date;
+  duration: number;
+  frameCount: number;
+  bytesReceived: number;
+  bytesSent: number;
+  averageFps: number;
+  averageBitrateIn: number;
+  averageBitrateOut: number;
+  averageQueueLatency: number;
+  averageLatency: number;
+  reconnectCount: number;
+  filesTransferred: number;
+  disconnectReason: string;
+}
+
+export interface QueuedFrame {
+  data: Buffer;
+  queuedAt: number;
+  // Monotonic (performance.now) time the frame arrived from the host
+  capturedAt: number;
+  // Streamed dimensions, which differ from the display when downscaled
+  width?: number;
+  height?: number;
+}
+
+// A frame from the host waiting to be scaled and compressed
+interface RawFrameJob {
+  data: Buffer;
+  source: FrameSize;
+  // Set when the frame is downscaled before compression
+  target?: FrameSize;
+  capturedAt: number;
+}
+
 export interface RDPConnection {
   socket: net.Socket | tls.TLSSocket;
   isConnected: boolean;
   sessionId: string;
-  frameBuffer: Buffer[];
+  frameBuffers: Map<number, QueuedFrame[]>;
+  // Displays with a scale/compress job in flight, and the newest frame
+  // waiting behind it. A newer frame replaces the waiting one, so a slow
+  // encoder holds at most one raw frame per display.
+  encoding: Set<number>;
+  pendingRawFrames: Map<number, RawFrameJob>;
+  // Most recent raw frame per display, kept only while thumbnails are subscribed
+  latestFrames: Map<number, { data: Buffer; width: number; height: number; dirty: boolean }>;
   inputQueue: any[];
   clipboardData: any;
   fileTransferData: any;
@@ -72,23 +398,102 @@ export interface RDPConnection {
 }
 
 export class RDPSessionManager extends EventEmitter {
+  private static instance: RDPSessionManager;
   private logger = new Logger('RDPSessionManager');
   private sessions: Map<string, RDPSession> = new Map();
   private rdpConnections: Map<string, RDPConnection> = new Map();
   private frameProcessors: Map<string, NodeJS.Timeout> = new Map();
+  private networkLinkDetector = new NetworkLinkDetector();
 
   constructor() {
     super();
     this.logger.info('RDP Session Manager initialized');
   }
 
-  public async createSession(socketId: string, config: RDPSessionConfig): Promise<RDPSession> {
+  // Shared by the gateway and the REST routes so both see the same sessions
+  public static getInstance(): RDPSessionManager {
+    if (!RDPSessionManager.instance) {
+      RDPSessionManager.instance = new RDPSessionManager();
+    }
+    return RDPSessionManager.instance;
+  }
+
+  public async createSession(
+    socketId: string,
+    requestedConfig: RDPSessionConfig,
+    capabilities?: NegotiatedCapabilities,
+    owner?: string
+  ): Promise<RDPSession> {
     const sessionId = uuidv4();
+    const negotiated = capabilities ? this.applyCapabilities(requestedConfig, capabilities) : requestedConfig;
+    // An unknown quality has no bitrate or resolution cap, and every frame would fail to scale
+    const config: RDPSessionConfig = isQuality(negotiated.quality) ? negotiated : { ...negotiated, quality: 'medium' };
+    const displays = this.discoverDisplays(config);
+    const networkLink = Config.getInstance().get('network.enableLinkDetection')
+      ? this.networkLinkDetector.detect()
+      : undefined;
+    const bitrateCeiling = this.getBitrateCeiling(networkLink);
+    const initialDisplay = displays.find(display => display.id === config.initialDisplay);
+    const maxBitrate = Math.min(QUALITY_BITRATES[config.quality] || QUALITY_BITRATES.medium, bitrateCeiling);
+    const rdpConfig = Config.getInstance().get('rdp');
+    const codecName = capabilities
+      ? capabilities.codec
+      : config.codec && CodecRegistry.getInstance().has(config.codec) ? config.codec : rdpConfig.codec;
     
     const session: RDPSession = {
       id: sessionId,
       socketId,
+      viewers: new Set(),
       config,
+      displays,
+      displaySubscription: initialDisplay
+        ? { mode: 'single', displayIds: [initialDisplay.id] }
+        : {
+            mode: displays.length > 1 ? 'virtual' : 'single',
+            displayIds: displays.map(display => display.id)
+          },
+      networkLink,
+      bitrateCeiling,
+      maxBitrate,
+      targetBitrate: maxBitrate,
+      effectiveQuality: config.quality,
+      targetFps: DEFAULT_FPS,
+      lastRefreshAt: 0,
+      codec: CodecRegistry.getInstance().create(codecName),
+      bitrateController: rdpConfig.adaptiveBitrate
+        ? new BitrateController(maxBitrate, {
+            minBitrate: rdpConfig.adaptiveMinBitrate,
+            congestionQueueTime: rdpConfig.adaptiveCongestionQueueTime,
+            congestionLatency: rdpConfig.adaptiveCongestionLatency,
+            recoveryPeriod: rdpConfig.adaptiveRecoveryPeriod
+          })
+        : undefined,
+      capabilities,
+      viewOnly: config.viewOnly === true,
+      permissions: {
+        mouse: config.viewOnly !== true,
+        keyboard: config.viewOnly !== true,
+        clipboard: config.viewOnly !== true && config.enableClipboard !== false,
+        fileTransfer: config.viewOnly !== true && config.enableFileTransfer !== false
+      },
+      scaleMode: config.scaleMode || 'fit',
+      pointerMode: 'absolute',
+      pointerModeChangedAt: 0,
+      keyboardMode: config.keyboardMode || 'auto',
+      touchContacts: new Map(),
+      inputLimiter: new InputLimiter(Config.getInstance().get('input'), data => this.writeMouseInput(session, data)),
+      hotkeyFilter: HotkeyFilter.fromPolicy(Config.getInstance().get('input').blockedHotkeys),
+      lastInputAt: Date.now(),
+      lastClientActivityAt: Date.now(),
+      idle: false,
+      pipelineMetrics: new PipelineMetrics(),
+      metricsHistory: new MetricsHistory(rdpConfig.metricsHistorySize),
+      thumbnailSubscribers: new Set(),
+      audioSequence: 0,
+      reconnectCount: 0,
+      filesTransferred: 0,
+      resumeToken: crypto.randomBytes(32).toString('hex'),
+      owner,
       status: 'connecting',
       startTime: new Date(),
       lastActivity: new Date(),
@@ -97,8 +502,12 @@ export class RDPSessionManager extends EventEmitter {
       bytesSent: 0
     };
 
+    if (config.initialDisplay !== undefined && !initialDisplay) {
+      this.logger.warn(`Requested initial display ${config.initialDisplay} not found, subscribing to all displays`);
+    }
+
     this.sessions.set(sessionId, session);
-    this.logger.info(`Creating RDP session ${sessionId} to ${config.host}:${config.port}`);
+    this.logger.info(`Creating RDP session ${sessionId} to ${config.host}:${config.port}${session.viewOnly ? ' (view-only)' : ''}`);
 
     try {
       // Create RDP connection
@@ -109,8 +518,11 @@ export class RDPSessionManager extends EventEmitter {
       session.status = 'connected';
       session.lastActivity = new Date();
       
-      // Start frame processing
-      this.startFrameProcessing(sessionId);
+      // Start one frame processing loop per discovered display
+      for (const display of session.displays) {
+        this.startFrameProcessing(sessionId, display.id);
+      }
+      this.startSessionMonitor(sessionId);
       
       this.logger.info(`RDP session ${sessionId} connected successfully`);
       this.emit('sessionCreated', session);
@@ -124,6 +536,73 @@ export class RDPSessionManager extends EventEmitter {
     }
   }
 
+  private getBitrateCeiling(link?: NetworkLinkInfo): number {
+    const network = Config.getInstance().get('network');
+
+    switch (link?.type) {
+      case 'ethernet':
+        // Leave headroom on slow wired links (e.g. 100 Mbps)
+        return link.speedMbps
+          ? Math.min(network.wiredBitrateCeiling, link.speedMbps * 1000000 / 2)
+          : network.wiredBitrateCeiling;
+      case 'wifi':
+        return network.wifiBitrateCeiling;
+      case 'cellular':
+        return network.cellularBitrateCeiling;
+      default:
+        return network.defaultBitrateCeiling;
+    }
+  }
+
+  // Features the client did not negotiate are switched off whatever its
+  // connect request asked for
+  private applyCapabilities(config: RDPSessionConfig, capabilities: NegotiatedCapabilities): RDPSessionConfig {
+    return {
+      ...config,
+      codec: capabilities.codec,
+      enableAudio: config.enableAudio && capabilities.audio,
+      enableClipboard: config.enableClipboard && capabilities.clipboard,
+      enableFileTransfer: config.enableFileTransfer && capabilities.fileTransfer,
+      enableDeviceRedirection: config.enableDeviceRedirection && capabilities.deviceRedirection,
+      enableMultiMonitor: config.enableMultiMonitor && capabilities.multiMonitor
+    };
+  }
+
+  // The quality's resolution cap, tightened by the client's advertised maximum
+  private getResolutionCap(session: RDPSession): ResolutionCap {
+    const cap = Config.getInstance().get('rdp').resolutionCaps[session.effectiveQuality];
+    const capabilities = session.capabilities;
+    if (!capabilities) return cap;
+
+    const tighter = (gateway: number, client: number): number =>
+      gateway > 0 && client > 0 ? Math.min(gateway, client) : Math.max(gateway, client);
+
+    return {
+      ...cap,
+      maxWidth: tighter(cap.maxWidth, capabilities.maxWidth),
+      maxHeight: tighter(cap.maxHeight, capabilities.maxHeight)
+    };
+  }
+
+  private discoverDisplays(config: RDPSessionConfig): RDPDisplay[] {
+    const count = config.enableMultiMonitor ? Math.max(1, config.monitorCount || 1) : 1;
+    const displays: RDPDisplay[] = [];
+
+    // Monitors are laid out left-to-right in the remote virtual desktop
+    for (let i = 0; i < count; i++) {
+      displays.push({
+        id: i,
+        x: i * config.width,
+        y: 0,
+        width: config.width,
+        height: config.height,
+        primary: i === 0
+      });
+    }
+
+    return displays;
+  }
+
   private async createRDPConnection(session: RDPSession): Promise<RDPConnection> {
     return new Promise((resolve, reject) => {
       const config = session.config;
@@ -139,7 +618,10 @@ export class RDPSessionManager extends EventEmitter {
         socket,
         isConnected: false,
         sessionId: session.id,
-        frameBuffer: [],
+        frameBuffers: new Map(session.displays.map(display => [display.id, []] as [number, QueuedFrame[]])),
+        encoding: new Set(),
+        pendingRawFrames: new Map(),
+        latestFrames: new Map(),
         inputQueue: [],
         clipboardData: null,
         fileTransferData: null,
@@ -167,7 +649,9 @@ export class RDPSessionManager extends EventEmitter {
       socket.on('close', () => {
         this.logger.info(`RDP connection closed for session ${session.id}`);
         rdpConnection.isConnected = false;
-        this.emit('sessionDisconnected', { sessionId: session.id });
+        if (session.status !== 'disconnected') {
+          this.disconnectSession(session.socketId, 'remote_closed');
+        }
       });
 
       // Handle TLS upgrade if enabled
@@ -237,9 +721,18 @@ export class RDPSessionManager extends EventEmitter {
         connection.isConnected = true;
         this.logger.info(`RDP connection confirmed for session ${connection.sessionId}`);
         this.emit('sessionConnected', { sessionId: connection.sessionId });
+      } else if (parsedData.type === 'display-layout') {
+        // Host resolution or monitor layout changed
+        this.handleDisplayLayoutChange(connection, parsedData.displays);
+      } else if (parsedData.type === 'keyboard-layout') {
+        // The host user switched input language
+        this.handleKeyboardLayoutChange(connection, parsedData.layout);
+      } else if (parsedData.type === 'audio') {
+        // System sound from the host, as 16-bit PCM
+        this.handleAudioData(connection, parsedData.format, parsedData.data);
       } else if (parsedData.type === 'frame') {
         // Handle video frame
-        this.handleVideoFrame(connection, parsedData.data);
+        this.handleVideoFrame(connection, parsedData.displayId, parsedData.data, parsedData.width, parsedData.height);
       } else if (parsedData.type === 'clipboard') {
         // Handle clipboard data
         this.handleClipboardData(connection, parsedData.data);
@@ -271,28 +764,290 @@ export class RDPSessionManager extends EventEmitter {
     if (version === 0x03 && type === 0x02) {
       return { type: 'connection-confirm' };
     } else if (version === 0x03 && type === 0x04) {
-      return { type: 'frame', data: data.slice(11) };
+      // Width and height are non-zero for raw pixel frames; rows may be padded
+      return {
+        type: 'frame',
+        displayId: data.readUInt8(5),
+        width: data.readUInt16BE(6),
+        height: data.readUInt16BE(8),
+        data: data.slice(11)
+      };
     } else if (version === 0x03 && type === 0x05) {
       return { type: 'clipboard', data: data.slice(11) };
     } else if (version === 0x03 && type === 0x06) {
       return { type: 'file-transfer', data: data.slice(11) };
     } else if (version === 0x03 && type === 0x07) {
       return { type: 'device', data: data.slice(11) };
+    } else if (version === 0x03 && type === 0x09) {
+      return { type: 'display-layout', displays: this.parseDisplayLayout(data) };
+    } else if (version === 0x03 && type === 0x0a) {
+      return { type: 'keyboard-layout', layout: data.readUInt32BE(5) };
+    } else if (version === 0x03 && type === 0x0b) {
+      return {
+        type: 'audio',
+        format: { channels: data.readUInt8(5), sampleRate: data.readUInt32BE(6) },
+        data: data.slice(11)
+      };
     }
 
     return { type: 'unknown', data };
   }
 
-  private handleVideoFrame(connection: RDPConnection, frameData: Buffer): void {
-    // Compress and encode frame data
-    const compressedFrame = this.compressFrame(frameData);
-    
-    // Add to frame buffer
-    connection.frameBuffer.push(compressedFrame);
+  private parseDisplayLayout(data: Buffer): RDPDisplay[] {
+    // Same layout as buildDisplayControlPacket: 18 bytes per monitor after a 6 byte header
+    const count = data.readUInt8(5);
+    if (count === 0 || data.length < 6 + count * 18) {
+      throw new Error('Invalid display layout PDU');
+    }
+
+    const displays: RDPDisplay[] = [];
+    for (let i = 0; i < count; i++) {
+      const offset = 6 + i * 18;
+      displays.push({
+        id: data.readUInt8(offset),
+        primary: data.readUInt8(offset + 1) === 0x01,
+        x: data.readInt32BE(offset + 2),
+        y: data.readInt32BE(offset + 6),
+        width: data.readUInt32BE(offset + 10),
+        height: data.readUInt32BE(offset + 14)
+      });
+    }
+
+    return displays;
+  }
+
+  private handleKeyboardLayoutChange(connection: RDPConnection, layout: number): void {
+    const session = this.sessions.get(connection.sessionId);
+    if (!session || session.hostKeyboardLayout === layout) return;
+
+    session.hostKeyboardLayout = layout;
+    this.logger.info(`Session ${session.id} host keyboard layout is ${layout.toString(16).padStart(8, '0')}`);
+    this.emit('keyboardLayoutChanged', { sessionId: session.id, layout });
+  }
+
+  private handleAudioData(connection: RDPConnection, format: AudioFormat, pcm: Buffer): void {
+    const session = this.sessions.get(connection.sessionId);
+    if (!session || !session.config.enableAudio || pcm.length === 0) return;
+    if (format.channels < 1 || format.channels > 2 || format.sampleRate < 8000 || format.sampleRate > 192000) return;
+
+    // A new format needs a new encoder; the client is told to reconfigure
+    const current = session.audioEncoder?.format;
+    if (!current || current.sampleRate !== format.sampleRate || current.channels !== format.channels) {
+      session.audioEncoder?.close();
+      session.audioEncoder = this.startAudioEncoder(session, format);
+    }
+    session.audioEncoder.write(pcm);
+  }
+
+  private startAudioEncoder(session: RDPSession, format: AudioFormat): OpusAudioEncoder {
+    const bitrate = Config.getInstance().get('rdp').audioBitrate;
+    const encoder = new OpusAudioEncoder(format, bitrate);
+
+    encoder.on('packet', (packet: Buffer) => {
+      this.emit('audioPacket', {
+        sessionId: session.id,
+        sequence: session.audioSequence++,
+        data: packet,
+        timestamp: Date.now()
+      });
+    });
+    encoder.on('error', (error: Error) => {
+      // Audio stays off for the rest of the session rather than retrying per packet
+      this.logger.error(`Audio encoder failed for session ${session.id}:`, error);
+      if (session.audioEncoder === encoder) {
+        session.config.enableAudio = false;
+        session.audioEncoder = undefined;
+      }
+    });
+
+    this.logger.info(`Audio started for session ${session.id}: ${format.sampleRate} Hz, ${format.channels} channel(s), Opus at ${bitrate} bit/s`);
+    this.emit('audioStarted', { sessionId: session.id, channels: format.channels });
+    return encoder;
+  }
+
+  private handleDisplayLayoutChange(connection: RDPConnection, displays: RDPDisplay[]): void {
+    const session = this.sessions.get(connection.sessionId);
+    if (!session) return;
+
+    if (JSON.stringify(session.displays) === JSON.stringify(displays)) return;
+
+    const previousIds = session.displays.map(display => display.id);
+    const currentIds = displays.map(display => display.id);
+    session.displays = displays;
+
+    const primary = displays.find(display => display.primary) || displays[0];
+    session.config.width = primary.width;
+    session.config.height = primary.height;
+    session.codec.reconfigure?.({ width: primary.width, height: primary.height });
+
+    // Tear down pipelines for monitors that went away; stale frames are dropped
+    for (const id of previousIds.filter(id => !currentIds.includes(id))) {
+      connection.frameBuffers.delete(id);
+      connection.latestFrames.delete(id);
+      this.stopDisplayProcessing(session.id, id);
+    }
+
+    for (const id of currentIds) {
+      const frameBuffer = connection.frameBuffers.get(id);
+      if (frameBuffer) {
+        // Frames queued at the old resolution are no longer valid
+        frameBuffer.length = 0;
+      } else {
+        connection.frameBuffers.set(id, []);
+        this.startFrameProcessing(session.id, id);
+      }
+    }
+    this.recordReceiveQueueDepth(connection);
+
+    const subscription = session.displaySubscription;
+    if (subscription.mode === 'virtual') {
+      subscription.displayIds = currentIds;
+    } else {
+      subscription.displayIds = subscription.displayIds.filter(id => currentIds.includes(id));
+      if (subscription.displayIds.length === 0) {
+        session.displaySubscription = { mode: 'single', displayIds: [primary.id] };
+      }
+    }
+
+    this.logger.info(`Display layout changed for session ${session.id}: ${displays.map(d => `${d.width}x${d.height}`).join(', ')}`);
+    this.emit('displayChanged', {
+      sessionId: session.id,
+      displays: session.displays,
+      displaySubscription: session.displaySubscription,
+      reason: 'host_layout_change'
+    });
+  }
+
+  private handleVideoFrame(
+    connection: RDPConnection,
+    displayId: number,
+    frameData: Buffer,
+    width: number = 0,
+    height: number = 0
+  ): void {
+    const capturedAt = performance.now();
+    const session = this.sessions.get(connection.sessionId);
+    const display = session?.displays.find(d => d.id === displayId);
+    if (!session || !display) {
+      this.logger.warn(`Frame for unknown display ${displayId} in session ${connection.sessionId}, dropping`);
+      return;
+    }
+
+    // Dimensions carried by the frame win over the last known layout
+    if (width > 0 && height > 0) {
+      const stride = frameData.length / height;
+      if (!Number.isInteger(stride) || stride < width * FrameScaler.BYTES_PER_PIXEL) {
+        this.logger.warn(`Malformed ${width}x${height} frame (${frameData.length} bytes) in session ${connection.sessionId}, dropping`);
+        return;
+      }
+      if (stride > width * FrameScaler.BYTES_PER_PIXEL) {
+        frameData = FrameScaler.removePadding(frameData, { width, height }, stride);
+      }
+
+      // The host resized without announcing a new layout
+      if (width !== display.width || height !== display.height) {
+        this.handleDisplayLayoutChange(connection, session.displays.map(d => (d.id === displayId ? { ...d, width, height } : d)));
+      }
+    }
+
+    // Only full raw frames can be scaled; anything else is streamed as-is
+    const cap = this.getResolutionCap(session);
+    const source = { width: width || display.width, height: height || display.height };
+    if (session.thumbnailSubscribers.size > 0 && FrameScaler.isRawFrame(frameData, source)) {
+      connection.latestFrames.set(displayId, { data: frameData, ...source, dirty: true });
+    }
+    let target: FrameSize | undefined = FrameScaler.getTargetSize(source, cap);
+    if ((target.width === source.width && target.height === source.height) || !FrameScaler.isRawFrame(frameData, source)) {
+      target = undefined;
+    }
+
+    const job: RawFrameJob = { data: frameData, source, target, capturedAt };
+    if (connection.encoding.has(displayId)) {
+      // Frames are full updates, so the newest supersedes one still waiting
+      if (connection.pendingRawFrames.has(displayId)) {
+        session.pipelineMetrics.recordFrameCoalesced();
+      }
+      connection.pendingRawFrames.set(displayId, job);
+      return;
+    }
+    this.encodeFrame(connection, session, displayId, job);
+  }
+
+  // Scaling and compression run on the libuv thread pool so they never
+  // block control-plane work on the event loop, and vice versa. One job runs
+  // per display at a time, which keeps that display's frames in order.
+  private encodeFrame(connection: RDPConnection, session: RDPSession, displayId: number, job: RawFrameJob): void {
+    connection.encoding.add(displayId);
+    session.pipelineMetrics.markEncodeStarted();
+
+    const encode = async () => {
+      const startedAt = performance.now();
+      const scaled = job.target ? await FrameScaler.scale(job.data, job.source, job.target) : job.data;
+      const size = job.target || job.source;
+      const compressed = await session.codec.encode(scaled, {
+        ...size,
+        raw: FrameScaler.isRawFrame(scaled, size),
+        lossless: session.effectiveQuality === 'lossless'
+      });
+      session.pipelineMetrics.recordEncode(performance.now() - startedAt, job.data.length, compressed.length);
+      this.enqueueFrame(connection, displayId, compressed, job.capturedAt, job.target);
+    };
+
+    encode()
+      .catch(error => {
+        session.pipelineMetrics.recordEncodeFailed();
+        this.logger.error(`Failed to process frame for session ${connection.sessionId}:`, error);
+      })
+      .finally(() => {
+        connection.encoding.delete(displayId);
+        const next = connection.pendingRawFrames.get(displayId);
+        connection.pendingRawFrames.delete(displayId);
+        if (next && connection.isConnected) {
+          this.encodeFrame(connection, session, displayId, next);
+        }
+      });
+  }
+
+  private enqueueFrame(
+    connection: RDPConnection,
+    displayId: number,
+    compressedFrame: Buffer,
+    capturedAt: number,
+    size?: FrameSize
+  ): void {
+    // Add to the frame buffer of the display it was captured from; it may
+    // have been removed while the frame was being processed
+    const frameBuffer = connection.frameBuffers.get(displayId);
+    const session = this.sessions.get(connection.sessionId);
+    if (!frameBuffer || !session) return;
+
+    const frame: QueuedFrame = { data: compressedFrame, queuedAt: Date.now(), capturedAt, width: size?.width, height: size?.height };
+    const { frameQueueSize, frameQueuePolicy } = Config.getInstance().get('rdp');
+
+    // Keep the queue bounded so a slow client cannot grow memory without limit
+    if (frameBuffer.length >= frameQueueSize) {
+      if (frameQueuePolicy === 'drop-newest') {
+        session.pipelineMetrics.recordFrameDropped();
+        return;
+      }
+
+      if (frameQueuePolicy === 'coalesce') {
+        // Frames are full updates, so the newest supersedes the last queued one
+        frameBuffer[frameBuffer.length - 1] = { ...frame, queuedAt: frameBuffer[frameBuffer.length - 1].queuedAt };
+        session.pipelineMetrics.recordFrameCoalesced();
+        return;
+      }
+
+      frameBuffer.shift();
+      session.pipelineMetrics.recordFrameDropped();
+    }
+    frameBuffer.push(frame);
+    this.recordReceiveQueueDepth(connection);
     
     // Emit frame data to client
     this.emit('frameReceived', {
       sessionId: connection.sessionId,
+      displayId,
       frame: compressedFrame,
       timestamp: Date.now()
     });
@@ -301,11 +1056,15 @@ export class RDPSessionManager extends EventEmitter {
   private handleClipboardData(connection: RDPConnection, data: Buffer): void {
     try {
       const clipboardText = data.toString('utf8');
+      const session = this.sessions.get(connection.sessionId);
+      if (!session || !this.resolveClipboardUpdate(session, 'host', clipboardText)) return;
+
       connection.clipboardData = clipboardText;
       
       this.emit('clipboardReceived', {
         sessionId: connection.sessionId,
-        data: clipboardText
+        data: clipboardText,
+        timestamp: session.clipboard?.timestamp
       });
     } catch (error) {
       this.logger.error('Error handling clipboard data:', error);
@@ -340,34 +1099,296 @@ export class RDPSessionManager extends EventEmitter {
     }
   }
 
-  private compressFrame(frameData: Buffer): Buffer {
-    // Compress frame using zlib
-    return zlib.deflateSync(frameData);
-  }
+  private startFrameProcessing(sessionId: string, displayId: number): void {
+    const processorKey = `${sessionId}:${displayId}`;
+    let lastSentAt = 0;
 
-  private startFrameProcessing(sessionId: string): void {
     const interval = setInterval(() => {
+      const session = this.sessions.get(sessionId);
       const connection = this.rdpConnections.get(sessionId);
-      if (!connection || !connection.isConnected) {
+      if (!session || !connection || !connection.isConnected) {
         clearInterval(interval);
-        this.frameProcessors.delete(sessionId);
+        this.frameProcessors.delete(processorKey);
         return;
       }
 
+      const frameBuffer = connection.frameBuffers.get(displayId);
+      if (!frameBuffer) return;
+
+      // Frames for displays the client is not watching are discarded
+      if (!session.displaySubscription.displayIds.includes(displayId)) {
+        frameBuffer.length = 0;
+        this.recordReceiveQueueDepth(connection);
+        return;
+      }
+
+      const display = session.displays.find(d => d.id === displayId);
+
+      // Under congestion the frame rate is reduced; queued frames wait (and
+      // are dropped or coalesced by the bounded queue) until the next slot
+      const nowMs = Date.now();
+      if (session.targetFps < DEFAULT_FPS && nowMs - lastSentAt < 1000 / session.targetFps - 5) return;
+      if (frameBuffer.length > 0) lastSentAt = nowMs;
+
       // Process queued frames
-      while (connection.frameBuffer.length > 0) {
-        const frame = connection.frameBuffer.shift();
+      while (frameBuffer.length > 0) {
+        const frame = frameBuffer.shift();
         if (frame) {
+          const now = Date.now();
+          session.pipelineMetrics.recordTimeInQueue('receive', now - frame.queuedAt);
+          const frameId = ++session.frameCount;
+          session.pipelineMetrics.markFrameSent(frameId, frame.capturedAt, frame.data.length, performance.now());
+
           this.emit('frameProcessed', {
             sessionId,
-            frame,
+            displayId,
+            frameId,
+            codec: session.codec.name,
+            x: display ? display.x : 0,
+            y: display ? display.y : 0,
+            width: frame.width || (display ? display.width : 0),
+            height: frame.height || (display ? display.height : 0),
+            frame: frame.data,
+            timestamp: now
+          });
+        }
+      }
+      this.recordReceiveQueueDepth(connection);
+    }, 1000 / DEFAULT_FPS);
+
+    this.frameProcessors.set(processorKey, interval);
+  }
+
+  private recordReceiveQueueDepth(connection: RDPConnection): void {
+    const session = this.sessions.get(connection.sessionId);
+    if (!session) return;
+
+    let depth = 0;
+    for (const frameBuffer of connection.frameBuffers.values()) {
+      depth += frameBuffer.length;
+    }
+    session.pipelineMetrics.recordDepth('receive', depth);
+  }
+
+  private startSessionMonitor(sessionId: string): void {
+    const rdpConfig = Config.getInstance().get('rdp');
+    const thresholds = {
+      queueDepth: rdpConfig.backpressureQueueDepth,
+      timeInQueue: rdpConfig.backpressureTimeInQueue,
+      sustainFor: rdpConfig.backpressureSustainFor
+    };
+    let previous = { timestamp: Date.now(), frameCount: 0, bytesReceived: 0, bytesSent: 0 };
+
+    // Shares the frame processor registry so stopFrameProcessing cleans it up
+    const interval = setInterval(() => {
+      const session = this.sessions.get(sessionId);
+      const connection = this.rdpConnections.get(sessionId);
+      if (!session || !connection) return;
+
+      const now = Date.now();
+      const elapsed = Math.max(1, now - previous.timestamp) / 1000;
+      const pipeline = session.pipelineMetrics.snapshot();
+      const runtime = RuntimeMetrics.getInstance().snapshot();
+      const client = TransportRegistry.getInstance().getStats(session.socketId);
+
+      session.metricsHistory.push({
+        timestamp: now,
+        fps: Math.round((session.frameCount - previous.frameCount) / elapsed),
+        bitrateIn: Math.round((connection.bytesReceived - previous.bytesReceived) * 8 / elapsed),
+        bitrateOut: Math.round((connection.bytesSent - previous.bytesSent) * 8 / elapsed),
+        maxBitrate: session.maxBitrate,
+        targetBitrate: session.targetBitrate,
+        quality: session.effectiveQuality,
+        receiveQueueDepth: pipeline.receive.depth,
+        sendQueueDepth: pipeline.send.depth,
+        receiveP95: pipeline.receive.timeInQueue.p95,
+        sendP95: pipeline.send.timeInQueue.p95,
+        latencyP95: pipeline.latency.p95,
+        bandwidthEstimate: pipeline.bandwidth.estimate,
+        encodeP95: pipeline.encode.time.p95,
+        encodePending: pipeline.encode.pending,
+        eventLoopUtilization: runtime.eventLoopUtilization,
+        eventLoopDelayP99: runtime.eventLoopDelay.p99,
+        clientBitrateIn: (client?.bytesInPerSec ?? 0) * 8,
+        clientBitrateOut: (client?.bytesOutPerSec ?? 0) * 8,
+        clientRtt: client?.rtt ?? null,
+        clientDrops: client?.drops ?? 0
+      });
+      previous = {
+        timestamp: now,
+        frameCount: session.frameCount,
+        bytesReceived: connection.bytesReceived,
+        bytesSent: connection.bytesSent
+      };
+
+      if (session.bitrateController) {
+        // Roughly the last second of frames at the default rate
+        const target = session.bitrateController.update(session.pipelineMetrics.recentSignals(DEFAULT_FPS), now);
+        if (target !== null) {
+          this.applyTargetBitrate(session, target);
+        }
+      }
+
+      this.checkIdle(session, connection, now);
+
+      for (const event of session.pipelineMetrics.checkBackpressure(thresholds)) {
+        if (event.state === 'warning') {
+          this.logger.warn(`Sustained ${event.stage} backpressure in session ${sessionId}: depth ${event.queueDepth}, p95 ${event.p95TimeInQueue}ms`);
+        } else {
+          this.logger.info(`${event.stage} backpressure cleared in session ${sessionId}`);
+        }
+        this.emit('backpressure', { sessionId, ...event });
+      }
+    }, rdpConfig.metricsSampleInterval);
+
+    this.frameProcessors.set(`${sessionId}:monitor`, interval);
+  }
+
+  private applyTargetBitrate(session: RDPSession, bitrate: number): void {
+    const previousQuality = session.effectiveQuality;
+    session.targetBitrate = bitrate;
+
+    if (session.config.quality === 'lossless') {
+      // Pixel-exact output is the point of lossless, so only frame rate gives
+      session.effectiveQuality = 'lossless';
+      session.targetFps = bitrate < session.maxBitrate / 2 ? CONGESTED_FPS : DEFAULT_FPS;
+    } else {
+      // Highest tier the target bitrate can sustain; drives the resolution cap
+      const tiers = LOSSY_QUALITIES.filter(quality => QUALITY_BITRATES[quality] <= Math.max(bitrate, QUALITY_BITRATES.low));
+      session.effectiveQuality = tiers[tiers.length - 1];
+      session.targetFps = bitrate < QUALITY_BITRATES.low ? CONGESTED_FPS : DEFAULT_FPS;
+    }
+
+    if (session.effectiveQuality !== previousQuality) {
+      this.logger.info(`Session ${session.id} adapted to ${session.effectiveQuality} quality (${bitrate} bps)`);
+    }
+    this.emit('bitrateAdapted', {
+      sessionId: session.id,
+      bitrate,
+      quality: session.effectiveQuality,
+      fps: session.targetFps,
+      estimatedBandwidth: session.pipelineMetrics.estimatedBandwidth()
+    });
+  }
+
+  public getMetricsHistory(sessionId: string, since?: number, until?: number): MetricsSample[] | undefined {
+    const session = this.sessions.get(sessionId);
+    return session ? session.metricsHistory.query(since, until) : undefined;
+  }
+
+  public recordFrameSent(sessionId: string, sendQueueDepth: number): void {
+    const session = this.sessions.get(sessionId);
+    if (!session) return;
+
+    session.pipelineMetrics.markSendQueued(Date.now(), sendQueueDepth);
+  }
+
+  // A frame replaced an older one still waiting for a slow client
+  public recordFrameCoalesced(sessionId: string): void {
+    this.sessions.get(sessionId)?.pipelineMetrics.recordFrameCoalesced();
+  }
+
+  public recordSendDrained(socketId: string): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session) return;
+
+    session.pipelineMetrics.markSendDrained(Date.now());
+  }
+
+  public acknowledgeFrame(socketId: string, frameId: number): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || typeof frameId !== 'number') return;
+
+    session.pipelineMetrics.recordFrameAck(frameId, performance.now());
+  }
+
+  public getPipelineMetrics(sessionId: string): PipelineMetricsSnapshot | undefined {
+    const session = this.sessions.get(sessionId);
+    return session ? session.pipelineMetrics.snapshot() : undefined;
+  }
+
+  public subscribeThumbnails(sessionId: string, subscriberId: string): void {
+    const session = this.sessions.get(sessionId);
+    if (!session || session.status === 'disconnected') {
+      throw new Error('Session not found');
+    }
+
+    session.thumbnailSubscribers.add(subscriberId);
+    const processorKey = `${sessionId}:thumbnails`;
+    if (this.frameProcessors.has(processorKey)) return;
+
+    const rdpConfig = Config.getInstance().get('rdp');
+    let encoding = false;
+    const interval = setInterval(async () => {
+      const connection = this.rdpConnections.get(sessionId);
+      if (!connection || encoding) return;
+
+      // Skip a tick rather than queue up behind a slow encode
+      encoding = true;
+      try {
+        for (const [displayId, frame] of connection.latestFrames) {
+          if (!frame.dirty) continue;
+          frame.dirty = false;
+
+          const thumbnail = await FrameScaler.thumbnail(frame.data, frame, rdpConfig.thumbnailWidth, rdpConfig.thumbnailQuality);
+          this.emit('thumbnail', {
+            sessionId,
+            displayId,
+            data: thumbnail,
             timestamp: Date.now()
           });
         }
+      } catch (error) {
+        this.logger.error(`Failed to encode thumbnail for session ${sessionId}:`, error);
+      } finally {
+        encoding = false;
+      }
+    }, rdpConfig.thumbnailInterval);
+
+    this.frameProcessors.set(processorKey, interval);
+    this.logger.info(`Thumbnail stream started for session ${sessionId}`);
+  }
+
+  public unsubscribeThumbnails(sessionId: string, subscriberId: string): void {
+    const session = this.sessions.get(sessionId);
+    if (!session) return;
+
+    session.thumbnailSubscribers.delete(subscriberId);
+    if (session.thumbnailSubscribers.size > 0) return;
+
+    const processorKey = `${sessionId}:thumbnails`;
+    const processor = this.frameProcessors.get(processorKey);
+    if (processor) {
+      clearInterval(processor);
+      this.frameProcessors.delete(processorKey);
+    }
+    this.rdpConnections.get(sessionId)?.latestFrames.clear();
+  }
+
+  public unsubscribeAllThumbnails(subscriberId: string): void {
+    for (const session of this.sessions.values()) {
+      if (session.thumbnailSubscribers.has(subscriberId)) {
+        this.unsubscribeThumbnails(session.id, subscriberId);
       }
-    }, 1000 / 30); // 30 FPS
+    }
+  }
+
+  private stopDisplayProcessing(sessionId: string, displayId: number): void {
+    const processorKey = `${sessionId}:${displayId}`;
+    const processor = this.frameProcessors.get(processorKey);
+    if (processor) {
+      clearInterval(processor);
+      this.frameProcessors.delete(processorKey);
+    }
+  }
 
-    this.frameProcessors.set(sessionId, interval);
+  private stopFrameProcessing(sessionId: string): void {
+    for (const [key, processor] of this.frameProcessors) {
+      if (key.startsWith(`${sessionId}:`)) {
+        clearInterval(processor);
+        this.frameProcessors.delete(key);
+      }
+    }
   }
 
   private upgradeToTLS(connection: RDPConnection, config: RDPSessionConfig): void {
@@ -402,86 +1423,774 @@ export class RDPSessionManager extends EventEmitter {
     });
   }
 
-  public async disconnectSession(socketId: string): Promise<void> {
+  public async disconnectSession(socketId: string, code: DisconnectReasonCode = 'client_request', detail?: string): Promise<void> {
     const session = this.findSessionBySocketId(socketId);
     if (!session) {
       this.logger.warn(`No session found for socket ${socketId}`);
       return;
     }
 
+    this.endMacro(session);
+    this.cancelDrag(session);
+
     const connection = this.rdpConnections.get(session.id);
     if (connection) {
+      session.bytesReceived = connection.bytesReceived;
+      session.bytesSent = connection.bytesSent;
       connection.socket.end();
       this.rdpConnections.delete(session.id);
     }
 
     // Stop frame processing
-    const processor = this.frameProcessors.get(session.id);
-    if (processor) {
-      clearInterval(processor);
-      this.frameProcessors.delete(session.id);
+    this.stopFrameProcessing(session.id);
+    session.codec.shutdown?.();
+    session.inputLimiter.clear();
+    session.audioEncoder?.close();
+    session.audioEncoder = undefined;
+
+    if (session.resumeTimer) {
+      clearTimeout(session.resumeTimer);
+      session.resumeTimer = undefined;
     }
 
+    const reason = detail ? `${code}:${detail}` : code;
     session.status = 'disconnected';
     session.lastActivity = new Date();
+    session.disconnectReason = { code, ...DISCONNECT_REASONS[code], detail };
+    session.summary = this.buildSessionSummary(session, reason);
     
-    this.logger.info(`RDP session ${session.id} disconnected`);
+    this.logger.info(`RDP session ${session.id} disconnected (${reason})`);
     this.emit('sessionDisconnected', session);
+    this.emit('sessionSummary', session.summary);
+    session.viewers.clear();
+    this.evictFinishedSessions();
   }
 
-  public forwardMouseInput(socketId: string, data: any): void {
-    const session = this.findSessionBySocketId(socketId);
-    if (!session || session.status !== 'connected') return;
+  // Sessions are kept after they end so their summaries and metrics can be
+  // queried, up to a limit; Map order makes the first ones found the oldest
+  private evictFinishedSessions(): void {
+    const limit = Config.getInstance().get('rdp').finishedSessionLimit;
+    const finished = Array.from(this.sessions.values()).filter(session => session.status === 'disconnected');
 
-    const connection = this.rdpConnections.get(session.id);
-    if (connection && connection.isConnected) {
-      const inputData = this.buildMouseInputPacket(data);
-      connection.socket.write(inputData);
-      connection.bytesSent += inputData.length;
+    for (const session of finished.slice(0, Math.max(0, finished.length - limit))) {
+      this.sessions.delete(session.id);
     }
   }
 
-  public forwardKeyboardInput(socketId: string, data: any): void {
+  // Keeps a session running after its client dropped so the client can
+  // reattach with its resume token; returns false when the session should be
+  // disconnected right away instead
+  public detachSession(socketId: string, detail?: string): boolean {
     const session = this.findSessionBySocketId(socketId);
-    if (!session || session.status !== 'connected') return;
+    const gracePeriod = Config.getInstance().get('rdp').resumeGracePeriod;
+    if (!session || session.status !== 'connected' || gracePeriod <= 0) return false;
+
+    this.endMacro(session);
+    this.cancelDrag(session);
+    session.detachedAt = Date.now();
+    session.resumeTimer = setTimeout(() => {
+      session.resumeTimer = undefined;
+      this.disconnectSession(socketId, 'connection_lost', detail).catch((error) => {
+        this.logger.error(`Failed to close detached session ${session.id}:`, error);
+      });
+    }, gracePeriod);
 
-    const connection = this.rdpConnections.get(session.id);
-    if (connection && connection.isConnected) {
-      const inputData = this.buildKeyboardInputPacket(data);
-      connection.socket.write(inputData);
-      connection.bytesSent += inputData.length;
-    }
+    this.logger.info(`RDP session ${session.id} detached, resumable for ${gracePeriod}ms`);
+    this.emit('sessionDetached', session);
+    return true;
   }
 
-  public forwardTouchInput(socketId: string, data: any): void {
-    const session = this.findSessionBySocketId(socketId);
-    if (!session || session.status !== 'connected') return;
+  public resumeSession(socketId: string, resumeToken: string, owner?: string): RDPSession {
+    const session = typeof resumeToken === 'string'
+      ? Array.from(this.sessions.values()).find(candidate =>
+          candidate.status === 'connected' && candidate.resumeToken === resumeToken)
+      : undefined;
 
-    const connection = this.rdpConnections.get(session.id);
-    if (connection && connection.isConnected) {
-      const inputData = this.buildTouchInputPacket(data);
-      connection.socket.write(inputData);
-      connection.bytesSent += inputData.length;
+    // The same error for every failure, so tokens cannot be probed
+    if (!session || session.detachedAt === undefined || (session.owner && session.owner !== owner)) {
+      throw new Error('Session cannot be resumed');
+    }
+
+    if (session.resumeTimer) {
+      clearTimeout(session.resumeTimer);
+      session.resumeTimer = undefined;
     }
+
+    const previousSocketId = session.socketId;
+    const detachedFor = Date.now() - session.detachedAt;
+    session.socketId = socketId;
+    session.detachedAt = undefined;
+    // Tokens are single use
+    session.resumeToken = crypto.randomBytes(32).toString('hex');
+    session.reconnectCount++;
+    session.lastActivity = new Date();
+    this.noteClientActivity(socketId);
+
+    this.logger.info(`RDP session ${session.id} resumed by ${socketId} after ${detachedFor}ms`);
+    this.emit('sessionResumed', { session, previousSocketId });
+
+    // The new client has nothing on screen yet
+    session.lastRefreshAt = 0;
+    this.requestRefresh(socketId);
+
+    return session;
   }
 
-  private buildMouseInputPacket(data: any): Buffer {
-    // Build RDP mouse input packet
+  // Viewers get the frames the controller's session already encodes; none of
+  // their input reaches the host
+  public addViewer(sessionId: string, socketId: string): RDPSession {
+    const session = this.sessions.get(sessionId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('Session not found');
+    }
+    if (session.socketId === socketId) {
+      throw new Error('Already controlling this session');
+    }
+
+    const maxViewers = Config.getInstance().get('rdp').maxViewersPerSession;
+    if (!session.viewers.has(socketId) && session.viewers.size >= maxViewers) {
+      throw new Error(`Session already has the maximum of ${maxViewers} viewers`);
+    }
+
+    session.viewers.add(socketId);
+    this.logger.info(`${socketId} is viewing session ${session.id} (${session.viewers.size} viewers)`);
+    this.emit('viewersChanged', session);
+
+    // A late viewer needs a full picture, not just what changes next
+    session.lastRefreshAt = 0;
+    this.requestRefresh(session.socketId);
+
+    return session;
+  }
+
+  public removeViewer(socketId: string): RDPSession | undefined {
+    for (const session of this.sessions.values()) {
+      if (session.status !== 'disconnected' && session.viewers.delete(socketId)) {
+        this.logger.info(`${socketId} stopped viewing session ${session.id}`);
+        this.emit('viewersChanged', session);
+        return session;
+      }
+    }
+    return undefined;
+  }
+
+  // The controller hands input to one of its viewers and keeps watching
+  public transferControl(socketId: string, toSocketId: string): RDPSession {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !session.viewers.has(toSocketId)) {
+      throw new Error('Viewer not found');
+    }
+
+    // The old controller's drag and macro end with its control
+    this.endMacro(session);
+    this.cancelDrag(session);
+    session.viewers.delete(toSocketId);
+    session.viewers.add(socketId);
+    session.socketId = toSocketId;
+    // The new controller has no pointer lock yet
+    session.pointerMode = 'absolute';
+    session.touchContacts.clear();
+    session.hotkeyFilter.reset();
+    session.lastActivity = new Date();
+    this.noteClientActivity(toSocketId);
+
+    this.logger.info(`Control of session ${session.id} passed from ${socketId} to ${toSocketId}`);
+    this.emit('viewersChanged', session);
+    return session;
+  }
+
+  private buildSessionSummary(session: RDPSession, reason: string): SessionSummary {
+    const endTime = new Date();
+    const duration = endTime.getTime() - session.startTime.getTime();
+    const samples = session.metricsHistory.query();
+    const average = (values: number[]) =>
+      values.length > 0 ? Math.round(values.reduce((sum, value) => sum + value, 0) / values.length) : 0;
+
+    return {
+      sessionId: session.id,
+      socketId: session.socketId,
+      host: session.config.host,
+      port: session.config.port,
+      username: session.config.username,
+      startTime: session.startTime,
+      endTime,
+      duration,
+      frameCount: session.frameCount,
+      bytesReceived: session.bytesReceived,
+      bytesSent: session.bytesSent,
+      averageFps: duration > 0 ? Math.round(session.frameCount / (duration / 1000)) : 0,
+      averageBitrateIn: duration > 0 ? Math.round(session.bytesReceived * 8 / (duration / 1000)) : 0,
+      averageBitrateOut: duration > 0 ? Math.round(session.bytesSent * 8 / (duration / 1000)) : 0,
+      // Gateway-side queueing only; the network leg is not observable here
+      averageQueueLatency: average(samples.map(sample => sample.receiveP95 + sample.sendP95)),
+      // End-to-end, from client frame acks; 0 if the client never acked
+      averageLatency: average(samples.filter(sample => sample.latencyP95 > 0).map(sample => sample.latencyP95)),
+      reconnectCount: session.reconnectCount,
+      filesTransferred: session.filesTransferred,
+      disconnectReason: reason
+    };
+  }
+
+  // Counts a completed upload or download towards the session's summary
+  public recordFileTransferred(socketId: string): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (session) {
+      session.filesTransferred++;
+    }
+  }
+
+  public getSessionSummaries(): SessionSummary[] {
+    return Array.from(this.sessions.values())
+      .filter(session => session.summary !== undefined)
+      .map(session => session.summary as SessionSummary);
+  }
+
+  public forwardMouseInput(socketId: string, data: any): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !this.canInject(session, 'mouse')) return;
+    // Pointer input would break up a synthesized drag
+    if (session.drag) return;
+    this.noteInput(session, 'mouse', data);
+
+    if (data?.action === 'move' || data?.action === 'wheel') {
+      session.inputLimiter.submit(data.action, data);
+      return;
+    }
+
+    // Held-back moves go first so a click lands where the pointer was left
+    session.inputLimiter.flush();
+    this.writeMouseInput(session, data);
+  }
+
+  private writeMouseInput(session: RDPSession, data: any): void {
+    const connection = this.rdpConnections.get(session.id);
+    // Routed on what the event carries; events that do not match the
+    // session's pointer mode are dropped once the grace period is over
+    const mode: PointerMode = data?.deltaX !== undefined || data?.deltaY !== undefined ? 'relative' : 'absolute';
+    if (mode !== session.pointerMode && Date.now() - session.pointerModeChangedAt > POINTER_MODE_GRACE) return;
+
+    if (mode === 'relative') {
+      if (connection && connection.isConnected) {
+        const inputData = this.buildRelativeMouseInputPacket(data);
+        connection.socket.write(inputData);
+        connection.bytesSent += inputData.length;
+      }
+      return;
+    }
+
+    const point = this.resolvePointerPosition(session, data);
+    if (!point) return;
+
+    if (connection && connection.isConnected) {
+      const inputData = this.buildMouseInputPacket({ ...data, ...point });
+      connection.socket.write(inputData);
+      connection.bytesSent += inputData.length;
+    }
+  }
+
+  // Returns the drag's duration; 'dragCompleted' is emitted once the button
+  // is released, or early if the drag is cancelled
+  public startDrag(socketId: string, gesture: DragGesture): number {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No active session');
+    }
+    if (!this.canInject(session, 'mouse')) {
+      throw new Error('Mouse input is not permitted in this session');
+    }
+    if (session.drag) {
+      throw new Error('A drag is already in progress');
+    }
+
+    const endpoints = [gesture?.from, gesture?.to];
+    if (endpoints.some(point => !Number.isFinite(Number(point?.x)) || !Number.isFinite(Number(point?.y)))) {
+      throw new Error('A drag needs a start and an end point');
+    }
+    const [from, to] = endpoints.map(point =>
+      this.resolvePointerPosition(session, { displayId: gesture.displayId, x: point.x, y: point.y }));
+    if (!from || !to) {
+      throw new Error(`Unknown display: ${gesture.displayId}`);
+    }
+
+    const button = Number.isInteger(gesture.button) ? Number(gesture.button) : 0;
+    const duration = Math.min(Math.max(Math.round(Number(gesture.duration) || DRAG_DEFAULT_DURATION), DRAG_MIN_DURATION), DRAG_MAX_DURATION);
+    const steps = Math.max(Math.round((duration - 2 * DRAG_HOLD_TIME) / DRAG_STEP_INTERVAL), 1);
+    const interval = (duration - 2 * DRAG_HOLD_TIME) / steps;
+
+    // Eased so the pointer accelerates away from the press and settles on the drop
+    const path: Array<{ x: number; y: number }> = [];
+    for (let step = 1; step <= steps; step++) {
+      const t = step / steps;
+      const eased = t * t * (3 - 2 * t);
+      path.push({
+        x: Math.round(from.x + (to.x - from.x) * eased),
+        y: Math.round(from.y + (to.y - from.y) * eased)
+      });
+    }
+
+    this.noteInput(session, 'drag', gesture);
+    session.inputLimiter.flush();
+    this.writeDragInput(session, 'move', button, from);
+    this.writeDragInput(session, 'down', button, from);
+    session.lastActivity = new Date();
+
+    let position = from;
+    const next = (index: number) => {
+      const delay = index === path.length ? DRAG_HOLD_TIME : index === 0 ? DRAG_HOLD_TIME + interval : interval;
+      const timer = setTimeout(() => {
+        // Stop holding the button as soon as the mouse is revoked
+        if (!this.canInject(session, 'mouse')) {
+          this.cancelDrag(session);
+          return;
+        }
+        if (index === path.length) {
+          session.drag = undefined;
+          this.writeDragInput(session, 'up', button, to);
+          this.emit('dragCompleted', { sessionId: session.id, cancelled: false });
+          return;
+        }
+        position = path[index];
+        this.writeDragInput(session, 'move', button, position);
+        next(index + 1);
+      }, delay);
+      session.drag = { timer, button, ...position };
+    };
+    next(0);
+
+    this.logger.debug(`Session ${session.id} dragging (${from.x}, ${from.y}) to (${to.x}, ${to.y}) over ${duration}ms`);
+    return duration;
+  }
+
+  public startMacroRecording(socketId: string): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No active session');
+    }
+    if (session.viewOnly) {
+      throw new Error('Session is view-only');
+    }
+    if (session.macroRecorder || session.macroReplay) {
+      throw new Error('A macro is already being recorded or replayed');
+    }
+
+    session.macroRecorder = new InputMacroRecorder();
+    this.logger.info(`Recording input macro in session ${session.id}`);
+  }
+
+  public stopMacroRecording(socketId: string): InputMacro {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !session.macroRecorder) {
+      throw new Error('No macro is being recorded');
+    }
+
+    const macro = session.macroRecorder.finish();
+    session.macroRecorder = undefined;
+
+    this.logger.info(`Recorded input macro in session ${session.id}: ${macro.events.length} events over ${macro.duration}ms`);
+    return macro;
+  }
+
+  // Plays a recorded macro through the same paths as live input, so the
+  // session's permissions and limits apply; returns its duration in ms
+  public replayMacro(socketId: string, value: any, speed: number = 1): number {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No active session');
+    }
+    if (session.viewOnly) {
+      throw new Error('Session is view-only');
+    }
+    if (session.macroRecorder || session.macroReplay) {
+      throw new Error('A macro is already being recorded or replayed');
+    }
+
+    const macro = parseInputMacro(value);
+    const rate = Math.min(Math.max(Number(speed) || 1, 0.1), 10);
+
+    const next = (index: number) => {
+      const delay = (macro.events[index].at - (index > 0 ? macro.events[index - 1].at : 0)) / rate;
+      session.macroReplay = setTimeout(() => {
+        this.playMacroEvent(session, macro.events[index]);
+        if (index + 1 < macro.events.length) {
+          next(index + 1);
+          return;
+        }
+        session.macroReplay = undefined;
+        this.logger.info(`Replayed input macro in session ${session.id}`);
+        this.emit('macroReplayFinished', { sessionId: session.id, cancelled: false });
+      }, delay);
+    };
+    next(0);
+
+    this.logger.info(`Replaying input macro in session ${session.id}: ${macro.events.length} events at ${rate}x`);
+    return Math.round(macro.duration / rate);
+  }
+
+  public stopMacroReplay(socketId: string): boolean {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !session.macroReplay) return false;
+
+    this.endMacro(session);
+    return true;
+  }
+
+  private playMacroEvent(session: RDPSession, event: MacroEvent): void {
+    const socketId = session.socketId;
+    try {
+      switch (event.kind) {
+        case 'mouse':
+          this.forwardMouseInput(socketId, event.data);
+          break;
+        case 'keyboard':
+          this.forwardKeyboardInput(socketId, event.data);
+          break;
+        case 'text':
+          this.forwardTextInput(socketId, event.data);
+          break;
+        case 'combo':
+          this.sendKeyCombo(socketId, event.data?.combo);
+          break;
+        case 'drag':
+          this.startDrag(socketId, event.data);
+          break;
+        case 'touch':
+          this.forwardTouchInput(socketId, event.data);
+          break;
+        case 'pen':
+          this.forwardPenInput(socketId, event.data);
+          break;
+      }
+    } catch (error) {
+      this.logger.debug(`Skipped ${event.kind} macro event in session ${session.id}: ${(error as Error).message}`);
+    }
+  }
+
+  // Drops a recording in progress and stops a replay
+  private endMacro(session: RDPSession): void {
+    session.macroRecorder = undefined;
+    if (!session.macroReplay) return;
+
+    clearTimeout(session.macroReplay);
+    session.macroReplay = undefined;
+    this.logger.info(`Stopped input macro replay in session ${session.id}`);
+    this.emit('macroReplayFinished', { sessionId: session.id, cancelled: true });
+  }
+
+  private cancelDrag(session: RDPSession): void {
+    if (!session.drag) return;
+
+    const { timer, button, x, y } = session.drag;
+    clearTimeout(timer);
+    session.drag = undefined;
+    // The host must not be left with the button held
+    this.writeDragInput(session, 'up', button, { x, y });
+    this.emit('dragCompleted', { sessionId: session.id, cancelled: true });
+  }
+
+  private writeDragInput(session: RDPSession, action: 'move' | 'down' | 'up', button: number, point: { x: number; y: number }): void {
+    const connection = this.rdpConnections.get(session.id);
+    if (connection && connection.isConnected) {
+      const inputData = this.buildMouseInputPacket({ ...point, button, action });
+      connection.socket.write(inputData);
+      connection.bytesSent += inputData.length;
+    }
+  }
+
+  // With a displayId the position is normalized (0..65535) within that
+  // display and is mapped onto the virtual desktop; without one it is taken
+  // as desktop pixels, as older clients send it
+  private resolvePointerPosition(session: RDPSession, data: any): { x: number; y: number } | undefined {
+    if (typeof data?.displayId === 'number') {
+      return toDesktopPoint(session.displays, data.displayId, Number(data.x) || 0, Number(data.y) || 0);
+    }
+    const clamp = (value: unknown) => Math.min(Math.max(Math.round(Number(value) || 0), 0), 0xffff);
+    return { x: clamp(data?.x), y: clamp(data?.y) };
+  }
+
+  public forwardKeyboardInput(socketId: string, data: any): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !this.canInject(session, 'keyboard')) return;
+
+    const flags = Number(data?.flags);
+    const release = (flags & KBDFLAGS_RELEASE) !== 0;
+    // The host acts on the scan code, so a mismatched keyCode must not get a
+    // blocked key past the filter; keyCode only counts when the scan code is unknown
+    const extended = (flags & (KBDFLAGS_EXTENDED | KBDFLAGS_EXTENDED1)) !== 0;
+    const virtualKey = virtualKeyForScanCode(Number(data?.scanCode) || 0, extended) ?? Number(data?.keyCode);
+    const hotkey = session.hotkeyFilter.check(virtualKey, release);
+    if (hotkey) {
+      if (!release) {
+        this.logger.info(`Blocked ${hotkey.entry} in session ${session.id}`);
+        this.emit('hotkeyBlocked', { sessionId: session.id, socketId: session.socketId, hotkey: hotkey.entry });
+      }
+      return;
+    }
+    this.noteInput(session, 'keyboard', data);
+
+    const connection = this.rdpConnections.get(session.id);
+    if (connection && connection.isConnected) {
+      const inputData = this.buildKeyboardInputPacket(data);
+      connection.socket.write(inputData);
+      connection.bytesSent += inputData.length;
+    }
+  }
+
+  public sendKeyCombo(socketId: string, combo: KeyCombo): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No active session');
+    }
+    if (session.viewOnly) {
+      throw new Error('Session is view-only');
+    }
+    if (!session.permissions.keyboard) {
+      throw new Error('Keyboard input is not permitted in this session');
+    }
+
+    const keys = KEY_COMBOS[combo];
+    if (!keys) {
+      throw new Error(`Unsupported key combination: ${combo}`);
+    }
+    if (session.hotkeyFilter.blocks(keys.map(key => key.virtualKey))) {
+      throw new Error(`Key combination blocked by policy: ${combo}`);
+    }
+    this.noteInput(session, 'combo', { combo });
+
+    const connection = this.rdpConnections.get(session.id);
+    if (!connection || !connection.isConnected) {
+      throw new Error('RDP connection not available');
+    }
+
+    this.writeKeyCombo(connection, keys);
+    session.lastActivity = new Date();
+    this.logger.info(`Sent ${combo} to session ${session.id}`);
+  }
+
+  private writeKeyCombo(connection: RDPConnection, keys: Array<{ scanCode: number; virtualKey: number; extended?: boolean }>): void {
+    const events = [
+      ...keys.map(key => ({ key, flags: KBDFLAGS_DOWN })),
+      ...[...keys].reverse().map(key => ({ key, flags: KBDFLAGS_RELEASE }))
+    ];
+    for (const { key, flags } of events) {
+      const inputData = this.buildKeyboardInputPacket({
+        keyCode: key.virtualKey,
+        scanCode: key.scanCode,
+        flags: flags | (key.extended ? KBDFLAGS_EXTENDED : 0)
+      });
+      connection.socket.write(inputData);
+      connection.bytesSent += inputData.length;
+    }
+  }
+
+  private noteInput(session: RDPSession, kind: MacroEventKind, data: any): void {
+    session.macroRecorder?.record(kind, data);
+    session.lastInputAt = Date.now();
+    this.leaveIdle(session);
+  }
+
+  // Signs of a user at the client that send no input (e.g. reading, using
+  // the client's own controls) keep the session from going idle
+  public noteClientActivity(socketId: string): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') return;
+
+    session.lastClientActivityAt = Date.now();
+    this.leaveIdle(session);
+  }
+
+  private leaveIdle(session: RDPSession): void {
+    if (!session.idle) return;
+
+    session.idle = false;
+    this.logger.info(`Session ${session.id} is active again`);
+    this.emit('sessionActive', { sessionId: session.id });
+  }
+
+  // Runs from the session monitor; acts once per idle period
+  private checkIdle(session: RDPSession, connection: RDPConnection, now: number): void {
+    const { idleTimeout, idleAction } = Config.getInstance().get('input');
+    if (idleTimeout <= 0 || session.idle || session.detachedAt !== undefined) return;
+
+    const idleFor = now - Math.max(session.lastInputAt, session.lastClientActivityAt);
+    if (idleFor < idleTimeout) return;
+
+    session.idle = true;
+    this.logger.info(`Session ${session.id} idle for ${Math.round(idleFor / 1000)}s (${idleAction})`);
+    this.emit('sessionIdle', { sessionId: session.id, socketId: session.socketId, idleFor, action: idleAction });
+
+    if (idleAction === 'lock') {
+      this.writeKeyCombo(connection, KEY_COMBOS['win-l']);
+    } else if (idleAction === 'disconnect') {
+      this.disconnectSession(session.socketId, 'idle_timeout').catch((error) => {
+        this.logger.error(`Failed to close idle session ${session.id}:`, error);
+      });
+    }
+  }
+
+  // Text that has no key sequence (IME output, emoji, pasted snippets) goes
+  // as Unicode keyboard events, one press and release per UTF-16 code unit
+  public forwardTextInput(socketId: string, data: any): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !this.canInject(session, 'keyboard')) return;
+    if (typeof data?.text !== 'string' || data.text.length === 0) return;
+    this.noteInput(session, 'text', data);
+
+    const connection = this.rdpConnections.get(session.id);
+    if (connection && connection.isConnected) {
+      const text = data.text.slice(0, MAX_TEXT_INPUT_LENGTH);
+      for (let i = 0; i < text.length; i++) {
+        const codeUnit = text.charCodeAt(i);
+        for (const release of [false, true]) {
+          const inputData = this.buildUnicodeInputPacket(codeUnit, release);
+          connection.socket.write(inputData);
+          connection.bytesSent += inputData.length;
+        }
+      }
+    }
+  }
+
+  public forwardTouchInput(socketId: string, data: any): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !this.canInject(session, 'mouse')) return;
+    this.noteInput(session, 'touch', data);
+
+    // A frame of contacts carries every finger currently on the screen, so
+    // gestures such as pinch and two-finger scroll reach the host intact
+    if (Array.isArray(data?.contacts)) {
+      this.forwardTouchFrame(session, data.displayId, data.contacts);
+      return;
+    }
+
+    const connection = this.rdpConnections.get(session.id);
+    if (connection && connection.isConnected) {
+      const inputData = this.buildTouchInputPacket(data);
+      connection.socket.write(inputData);
+      connection.bytesSent += inputData.length;
+    }
+  }
+
+  public forwardPenInput(socketId: string, data: PenEvent): void {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || !this.canInject(session, 'mouse')) return;
+    this.noteInput(session, 'pen', data);
+
+    const point = this.resolvePointerPosition(session, data);
+    if (!point) return;
+
+    const connection = this.rdpConnections.get(session.id);
+    if (connection && connection.isConnected) {
+      const inputData = this.buildPenInputPacket(data, point);
+      connection.socket.write(inputData);
+      connection.bytesSent += inputData.length;
+    }
+  }
+
+  private forwardTouchFrame(session: RDPSession, displayId: number | undefined, contacts: TouchContact[]): void {
+    const frame: TouchFrameContact[] = [];
+
+    for (const contact of contacts) {
+      let contactId = session.touchContacts.get(contact.id);
+      if (contactId === undefined) {
+        if (contact.action !== 'down') continue;
+        contactId = this.allocateTouchContact(session);
+        if (contactId === undefined) continue;
+        session.touchContacts.set(contact.id, contactId);
+      }
+
+      const center = this.resolvePointerPosition(session, { displayId, x: contact.x, y: contact.y });
+      if (!center) continue;
+
+      // Contact area as offsets from the center, mapped like the position
+      const halfWidth = (Number(contact.width) || 0) / 2;
+      const halfHeight = (Number(contact.height) || 0) / 2;
+      const topLeft = this.resolvePointerPosition(session, { displayId, x: contact.x - halfWidth, y: contact.y - halfHeight }) ?? center;
+      const bottomRight = this.resolvePointerPosition(session, { displayId, x: contact.x + halfWidth, y: contact.y + halfHeight }) ?? center;
+
+      frame.push({
+        contactId,
+        flags: this.touchContactFlags(contact.action),
+        x: center.x,
+        y: center.y,
+        rect: [topLeft.x - center.x, topLeft.y - center.y, bottomRight.x - center.x, bottomRight.y - center.y],
+        orientation: ((Math.round(Number(contact.orientation) || 0) % 360) + 360) % 360,
+        pressure: Math.min(Math.max(Math.round(Number(contact.pressure ?? TOUCH_PRESSURE_MAX)), 0), TOUCH_PRESSURE_MAX)
+      });
+
+      if (contact.action === 'up' || contact.action === 'cancel') {
+        session.touchContacts.delete(contact.id);
+      }
+    }
+
+    if (frame.length === 0) return;
+
+    const connection = this.rdpConnections.get(session.id);
+    if (connection && connection.isConnected) {
+      const inputData = this.buildTouchFramePacket(frame);
+      connection.socket.write(inputData);
+      connection.bytesSent += inputData.length;
+    }
+  }
+
+  // Lowest host contact id not held by another finger
+  private allocateTouchContact(session: RDPSession): number | undefined {
+    const used = new Set(session.touchContacts.values());
+    for (let contactId = 0; contactId < MAX_TOUCH_CONTACTS; contactId++) {
+      if (!used.has(contactId)) return contactId;
+    }
+    return undefined;
+  }
+
+  private touchContactFlags(action: TouchContact['action'] | PenEvent['action']): number {
+    switch (action) {
+      case 'hover':
+        return CONTACT_FLAG_UPDATE | CONTACT_FLAG_INRANGE;
+      case 'down':
+        return CONTACT_FLAG_DOWN | CONTACT_FLAG_INRANGE | CONTACT_FLAG_INCONTACT;
+      case 'up':
+        return CONTACT_FLAG_UP;
+      case 'cancel':
+        return CONTACT_FLAG_UP | CONTACT_FLAG_CANCELED;
+      default:
+        return CONTACT_FLAG_UPDATE | CONTACT_FLAG_INRANGE | CONTACT_FLAG_INCONTACT;
+    }
+  }
+
+  private buildMouseInputPacket(data: any): Buffer {
+    // Build RDP mouse input packet
+    const clamp = (value: unknown, min: number, max: number) => Math.min(Math.max(Math.round(Number(value) || 0), min), max);
     const packet = Buffer.alloc(20);
     packet.writeUInt8(0x03, 0); // TPKT version
     packet.writeUInt8(0x00, 1); // Reserved
     packet.writeUInt16BE(20, 2); // Length
     packet.writeUInt8(0x08, 4); // Input PDU
     packet.writeUInt8(0x01, 5); // Mouse input
-    packet.writeUInt16BE(data.x, 6);
-    packet.writeUInt16BE(data.y, 8);
-    packet.writeUInt8(data.button, 10);
+    packet.writeUInt16BE(clamp(data.x, 0, 0xffff), 6);
+    packet.writeUInt16BE(clamp(data.y, 0, 0xffff), 8);
+    packet.writeUInt8(clamp(data.button, 0, 0xff), 10);
     packet.writeUInt8(data.action === 'down' ? 0x01 : 0x00, 11);
-    packet.writeUInt16BE(data.wheel || 0, 12);
+    // Signed: negative deltas scroll up
+    packet.writeInt16BE(clamp(data.wheel, -0x8000, 0x7fff), 12);
     
     return packet;
   }
 
+  private buildRelativeMouseInputPacket(data: any): Buffer {
+    // Build RDP mouse input packet carrying motion rather than a position
+    const clamp = (value: unknown) => Math.min(Math.max(Math.round(Number(value) || 0), -0x8000), 0x7fff);
+    const packet = Buffer.alloc(20);
+    packet.writeUInt8(0x03, 0); // TPKT version
+    packet.writeUInt8(0x00, 1); // Reserved
+    packet.writeUInt16BE(20, 2); // Length
+    packet.writeUInt8(0x08, 4); // Input PDU
+    packet.writeUInt8(0x05, 5); // Relative mouse input
+    packet.writeInt16BE(clamp(data.deltaX), 6);
+    packet.writeInt16BE(clamp(data.deltaY), 8);
+    packet.writeUInt8(data.button || 0, 10);
+    packet.writeUInt8(data.action === 'down' ? 0x01 : 0x00, 11);
+
+    return packet;
+  }
+
   private buildKeyboardInputPacket(data: any): Buffer {
     // Build RDP keyboard input packet
     const packet = Buffer.alloc(20);
@@ -498,6 +2207,20 @@ export class RDPSessionManager extends EventEmitter {
     return packet;
   }
 
+  private buildUnicodeInputPacket(codeUnit: number, release: boolean): Buffer {
+    // Build RDP Unicode keyboard input packet
+    const packet = Buffer.alloc(20);
+    packet.writeUInt8(0x03, 0); // TPKT version
+    packet.writeUInt8(0x00, 1); // Reserved
+    packet.writeUInt16BE(20, 2); // Length
+    packet.writeUInt8(0x08, 4); // Input PDU
+    packet.writeUInt8(0x04, 5); // Unicode input
+    packet.writeUInt16BE(release ? KBDFLAGS_RELEASE : 0, 6);
+    packet.writeUInt16BE(codeUnit, 8);
+
+    return packet;
+  }
+
   private buildTouchInputPacket(data: any): Buffer {
     // Build RDP touch input packet
     const packet = Buffer.alloc(20);
@@ -514,9 +2237,64 @@ export class RDPSessionManager extends EventEmitter {
     return packet;
   }
 
+  private buildPenInputPacket(data: PenEvent, point: { x: number; y: number }): Buffer {
+    // Build RDP pen input packet
+    const inContact = data.action === 'down' || data.action === 'move';
+    let penFlags = data.barrel ? PEN_FLAG_BARREL_PRESSED : 0;
+    if (data.eraser) {
+      penFlags |= PEN_FLAG_INVERTED | (inContact ? PEN_FLAG_ERASER_PRESSED : 0);
+    }
+    const tilt = (value: unknown) => Math.min(Math.max(Math.round(Number(value) || 0), -90), 90);
+
+    const packet = Buffer.alloc(24);
+    packet.writeUInt8(0x03, 0); // TPKT version
+    packet.writeUInt8(0x00, 1); // Reserved
+    packet.writeUInt16BE(24, 2); // Length
+    packet.writeUInt8(0x08, 4); // Input PDU
+    packet.writeUInt8(0x07, 5); // Pen input
+    packet.writeUInt16BE(this.touchContactFlags(data.action), 6);
+    packet.writeUInt8(penFlags, 8);
+    packet.writeUInt16BE(point.x, 9);
+    packet.writeUInt16BE(point.y, 11);
+    packet.writeUInt16BE(Math.min(Math.max(Math.round(Number(data.pressure) || 0), 0), TOUCH_PRESSURE_MAX), 13);
+    packet.writeUInt16BE(((Math.round(Number(data.rotation) || 0) % 360) + 360) % 360, 15);
+    packet.writeInt16BE(tilt(data.tiltX), 17);
+    packet.writeInt16BE(tilt(data.tiltY), 19);
+
+    return packet;
+  }
+
+  private buildTouchFramePacket(contacts: TouchFrameContact[]): Buffer {
+    // Build RDP multi-touch frame packet, 19 bytes per contact
+    const length = 7 + contacts.length * 19;
+    const packet = Buffer.alloc(length);
+    packet.writeUInt8(0x03, 0); // TPKT version
+    packet.writeUInt8(0x00, 1); // Reserved
+    packet.writeUInt16BE(length, 2); // Length
+    packet.writeUInt8(0x08, 4); // Input PDU
+    packet.writeUInt8(0x06, 5); // Touch frame
+    packet.writeUInt8(contacts.length, 6);
+
+    contacts.forEach((contact, index) => {
+      const offset = 7 + index * 19;
+      packet.writeUInt8(contact.contactId, offset);
+      packet.writeUInt16BE(contact.flags, offset + 1);
+      packet.writeUInt16BE(contact.x, offset + 3);
+      packet.writeUInt16BE(contact.y, offset + 5);
+      contact.rect.forEach((edge, i) => packet.writeInt16BE(edge, offset + 7 + i * 2));
+      packet.writeUInt16BE(contact.orientation, offset + 15);
+      packet.writeUInt16BE(contact.pressure, offset + 17);
+    });
+
+    return packet;
+  }
+
   public setClipboard(socketId: string, data: any): void {
     const session = this.findSessionBySocketId(socketId);
-    if (!session || session.status !== 'connected') return;
+    if (!session || !this.canInject(session, 'clipboard')) return;
+
+    const content = typeof data?.text === 'string' ? data.text : JSON.stringify(data);
+    if (!this.resolveClipboardUpdate(session, 'client', content)) return;
 
     const connection = this.rdpConnections.get(session.id);
     if (connection && connection.isConnected) {
@@ -528,7 +2306,7 @@ export class RDPSessionManager extends EventEmitter {
 
   public getClipboard(socketId: string): void {
     const session = this.findSessionBySocketId(socketId);
-    if (!session || session.status !== 'connected') return;
+    if (!session || !this.canInject(session, 'clipboard')) return;
 
     const connection = this.rdpConnections.get(session.id);
     if (connection && connection.isConnected) {
@@ -538,6 +2316,44 @@ export class RDPSessionManager extends EventEmitter {
     }
   }
 
+  // Returns true when the update should be applied and propagated to the other side
+  private resolveClipboardUpdate(session: RDPSession, origin: ClipboardOrigin, content: string): boolean {
+    const rdpConfig = Config.getInstance().get('rdp');
+    const now = Date.now();
+    const current = session.clipboard;
+
+    // Echo of what we already synced; dropping it stops host/client ping-pong
+    if (current && current.content === content) {
+      return false;
+    }
+
+    const conflicting = current !== undefined
+      && current.origin !== origin
+      && now - current.timestamp < rdpConfig.clipboardConflictWindow;
+
+    if (conflicting && current) {
+      const incoming: ClipboardState = { content, origin, timestamp: now };
+      const hostWins = rdpConfig.clipboardConflictPolicy === 'host-priority';
+      const winner = hostWins ? (origin === 'host' ? incoming : current) : incoming;
+      const overwritten = winner === incoming ? current : incoming;
+
+      this.logger.info(`Clipboard conflict in session ${session.id}: ${winner.origin} wins (${rdpConfig.clipboardConflictPolicy})`);
+      this.emit('clipboardConflict', {
+        sessionId: session.id,
+        policy: rdpConfig.clipboardConflictPolicy,
+        winner: winner.origin,
+        overwritten
+      });
+
+      if (winner !== incoming) {
+        return false;
+      }
+    }
+
+    session.clipboard = { content, origin, timestamp: now };
+    return true;
+  }
+
   private buildClipboardPacket(data: any): Buffer {
     const dataBuffer = Buffer.from(JSON.stringify(data), 'utf8');
     const packet = Buffer.alloc(11 + dataBuffer.length);
@@ -565,13 +2381,23 @@ export class RDPSessionManager extends EventEmitter {
   }
 
   public changeQuality(socketId: string, quality: string): void {
+    if (!isQuality(quality)) {
+      throw new Error(`Unknown quality: ${quality}`);
+    }
     const session = this.findSessionBySocketId(socketId);
     if (!session || session.status !== 'connected') return;
 
-    session.config.quality = quality as any;
+    session.config.quality = quality;
+    session.maxBitrate = Math.min(QUALITY_BITRATES[session.config.quality] || QUALITY_BITRATES.medium, session.bitrateCeiling);
     session.lastActivity = new Date();
+    if (session.bitrateController) {
+      session.bitrateController.setCeiling(session.maxBitrate);
+      this.applyTargetBitrate(session, session.bitrateController.target);
+    } else {
+      this.applyTargetBitrate(session, session.maxBitrate);
+    }
     
-    this.emit('qualityChanged', { sessionId: session.id, quality });
+    this.emit('qualityChanged', { sessionId: session.id, quality, maxBitrate: session.maxBitrate });
   }
 
   public setFullscreen(socketId: string, enabled: boolean): void {
@@ -586,13 +2412,256 @@ export class RDPSessionManager extends EventEmitter {
     const session = this.findSessionBySocketId(socketId);
     if (!session || session.status !== 'connected') return;
 
+    if (session.displays.some(display => display.id === monitorIndex)) {
+      session.displaySubscription = { mode: 'single', displayIds: [monitorIndex] };
+    }
+
     session.lastActivity = new Date();
     this.emit('monitorChanged', { sessionId: session.id, monitorIndex });
   }
 
+  public resizeDisplay(socketId: string, width: number, height: number, displayId: number = 0): RDPDisplay[] {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No connected session');
+    }
+
+    const display = session.displays.find(d => d.id === displayId);
+    if (!display) {
+      throw new Error(`Unknown display: ${displayId}`);
+    }
+
+    if (!Number.isFinite(width) || !Number.isFinite(height)) {
+      throw new Error('Display width and height must be numbers');
+    }
+
+    // RDP requires even widths; keep within the Display Control limits
+    const clampedWidth = Math.min(8192, Math.max(200, Math.floor(width))) & ~1;
+    const clampedHeight = Math.min(8192, Math.max(200, Math.floor(height)));
+
+    if (display.width === clampedWidth && display.height === clampedHeight) {
+      return session.displays;
+    }
+
+    // Only this monitor changes; the others keep their origins, which may be
+    // negative or stacked vertically in the host's layout
+    display.width = clampedWidth;
+    display.height = clampedHeight;
+
+    if (display.primary) {
+      session.config.width = clampedWidth;
+      session.config.height = clampedHeight;
+    }
+
+    const connection = this.rdpConnections.get(session.id);
+    if (connection && connection.isConnected) {
+      const packet = this.buildDisplayControlPacket(session.displays);
+      connection.socket.write(packet);
+      connection.bytesSent += packet.length;
+    }
+
+    session.lastActivity = new Date();
+    this.logger.info(`Display ${displayId} of session ${session.id} resized to ${clampedWidth}x${clampedHeight}`);
+    this.emit('displayChanged', {
+      sessionId: session.id,
+      displays: session.displays,
+      displaySubscription: session.displaySubscription,
+      reason: 'client_resize'
+    });
+
+    return session.displays;
+  }
+
+  // Asks the host for a full frame, e.g. after the client lost data or just
+  // started watching a display, instead of waiting for the next full update
+  public requestRefresh(socketId: string, displayIds?: number[]): void {
+    const session = this.findSessionBySocketId(socketId);
+    const connection = session ? this.rdpConnections.get(session.id) : undefined;
+    if (!session || !connection || !connection.isConnected) return;
+
+    // Anything but an array of ids from the client means its subscribed displays
+    const requested = Array.isArray(displayIds) ? displayIds : session.displaySubscription.displayIds;
+    const targets = session.displays.map(display => display.id).filter(id => requested.includes(id));
+    if (targets.length === 0) return;
+
+    // Collapse refresh storms from clients that keep failing to decode
+    const now = Date.now();
+    if (now - session.lastRefreshAt < REFRESH_MIN_INTERVAL) return;
+    session.lastRefreshAt = now;
+    session.pipelineMetrics.recordRefresh();
+    session.codec.requestKeyframe?.();
+
+    // Queued frames are superseded by the full frame that follows
+    for (const id of targets) {
+      const frameBuffer = connection.frameBuffers.get(id);
+      if (frameBuffer) frameBuffer.length = 0;
+    }
+    this.recordReceiveQueueDepth(connection);
+
+    const packet = Buffer.alloc(6 + targets.length);
+    packet.writeUInt8(0x03, 0); // TPKT version
+    packet.writeUInt8(0x00, 1); // Reserved
+    packet.writeUInt16BE(packet.length, 2); // Length
+    packet.writeUInt8(0x0A, 4); // Refresh PDU
+    packet.writeUInt8(targets.length, 5); // Display count
+    targets.forEach((id, index) => packet.writeUInt8(id, 6 + index));
+
+    connection.socket.write(packet);
+    connection.bytesSent += packet.length;
+    this.logger.debug(`Requested refresh of displays [${targets.join(', ')}] for session ${session.id}`);
+  }
+
+  private buildDisplayControlPacket(displays: RDPDisplay[]): Buffer {
+    // Monitor layout PDU: 18 bytes per monitor after a 6 byte header
+    const packet = Buffer.alloc(6 + displays.length * 18);
+    packet.writeUInt8(0x03, 0); // TPKT version
+    packet.writeUInt8(0x00, 1); // Reserved
+    packet.writeUInt16BE(packet.length, 2); // Length
+    packet.writeUInt8(0x09, 4); // Display control PDU
+    packet.writeUInt8(displays.length, 5); // Monitor count
+
+    displays.forEach((display, index) => {
+      const offset = 6 + index * 18;
+      packet.writeUInt8(display.id, offset);
+      packet.writeUInt8(display.primary ? 0x01 : 0x00, offset + 1);
+      packet.writeInt32BE(display.x, offset + 2);
+      packet.writeInt32BE(display.y, offset + 6);
+      packet.writeUInt32BE(display.width, offset + 10);
+      packet.writeUInt32BE(display.height, offset + 14);
+    });
+
+    return packet;
+  }
+
+  public subscribeDisplays(socketId: string, mode: DisplaySubscriptionMode, displayIds: number[] = []): {
+    subscription: DisplaySubscription;
+    displays: RDPDisplay[];
+  } {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No active session');
+    }
+
+    const knownIds = session.displays.map(display => display.id);
+    const unknownIds = displayIds.filter(id => !knownIds.includes(id));
+    if (unknownIds.length > 0) {
+      throw new Error(`Unknown display(s): ${unknownIds.join(', ')}`);
+    }
+
+    let subscription: DisplaySubscription;
+    switch (mode) {
+      case 'single':
+        if (displayIds.length !== 1) {
+          throw new Error('Single display subscription requires exactly one display');
+        }
+        subscription = { mode, displayIds: [...displayIds] };
+        break;
+      case 'multiple':
+        if (displayIds.length === 0) {
+          throw new Error('Multiple display subscription requires at least one display');
+        }
+        subscription = { mode, displayIds: Array.from(new Set(displayIds)) };
+        break;
+      case 'virtual':
+        // The stitched virtual desktop always spans every display
+        subscription = { mode, displayIds: knownIds };
+        break;
+      default:
+        throw new Error(`Unsupported display subscription mode: ${mode}`);
+    }
+
+    const added = subscription.displayIds.filter(id => !session.displaySubscription.displayIds.includes(id));
+    session.displaySubscription = subscription;
+    session.lastActivity = new Date();
+
+    // Frames for newly watched displays were being discarded; get a full one now
+    if (added.length > 0) {
+      this.requestRefresh(socketId, added);
+    }
+
+    this.logger.info(`Session ${session.id} subscribed to displays [${subscription.displayIds.join(', ')}] (${mode})`);
+    this.emit('displaySubscriptionChanged', { sessionId: session.id, subscription });
+
+    return {
+      subscription,
+      displays: session.displays.filter(display => subscription.displayIds.includes(display.id))
+    };
+  }
+
+  public hasPermission(socketId: string, permission: SessionPermission): boolean {
+    const session = this.findSessionBySocketId(socketId);
+    return session !== undefined && this.canInject(session, permission);
+  }
+
+  // Takes effect on the next input; unknown keys are ignored
+  public setPermissions(sessionId: string, changes: Partial<SessionPermissions>): SessionPermissions {
+    const session = this.sessions.get(sessionId);
+    if (!session || session.status === 'disconnected') {
+      throw new Error('Session not found');
+    }
+    if (session.viewOnly) {
+      throw new Error('Session is view-only');
+    }
+
+    for (const permission of SESSION_PERMISSIONS) {
+      if (typeof changes?.[permission] === 'boolean') {
+        session.permissions[permission] = changes[permission] as boolean;
+      }
+    }
+    // Held-back moves must not reach the host once the mouse is revoked
+    if (!session.permissions.mouse) {
+      session.inputLimiter.clear();
+    }
+
+    const granted = SESSION_PERMISSIONS.filter(permission => session.permissions[permission]);
+    this.logger.info(`Session ${session.id} permissions set to [${granted.join(', ')}]`);
+    this.emit('permissionsChanged', { sessionId: session.id, permissions: { ...session.permissions } });
+    return { ...session.permissions };
+  }
+
+  public setKeyboardMode(socketId: string, mode: KeyboardMode): KeyboardMode {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No active session');
+    }
+    if (mode !== 'auto' && mode !== 'position' && mode !== 'character') {
+      throw new Error(`Unsupported keyboard mode: ${mode}`);
+    }
+
+    session.keyboardMode = mode;
+    session.lastActivity = new Date();
+
+    this.logger.info(`Session ${session.id} keyboard mode set to ${mode}`);
+    return mode;
+  }
+
+  public setPointerMode(socketId: string, mode: PointerMode): PointerMode {
+    const session = this.findSessionBySocketId(socketId);
+    if (!session || session.status !== 'connected') {
+      throw new Error('No active session');
+    }
+    if (mode !== 'absolute' && mode !== 'relative') {
+      throw new Error(`Unsupported pointer mode: ${mode}`);
+    }
+
+    if (session.pointerMode !== mode) {
+      session.pointerMode = mode;
+      session.pointerModeChangedAt = Date.now();
+    }
+    session.lastActivity = new Date();
+
+    this.logger.info(`Session ${session.id} pointer mode set to ${mode}`);
+    return mode;
+  }
+
+  private canInject(session: RDPSession, permission: SessionPermission): boolean {
+    return session.status === 'connected' && !session.viewOnly && session.permissions[permission];
+  }
+
   private findSessionBySocketId(socketId: string): RDPSession | undefined {
+    // A socket may have ended earlier sessions; only the live one is relevant
     for (const session of this.sessions.values()) {
-      if (session.socketId === socketId) {
+      if (session.socketId === socketId && session.status !== 'disconnected') {
         return session;
       }
     }
@@ -603,6 +2672,11 @@ export class RDPSessionManager extends EventEmitter {
     return this.sessions.get(sessionId);
   }
 
+  // The live session the socket controls
+  public getSessionBySocketId(socketId: string): RDPSession | undefined {
+    return this.findSessionBySocketId(socketId);
+  }
+
   public getAllSessions(): RDPSession[] {
     return Array.from(this.sessions.values());
   }
@@ -618,7 +2692,9 @@ export class RDPSessionManager extends EventEmitter {
     
     // Disconnect all sessions
     for (const session of this.sessions.values()) {
-      await this.disconnectSession(session.socketId);
+      if (session.status !== 'disconnected') {
+        await this.disconnectSession(session.socketId, 'server_shutdown');
+      }
     }
     
     this.sessions.clear();
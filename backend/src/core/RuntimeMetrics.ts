@@ -0,0 +1,69 @@
+import { performance, monitorEventLoopDelay, EventLoopUtilization, IntervalHistogram } from 'perf_hooks';
+
+export interface RuntimeMetricsSnapshot {
+  // Fraction of the last sample window the event loop spent busy (0..1)
+  eventLoopUtilization: number;
+  eventLoopDelay: {
+    p50: number;
+    p99: number;
+    max: number;
+  };
+  threadPoolSize: number;
+}
+
+const SAMPLE_INTERVAL = 1000;
+
+// Control-plane work (REST, JSON, logging) shares the main event loop, while
+// frame scaling and compression run on the libuv thread pool. This tracks how
+// busy the loop is so jitter from control traffic shows up in metrics.
+export class RuntimeMetrics {
+  private static instance: RuntimeMetrics;
+  private delay: IntervalHistogram;
+  private previous: EventLoopUtilization;
+  private latest: RuntimeMetricsSnapshot = {
+    eventLoopUtilization: 0,
+    eventLoopDelay: { p50: 0, p99: 0, max: 0 },
+    threadPoolSize: parseInt(process.env.UV_THREADPOOL_SIZE || '4')
+  };
+
+  private constructor() {
+    this.delay = monitorEventLoopDelay({ resolution: 10 });
+    this.delay.enable();
+    this.previous = performance.eventLoopUtilization();
+
+    const interval = setInterval(() => this.sample(), SAMPLE_INTERVAL);
+    interval.unref();
+  }
+
+  public static getInstance(): RuntimeMetrics {
+    if (!RuntimeMetrics.instance) {
+      RuntimeMetrics.instance = new RuntimeMetrics();
+    }
+    return RuntimeMetrics.instance;
+  }
+
+  private sample(): void {
+    const current = performance.eventLoopUtilization();
+    const utilization = performance.eventLoopUtilization(current, this.previous).utilization;
+    this.previous = current;
+
+    // Histogram values are in nanoseconds
+    const toMs = (value: number) => Math.round(value / 1e4) / 100;
+
+    this.latest = {
+      ...this.latest,
+      eventLoopUtilization: Math.round(utilization * 1000) / 1000,
+      eventLoopDelay: {
+        p50: toMs(this.delay.percentile(50)),
+        p99: toMs(this.delay.percentile(99)),
+        max: toMs(this.delay.max)
+      }
+    };
+    this.delay.reset();
+  }
+
+  // Figures cover the most recent one second window
+  public snapshot(): RuntimeMetricsSnapshot {
+    return this.latest;
+  }
+}
@@ -1,5 +1,9 @@
 import express from 'express';
 import { createServer } from 'http';
+import { createServer as createHttpsServer, ServerOptions as HttpsServerOptions } from 'https';
+import { createSecureContext, SecureContext } from 'tls';
+import { readFileSync } from 'fs';
+import { Socket as NetSocket } from 'net';
 import { Server as SocketIOServer } from 'socket.io';
 import cors from 'cors';
 import helmet from 'helmet';
@@ -12,41 +16,139 @@ import { AuthManager } from '../services/AuthManager';
 import { FileTransferManager } from '../services/FileTransferManager';
 import { DeviceRedirectionManager } from '../services/DeviceRedirectionManager';
 import { AuditLogger } from '../services/AuditLogger';
+import { CapabilityReporter, GATEWAY_PROTOCOL_VERSION, MIN_CLIENT_PROTOCOL_VERSION } from '../services/CapabilityReporter';
+import { ControlChannel } from './ControlChannel';
+import { RuntimeMetrics } from './RuntimeMetrics';
+import { OPUS_SAMPLE_RATE } from './AudioEncoder';
+import { RawSocketServer } from '../ws/RawSocketServer';
+import { Transport, TransportRegistry, GatewaySocket } from '../ws/Transport';
+import { SocketIOTransport } from '../ws/SocketIOTransport';
+import { FrameSendQueue } from '../ws/FrameSendQueue';
+import { FrameBatcher } from '../ws/FrameBatcher';
+import { SignalingServer } from '../ws/SignalingServer';
+import { RelayClient } from '../ws/RelayClient';
+import { TcpSocketServer } from '../ws/TcpSocketServer';
+import { IceServerManager, IceServersUpdate } from '../services/IceServerManager';
+import { HookDispatcher } from '../services/HookDispatcher';
+import { DiscoveryAdvertiser } from '../services/DiscoveryAdvertiser';
+import { ConnectionGuard, ConnectionViolationEvent } from '../services/ConnectionGuard';
 import { Config } from '../utils/Config';
+import { resolveBindAddress } from '../utils/Network';
 
 // Load environment variables
 dotenv.config();
 
 const logger = new Logger('RDPGateway');
+
+interface OutboundFrame {
+  sessionId: string;
+  message: any;
+  data: Buffer;
+}
 const config = Config.getInstance();
 
 class RDPGateway {
   private app: express.Application;
   private server: any;
   private io: SocketIOServer;
+  private rawSocketServer: RawSocketServer | null = null;
+  // Every way clients reach the gateway; routing only goes through this
+  private transports = TransportRegistry.getInstance();
+  private signalingServer: SignalingServer | null = null;
+  private relayClient: RelayClient | null = null;
+  private discoveryAdvertiser: DiscoveryAdvertiser | null = null;
+  private frameQueues: Map<string, FrameSendQueue<OutboundFrame>> = new Map();
   private rdpSessionManager!: RDPSessionManager;
   private wsManager!: WebSocketManager;
   private authManager!: AuthManager;
   private fileTransferManager!: FileTransferManager;
   private deviceRedirectionManager!: DeviceRedirectionManager;
   private auditLogger!: AuditLogger;
+  private capabilityReporter!: CapabilityReporter;
+  private controlChannel: ControlChannel | null = null;
+  private iceServerManager!: IceServerManager;
+  private hookDispatcher!: HookDispatcher;
 
   constructor() {
     this.app = express();
     this.setupMiddleware();
     this.setupRoutes();
     
-    this.server = createServer(this.app);
+    this.server = config.get('server').ssl.enabled
+      ? createHttpsServer(this.buildTlsOptions(), this.app)
+      : createServer(this.app);
+
+    // Disallowed addresses are dropped before TLS or HTTP parsing
+    this.server.on('connection', (socket: NetSocket) => {
+      if (!ConnectionGuard.getInstance().isAllowed(socket.remoteAddress, 'tcp')) {
+        socket.destroy();
+        return;
+      }
+      socket.setNoDelay(config.get('server').socket.noDelay);
+    });
+
     this.io = new SocketIOServer(this.server, {
       cors: {
         origin: config.get('cors').allowedOrigins,
         methods: ['GET', 'POST'],
         credentials: true
       },
-      transports: ['websocket', 'polling']
+      transports: ['websocket', 'polling'],
+      pingInterval: config.get('websocket').pingInterval,
+      pingTimeout: config.get('websocket').pingTimeout,
+      // Only called for new handshakes, not for every polling request
+      allowRequest: (req, callback) => {
+        callback(null, ConnectionGuard.getInstance().admitHandshake(req.socket.remoteAddress, 'socket.io'));
+      },
+      perMessageDeflate: config.get('websocket').compression
+        ? { threshold: config.get('websocket').compressionThreshold }
+        : false
     });
 
+    this.transports.register(new SocketIOTransport(this.io));
+
+    const websocketConfig = config.get('websocket');
+    // Relayed clients are plain WebSocket clients too, even without a local endpoint
+    if (websocketConfig.rawPath || websocketConfig.relayUrl) {
+      this.rawSocketServer = new RawSocketServer(
+        websocketConfig.rawPath,
+        websocketConfig.pingInterval,
+        websocketConfig.heartbeatMisses,
+        websocketConfig.rawMaxPayload,
+        websocketConfig.compression ? websocketConfig.compressionThreshold : false
+      );
+      this.transports.register(this.rawSocketServer);
+    }
+
+    const nativeConfig = config.get('native');
+    if (nativeConfig.tcpPort > 0) {
+      const ssl = config.get('server').ssl;
+      this.transports.register(new TcpSocketServer({
+        host: config.get('server').host,
+        socket: config.get('server').socket,
+        tcpPort: nativeConfig.tcpPort,
+        udpPort: nativeConfig.udpPort,
+        maxPayload: nativeConfig.maxPayload,
+        fecGroupSize: nativeConfig.fecGroupSize,
+        fecLossThreshold: nativeConfig.fecLossThreshold,
+        pingInterval: websocketConfig.pingInterval,
+        heartbeatMisses: websocketConfig.heartbeatMisses,
+        tls: ssl.enabled ? this.buildTlsOptions() : undefined
+      }));
+    }
+
     this.initializeServices();
+
+    if (websocketConfig.signalingPath) {
+      this.signalingServer = new SignalingServer(
+        websocketConfig.signalingPath,
+        this.authManager,
+        this.iceServerManager,
+        this.rdpSessionManager
+      );
+      this.signalingServer.attach(this.server);
+    }
+    this.setupSessionEventHandlers();
     this.setupSocketHandlers();
   }
 
@@ -93,7 +195,8 @@ class RDPGateway {
       res.json({
         status: 'healthy',
         timestamp: new Date().toISOString(),
-        version: process.env.npm_package_version || '1.0.0'
+        version: process.env.npm_package_version || '1.0.0',
+        runtime: RuntimeMetrics.getInstance().snapshot()
       });
     });
 
@@ -103,10 +206,12 @@ class RDPGateway {
     this.app.use('/api/files', require('../routes/files').default);
     this.app.use('/api/devices', require('../routes/devices').default);
     this.app.use('/api/audit', require('../routes/audit').default);
+    this.app.use('/api/webrtc', require('../routes/webrtc').default);
+    this.app.use('/api/transports', require('../routes/transports').default);
 
-    // WebSocket upgrade endpoint
+    // Plain HTTP requests to the WebSocket path get pointed at the right protocols
     this.app.get('/ws', (req, res) => {
-      res.send('WebSocket endpoint available at /socket.io/');
+      res.send('Socket.IO endpoint available at /socket.io/; plain WebSocket endpoint at ' + (config.get('websocket').rawPath || '(disabled)'));
     });
 
     // 404 handler
@@ -128,157 +233,868 @@ class RDPGateway {
   }
 
   private initializeServices(): void {
-    this.rdpSessionManager = new RDPSessionManager();
+    this.rdpSessionManager = RDPSessionManager.getInstance();
     this.wsManager = new WebSocketManager(this.io);
     this.authManager = new AuthManager();
     this.fileTransferManager = new FileTransferManager();
     this.deviceRedirectionManager = new DeviceRedirectionManager();
     this.auditLogger = new AuditLogger();
+    this.capabilityReporter = new CapabilityReporter();
+    this.capabilityReporter.probe();
+    this.iceServerManager = IceServerManager.getInstance();
+    this.hookDispatcher = new HookDispatcher();
+
+    ConnectionGuard.getInstance().on('violation', (event: ConnectionViolationEvent) => {
+      logger.warn(`Rejected ${event.transport} connection from ${event.ip}: ${event.violation}`);
+      this.auditLogger.logSecurityEvent('gateway', {
+        type: event.violation,
+        ipAddress: event.ip,
+        transport: event.transport,
+        severity: 'high'
+      });
+    });
+
+    const fileTransferConfig = config.get('fileTransfer');
+    if (fileTransferConfig.watchDir) {
+      this.fileTransferManager.startWatchFolder(fileTransferConfig.watchDir, fileTransferConfig.watchSettleDelay);
+    }
 
     logger.info('All services initialized');
   }
 
-  private setupSocketHandlers(): void {
-    this.io.on('connection', (socket) => {
-      logger.info(`Client connected: ${socket.id}`);
-
-      // Authentication
-      socket.on('authenticate', async (data) => {
-        try {
-          const authResult = await this.authManager.authenticateSocket(socket, data);
-          if (authResult.success) {
-            socket.emit('authenticated', { success: true });
-            this.auditLogger.logConnection(socket.id, data);
-          } else {
-            socket.emit('authenticated', { success: false, error: authResult.error });
+  private setupSessionEventHandlers(): void {
+    // Deliver processed frames to the controller and every viewer; each has
+    // its own queue, so a slow viewer only holds back itself
+    this.rdpSessionManager.on('frameProcessed', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session) return;
+
+      const frame = {
+        sessionId: event.sessionId,
+        displayId: event.displayId,
+        frameId: event.frameId,
+        codec: event.codec,
+        x: event.x,
+        y: event.y,
+        width: event.width,
+        height: event.height,
+        timestamp: event.timestamp
+      };
+
+      const outbound = { sessionId: event.sessionId, message: frame, data: event.frame };
+      for (const socketId of [session.socketId, ...session.viewers]) {
+        // No queue while the controller is detached, waiting to resume
+        const queue = this.frameQueues.get(socketId);
+        if (!queue) continue;
+
+        const result = queue.offer(event.displayId, outbound);
+        if (result === 'replaced') {
+          this.transports.connectionStats(socketId)?.recordDrop();
+          if (socketId === session.socketId) {
+            this.rdpSessionManager.recordFrameCoalesced(event.sessionId);
           }
-        } catch (error) {
-          logger.error('Authentication error:', error);
-          socket.emit('authenticated', { success: false, error: 'Authentication failed' });
         }
-      });
+      }
+    });
 
-      // RDP Session Management
-      socket.on('rdp:connect', async (data) => {
-        try {
-          const session = await this.rdpSessionManager.createSession(socket.id, data);
-          socket.emit('rdp:connected', { sessionId: session.id });
-          this.auditLogger.logRDPSession(socket.id, data);
-        } catch (error) {
-          logger.error('RDP connection error:', error);
-          socket.emit('rdp:error', { error: (error as Error).message });
-        }
+    this.rdpSessionManager.on('viewersChanged', (session) => {
+      this.emitTo(this.sessionRoom(session.id), 'session:viewers', {
+        sessionId: session.id,
+        controller: session.socketId,
+        viewers: Array.from(session.viewers)
       });
+    });
 
-      socket.on('rdp:disconnect', async (data) => {
-        try {
-          await this.rdpSessionManager.disconnectSession(socket.id);
-          socket.emit('rdp:disconnected');
-          this.auditLogger.logRDPDisconnect(socket.id);
-        } catch (error) {
-          logger.error('RDP disconnect error:', error);
-        }
-      });
+    this.rdpSessionManager.on('bitrateAdapted', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session) return;
 
-      // Input forwarding
-      socket.on('input:mouse', (data) => {
-        this.rdpSessionManager.forwardMouseInput(socket.id, data);
+      this.emitTo(this.sessionRoom(session.id), 'quality:adapted', {
+        bitrate: event.bitrate,
+        quality: event.quality,
+        fps: event.fps,
+        estimatedBandwidth: event.estimatedBandwidth
       });
+    });
 
-      socket.on('input:keyboard', (data) => {
-        this.rdpSessionManager.forwardKeyboardInput(socket.id, data);
+    this.rdpSessionManager.on('thumbnail', (event) => {
+      this.emitTo(`thumbnails:${event.sessionId}`, 'thumbnail', {
+        sessionId: event.sessionId,
+        displayId: event.displayId,
+        data: event.data.toString('base64'),
+        mimeType: 'image/jpeg',
+        timestamp: event.timestamp
       });
+    });
+
+    // Host audio goes to everyone watching the session; Opus packets are small
+    // enough that they need no queue of their own
+    this.rdpSessionManager.on('audioStarted', (event) => {
+      this.emitTo(this.sessionRoom(event.sessionId), 'audio:config', this.toAudioConfigMessage(event.channels));
+    });
 
-      socket.on('input:touch', (data) => {
-        this.rdpSessionManager.forwardTouchInput(socket.id, data);
+    this.rdpSessionManager.on('audioPacket', (event) => {
+      this.emitTo(this.sessionRoom(event.sessionId), 'audio:packet', {
+        sequence: event.sequence,
+        data: event.data.toString('base64'),
+        timestamp: event.timestamp
       });
+    });
+
+    this.rdpSessionManager.on('displayChanged', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session) return;
 
-      // Clipboard
-      socket.on('clipboard:set', (data) => {
-        this.rdpSessionManager.setClipboard(socket.id, data);
+      this.emitTo(this.sessionRoom(session.id), 'display:changed', {
+        displays: event.displays,
+        displaySubscription: event.displaySubscription,
+        reason: event.reason
       });
+    });
+
+    this.rdpSessionManager.on('keyboardLayoutChanged', (event) => {
+      this.emitTo(this.sessionRoom(event.sessionId), 'keyboard:layout', { layout: event.layout });
+    });
+
+    this.rdpSessionManager.on('dragCompleted', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session) return;
+
+      this.emitTo(session.socketId, 'input:drag:done', { cancelled: event.cancelled });
+    });
 
-      socket.on('clipboard:get', () => {
-        this.rdpSessionManager.getClipboard(socket.id);
+    this.rdpSessionManager.on('sessionIdle', (event) => {
+      if (event.action !== 'notify') {
+        this.auditLogger.logSecurityEvent(event.socketId, {
+          type: 'idle_timeout',
+          action: event.action,
+          idleFor: event.idleFor,
+          severity: 'low'
+        });
+      }
+      this.emitTo(this.sessionRoom(event.sessionId), 'session:idle', { idleFor: event.idleFor, action: event.action });
+    });
+
+    this.rdpSessionManager.on('sessionActive', (event) => {
+      this.emitTo(this.sessionRoom(event.sessionId), 'session:active', {});
+    });
+
+    this.rdpSessionManager.on('hotkeyBlocked', (event) => {
+      this.auditLogger.logSecurityEvent(event.socketId, {
+        type: 'hotkey_blocked',
+        hotkey: event.hotkey,
+        severity: 'low'
       });
+      this.emitTo(event.socketId, 'input:blocked', { hotkey: event.hotkey });
+    });
 
-      // File transfer
-      socket.on('file:upload', async (data) => {
-        try {
-          const result = await this.fileTransferManager.handleUpload(socket.id, data);
-          socket.emit('file:uploaded', result);
-        } catch (error) {
-          logger.error('File upload error:', error);
-          socket.emit('file:error', { error: (error as Error).message });
+    this.rdpSessionManager.on('macroReplayFinished', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session) return;
+
+      this.emitTo(session.socketId, 'macro:replay:done', { cancelled: event.cancelled });
+    });
+
+    this.rdpSessionManager.on('permissionsChanged', (event) => {
+      this.emitTo(this.sessionRoom(event.sessionId), 'permissions:changed', { permissions: event.permissions });
+    });
+
+    // Tell the client why its session ended before anything is torn down
+    this.rdpSessionManager.on('sessionDisconnected', (session) => {
+      this.emitTo(this.sessionRoom(session.id), 'rdp:disconnected', session.disconnectReason);
+      this.fileTransferManager.forgetFileOffers(session.id);
+    });
+
+    this.rdpSessionManager.on('sessionSummary', (summary) => {
+      this.auditLogger.logSessionSummary(summary);
+      this.hookDispatcher.dispatchSessionSummary(summary);
+    });
+
+    this.rdpSessionManager.on('clipboardReceived', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session || !session.permissions.clipboard) return;
+
+      this.emitTo(session.socketId, 'clipboard:data', { text: event.data, timestamp: event.timestamp });
+    });
+
+    this.rdpSessionManager.on('clipboardConflict', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session) return;
+
+      this.emitTo(session.socketId, 'clipboard:conflict', {
+        policy: event.policy,
+        winner: event.winner,
+        overwritten: {
+          origin: event.overwritten.origin,
+          text: event.overwritten.content,
+          timestamp: event.overwritten.timestamp
         }
       });
+    });
 
-      socket.on('file:download', async (data) => {
-        try {
-          const result = await this.fileTransferManager.handleDownload(socket.id, data);
-          socket.emit('file:downloaded', result);
-        } catch (error) {
-          logger.error('File download error:', error);
-          socket.emit('file:error', { error: (error as Error).message });
+    this.rdpSessionManager.on('backpressure', (event) => {
+      const session = this.rdpSessionManager.getSession(event.sessionId);
+      if (!session) return;
+
+      this.emitTo(session.socketId, 'metrics:backpressure', event);
+    });
+
+    // Completed transfers count towards the session summary
+    for (const event of ['fileUploaded', 'fileDownloaded']) {
+      this.fileTransferManager.on(event, ({ transfer, sessionId }) => {
+        if (transfer.status === 'completed') {
+          this.rdpSessionManager.recordFileTransferred(sessionId);
         }
       });
+    }
 
-      // Device redirection
-      socket.on('device:connect', async (data) => {
-        try {
-          const result = await this.deviceRedirectionManager.connectDevice(socket.id, data);
-          socket.emit('device:connected', result);
-        } catch (error) {
-          logger.error('Device connection error:', error);
-          socket.emit('device:error', { error: (error as Error).message });
+    // Offer watch folder files to every session with file transfer enabled
+    this.fileTransferManager.on('fileOffered', (offer) => {
+      for (const session of this.rdpSessionManager.getAllSessions()) {
+        if (session.status === 'connected' && session.permissions.fileTransfer) {
+          this.emitTo(session.socketId, 'file:offer', this.toFileOfferMessage(offer));
         }
-      });
+      }
+    });
+
+    // Only the session that decided hears about it; other sessions keep the offer
+    this.fileTransferManager.on('fileOfferResolved', (event) => {
+      this.emitTo(this.sessionRoom(event.sessionId), 'file:offerResolved', event);
+    });
+  }
+
+  // Emits to a room on every transport
+  private emitTo(room: string, event: string, payload: any): void {
+    this.transports.to(room, event, payload);
+  }
+
+  // The controller and viewers of a session
+  private sessionRoom(sessionId: string): string {
+    return `session:${sessionId}`;
+  }
+
+  private broadcast(event: string, payload: any): void {
+    this.transports.broadcast(event, payload);
+  }
+
+  // Browsers need a secure context for many APIs, and plain ws:// would leak
+  // session tokens, so the same listener serves https/wss when enabled
+  private buildTlsOptions(): HttpsServerOptions {
+    const ssl = config.get('server').ssl;
+    if (!ssl.cert || !ssl.key) {
+      throw new Error('SSL is enabled but SSL_CERT or SSL_KEY is not set');
+    }
+
+    const options: HttpsServerOptions = {
+      cert: readFileSync(ssl.cert),
+      key: readFileSync(ssl.key),
+      minVersion: ssl.minVersion
+    };
+
+    if (ssl.requireClientCert) {
+      if (!ssl.ca) {
+        throw new Error('SSL_REQUIRE_CLIENT_CERT needs SSL_CA to verify client certificates');
+      }
+      options.ca = readFileSync(ssl.ca);
+      options.requestCert = true;
+      options.rejectUnauthorized = true;
+    }
+
+    const sniContexts = new Map<string, SecureContext>();
+    for (const [serverName, pair] of Object.entries(ssl.sniCerts as Record<string, { cert: string; key: string }>)) {
+      sniContexts.set(serverName.toLowerCase(), createSecureContext({
+        cert: readFileSync(pair.cert),
+        key: readFileSync(pair.key),
+        ca: options.ca,
+        minVersion: ssl.minVersion
+      }));
+    }
+    if (sniContexts.size > 0) {
+      // Unknown server names fall back to the default certificate
+      options.SNICallback = (serverName, callback) => callback(null, sniContexts.get(serverName.toLowerCase()));
+    }
+
+    logger.info(`TLS enabled${ssl.requireClientCert ? ' with client certificate verification' : ''}${sniContexts.size > 0 ? `, ${sniContexts.size} SNI certificate(s)` : ''}`);
+    return options;
+  }
+
+  private toSessionMessage(session: any): any {
+    return {
+      sessionId: session.id,
+      resumeToken: session.resumeToken,
+      displays: session.displays,
+      displaySubscription: session.displaySubscription,
+      networkLink: session.networkLink,
+      maxBitrate: session.maxBitrate,
+      quality: session.config.quality,
+      viewOnly: session.viewOnly,
+      permissions: session.permissions,
+      scaleMode: session.scaleMode,
+      keyboardMode: session.keyboardMode,
+      hostKeyboardLayout: session.hostKeyboardLayout,
+      codec: session.codec.name,
+      capabilities: this.capabilityReporter.getCapabilities(session.config, session.displays.length)
+    };
+  }
+
+  // Rotated STUN/TURN servers go to each authenticated client with TURN
+  // credentials minted for that client
+  private pushIceServerUpdates(socket: GatewaySocket): void {
+    if (socket.data.iceServersListener) return;
+
+    const listener = (update: IceServersUpdate) => {
+      socket.emit('webrtc:iceServers', { ...this.iceServerManager.getIceServers(socket.data.user?.username), restartIce: update.restartIce });
+    };
+    socket.data.iceServersListener = listener;
+    this.iceServerManager.on('iceServersUpdated', listener);
+    socket.on('disconnect', () => this.iceServerManager.off('iceServersUpdated', listener));
+  }
+
+  private assertFileTransferPermitted(socketId: string): void {
+    if (!this.rdpSessionManager.hasPermission(socketId, 'fileTransfer')) {
+      throw new Error('File transfer is not permitted in this session');
+    }
+  }
+
+  private toAudioConfigMessage(channels: number): any {
+    return { codec: 'opus', sampleRate: OPUS_SAMPLE_RATE, channels };
+  }
 
-      socket.on('device:disconnect', async (data) => {
-        try {
-          await this.deviceRedirectionManager.disconnectDevice(socket.id, data);
-          socket.emit('device:disconnected', { deviceId: data.deviceId });
-        } catch (error) {
-          logger.error('Device disconnect error:', error);
+  private toFileOfferMessage(offer: any): any {
+    return {
+      offerId: offer.id,
+      fileName: offer.fileName,
+      fileSize: offer.fileSize,
+      mimeType: offer.mimeType,
+      offeredAt: offer.offeredAt
+    };
+  }
+
+  private setupSocketHandlers(): void {
+    this.transports.on('connection', (socket: GatewaySocket, transport: Transport) => {
+      this.handleClientConnection(socket, transport);
+    });
+
+    logger.info('Socket handlers configured');
+  }
+
+  private handleClientConnection(socket: GatewaySocket, transport: Transport): void {
+    const maxConnections = config.get('server').maxConnections;
+    if (maxConnections > 0 && this.frameQueues.size >= maxConnections) {
+      logger.warn(`Rejecting ${socket.id}: ${maxConnections} connections already open`);
+      socket.emit('error', { error: 'Too many connections' });
+      socket.disconnect(true);
+      return;
+    }
+
+    logger.info(`Client connected: ${socket.id}`);
+
+    const websocketConfig = config.get('websocket');
+    const frameBatcher = new FrameBatcher<OutboundFrame>(
+      {
+        maxFrames: websocketConfig.frameBatchSize,
+        maxBytes: websocketConfig.frameBatchBytes,
+        latency: websocketConfig.frameBatchLatency
+      },
+      (frame) => frame.data.length,
+      (frames) => {
+        if (frames.length === 1) {
+          transport.emitFrame(socket, frames[0].message, frames[0].data);
+        } else {
+          transport.emitFrameBatch(socket, frames);
         }
-      });
+        // Frames still buffered by the transport count as the send queue;
+        // the bitrate follows the controller's connection, not the viewers'
+        const sessionId = frames[0].sessionId;
+        if (this.rdpSessionManager.getSession(sessionId)?.socketId === socket.id) {
+          this.rdpSessionManager.recordFrameSent(sessionId, socket.conn.writeBuffer.length);
+        }
+      }
+    );
+    const frameQueue = new FrameSendQueue<OutboundFrame>(socket, websocketConfig.maxPendingFrames, (frame) => {
+      frameBatcher.add(frame);
+    });
+    this.frameQueues.set(socket.id, frameQueue);
+    const connectionStats = transport.connectionStats(socket.id);
+    connectionStats?.addQueue(() => frameQueue.depth + frameBatcher.depth);
 
-      // Quality settings
-      socket.on('quality:change', (data) => {
-        this.rdpSessionManager.changeQuality(socket.id, data.quality);
-      });
+    socket.conn.on('drain', () => {
+      this.rdpSessionManager.recordSendDrained(socket.id);
+      frameQueue.flush();
+    });
 
-      // Fullscreen
-      socket.on('display:fullscreen', (data) => {
-        this.rdpSessionManager.setFullscreen(socket.id, data.enabled);
-      });
+    // Authentication
+    socket.on('authenticate', async (data) => {
+      try {
+        const authResult = await this.authManager.authenticateSocket(socket, data);
+        if (authResult.success) {
+          socket.data.user = authResult.user;
+          socket.join('authenticated');
+          socket.emit('authenticated', {
+            success: true,
+            capabilities: this.capabilityReporter.getCapabilities()
+          });
+          socket.emit('webrtc:iceServers', { ...this.iceServerManager.getIceServers(authResult.user?.username), restartIce: false });
+          this.pushIceServerUpdates(socket);
+          this.auditLogger.logConnection(socket.id, data);
+        } else {
+          socket.emit('authenticated', { success: false, error: authResult.error });
+        }
+      } catch (error) {
+        logger.error('Authentication error:', error);
+        socket.emit('authenticated', { success: false, error: 'Authentication failed' });
+      }
+    });
 
-      // Multi-monitor
-      socket.on('display:monitor', (data) => {
-        this.rdpSessionManager.setMonitor(socket.id, data.monitorIndex);
-      });
+    // Protocol version and capability negotiation, sent before rdp:connect
+    socket.on('hello', (data) => {
+      try {
+        const capabilities = this.capabilityReporter.negotiate(data);
+        socket.data.capabilities = capabilities;
+        socket.emit('hello', {
+          protocolVersion: GATEWAY_PROTOCOL_VERSION,
+          minProtocolVersion: MIN_CLIENT_PROTOCOL_VERSION,
+          capabilities
+        });
+      } catch (error) {
+        logger.warn(`Capability negotiation failed for ${socket.id}:`, error);
+        socket.emit('hello:error', {
+          error: (error as Error).message,
+          protocolVersion: GATEWAY_PROTOCOL_VERSION,
+          minProtocolVersion: MIN_CLIENT_PROTOCOL_VERSION
+        });
+      }
+    });
+
+    // RDP Session Management
+    socket.on('rdp:connect', async (data) => {
+      try {
+        const session = await this.rdpSessionManager.createSession(
+          socket.id,
+          data,
+          socket.data.capabilities,
+          socket.data.user?.username
+        );
+        socket.join(this.sessionRoom(session.id));
+        socket.emit('rdp:connected', this.toSessionMessage(session));
+        this.auditLogger.logRDPSession(socket.id, data);
+
+        if (session.permissions.fileTransfer) {
+          for (const offer of this.fileTransferManager.getFileOffers(session.id)) {
+            socket.emit('file:offer', this.toFileOfferMessage(offer));
+          }
+        }
+      } catch (error) {
+        logger.error('RDP connection error:', error);
+        socket.emit('rdp:error', { error: (error as Error).message });
+      }
+    });
+
+    // Reattach to a session this client lost, within the resume grace period
+    socket.on('rdp:resume', (data) => {
+      try {
+        const session = this.rdpSessionManager.resumeSession(socket.id, data?.resumeToken, socket.data.user?.username);
+        socket.join(this.sessionRoom(session.id));
+        socket.emit('rdp:resumed', {
+          ...this.toSessionMessage(session),
+          effectiveQuality: session.effectiveQuality,
+          targetBitrate: session.targetBitrate,
+          frameCount: session.frameCount,
+          reconnectCount: session.reconnectCount
+        });
+        // The new socket missed the config sent when audio started
+        if (session.audioEncoder) {
+          socket.emit('audio:config', this.toAudioConfigMessage(session.audioEncoder.format.channels));
+        }
+      } catch (error) {
+        logger.warn(`Session resume failed for ${socket.id}:`, error);
+        socket.emit('rdp:resumeFailed', { error: (error as Error).message });
+      }
+    });
+
+    // Watch a running session; same access rule as thumbnails
+    socket.on('session:join', (data) => {
+      try {
+        const session = this.rdpSessionManager.getSession(data?.sessionId);
+        const user = socket.data.user;
+        if (!session || !user || (user.role !== 'admin' && session.owner !== user.username)) {
+          throw new Error('Session not found');
+        }
+
+        this.rdpSessionManager.addViewer(session.id, socket.id);
+        socket.join(this.sessionRoom(session.id));
+        // Viewers never get the resume token; only the controller may resume
+        const { resumeToken, ...message } = this.toSessionMessage(session);
+        socket.emit('session:joined', {
+          ...message,
+          viewOnly: true,
+          controller: session.socketId,
+          viewers: Array.from(session.viewers)
+        });
+        // Audio already playing; the viewer missed the room-wide config
+        if (session.audioEncoder) {
+          socket.emit('audio:config', this.toAudioConfigMessage(session.audioEncoder.format.channels));
+        }
+      } catch (error) {
+        logger.warn(`Session join failed for ${socket.id}:`, error);
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('session:leave', () => {
+      const session = this.rdpSessionManager.removeViewer(socket.id);
+      if (session) {
+        socket.leave(this.sessionRoom(session.id));
+      }
+    });
+
+    socket.on('control:transfer', (data) => {
+      try {
+        const session = this.rdpSessionManager.transferControl(socket.id, data?.socketId);
+        // Resuming is now up to the new controller
+        this.emitTo(session.socketId, 'control:granted', { sessionId: session.id, resumeToken: session.resumeToken });
+        socket.emit('control:revoked', { sessionId: session.id, controller: session.socketId });
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('rdp:disconnect', async (data) => {
+      try {
+        await this.rdpSessionManager.disconnectSession(socket.id, 'client_request');
+        this.auditLogger.logRDPDisconnect(socket.id);
+      } catch (error) {
+        logger.error('RDP disconnect error:', error);
+      }
+    });
+
+    // Input forwarding
+    socket.on('input:mouse', (data) => {
+      this.rdpSessionManager.forwardMouseInput(socket.id, data);
+    });
+
+    socket.on('input:keyboard', (data) => {
+      this.rdpSessionManager.forwardKeyboardInput(socket.id, data);
+    });
+
+    socket.on('input:text', (data) => {
+      this.rdpSessionManager.forwardTextInput(socket.id, data);
+    });
+
+    // Press, paced move and release, for drags that instant input would not start
+    socket.on('input:drag', (data) => {
+      try {
+        const duration = this.rdpSessionManager.startDrag(socket.id, data);
+        socket.emit('input:drag:started', { duration });
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    // Input macros: the client keeps the recording and sends it back to replay
+    socket.on('macro:record:start', () => {
+      try {
+        this.rdpSessionManager.startMacroRecording(socket.id);
+        socket.emit('macro:recording', {});
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('macro:record:stop', () => {
+      try {
+        const macro = this.rdpSessionManager.stopMacroRecording(socket.id);
+        socket.emit('macro:recorded', { macro });
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('macro:replay', (data) => {
+      try {
+        const duration = this.rdpSessionManager.replayMacro(socket.id, data?.macro, data?.speed);
+        this.auditLogger.logSecurityEvent(socket.id, {
+          type: 'macro_replay',
+          events: data.macro.events.length,
+          duration,
+          severity: 'medium'
+        });
+        socket.emit('macro:replay:started', { duration });
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('macro:replay:stop', () => {
+      this.rdpSessionManager.stopMacroReplay(socket.id);
+    });
+
+    // Combinations such as Ctrl+Alt+Del that never reach the browser
+    socket.on('input:combo', (data) => {
+      try {
+        this.rdpSessionManager.sendKeyCombo(socket.id, data?.combo);
+        this.auditLogger.logSecurityEvent(socket.id, {
+          type: 'key_combo',
+          combo: data.combo,
+          severity: data.combo === 'ctrl-alt-del' ? 'high' : 'low'
+        });
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('input:touch', (data) => {
+      this.rdpSessionManager.forwardTouchInput(socket.id, data);
+    });
+
+    socket.on('input:pen', (data) => {
+      this.rdpSessionManager.forwardPenInput(socket.id, data);
+    });
+
+    socket.on('keyboard:mode', (data) => {
+      try {
+        const mode = this.rdpSessionManager.setKeyboardMode(socket.id, data?.mode);
+        socket.emit('keyboard:mode:changed', { mode });
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    // The user is present at the client without sending input
+    socket.on('client:activity', () => {
+      this.rdpSessionManager.noteClientActivity(socket.id);
+    });
+
+    // Relative while the client holds a pointer lock, absolute otherwise
+    socket.on('pointer:mode', (data) => {
+      try {
+        const mode = this.rdpSessionManager.setPointerMode(socket.id, data?.mode);
+        socket.emit('pointer:mode:changed', { mode });
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    // Clipboard
+    socket.on('clipboard:set', (data) => {
+      this.rdpSessionManager.setClipboard(socket.id, data);
+    });
+
+    socket.on('clipboard:get', () => {
+      this.rdpSessionManager.getClipboard(socket.id);
+    });
+
+    // File transfer
+    socket.on('file:upload', async (data) => {
+      try {
+        this.assertFileTransferPermitted(socket.id);
+        const result = await this.fileTransferManager.handleUpload(socket.id, data);
+        socket.emit('file:uploaded', result);
+      } catch (error) {
+        logger.error('File upload error:', error);
+        socket.emit('file:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('file:download', async (data) => {
+      try {
+        this.assertFileTransferPermitted(socket.id);
+        const result = await this.fileTransferManager.handleDownload(socket.id, data);
+        socket.emit('file:downloaded', result);
+      } catch (error) {
+        logger.error('File download error:', error);
+        socket.emit('file:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('file:offer:accept', async (data) => {
+      try {
+        this.assertFileTransferPermitted(socket.id);
+        const session = this.rdpSessionManager.getSessionBySocketId(socket.id)!;
+        const result = await this.fileTransferManager.acceptFileOffer(session.id, data?.offerId, socket.id);
+        socket.emit('file:downloaded', result);
+      } catch (error) {
+        logger.error('File offer accept error:', error);
+        socket.emit('file:error', { error: (error as Error).message });
+      }
+    });
 
-      // Disconnect
-      socket.on('disconnect', async () => {
+    socket.on('file:offer:decline', (data) => {
+      const session = this.rdpSessionManager.getSessionBySocketId(socket.id);
+      if (session) {
+        this.fileTransferManager.declineFileOffer(session.id, data?.offerId);
+      }
+    });
+
+    // Device redirection
+    socket.on('device:connect', async (data) => {
+      try {
+        const result = await this.deviceRedirectionManager.connectDevice(socket.id, data);
+        socket.emit('device:connected', result);
+      } catch (error) {
+        logger.error('Device connection error:', error);
+        socket.emit('device:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('device:disconnect', async (data) => {
+      try {
+        await this.deviceRedirectionManager.disconnectDevice(socket.id, data);
+        socket.emit('device:disconnected', { deviceId: data.deviceId });
+      } catch (error) {
+        logger.error('Device disconnect error:', error);
+      }
+    });
+
+    // Quality settings
+    socket.on('quality:change', (data) => {
+      try {
+        this.rdpSessionManager.changeQuality(socket.id, data?.quality);
+      } catch (error) {
+        socket.emit('session:error', { error: (error as Error).message });
+      }
+    });
+
+    // Fullscreen
+    socket.on('display:fullscreen', (data) => {
+      this.rdpSessionManager.setFullscreen(socket.id, data.enabled);
+    });
+
+    // Multi-monitor
+    socket.on('display:monitor', (data) => {
+      this.rdpSessionManager.setMonitor(socket.id, data.monitorIndex);
+    });
+
+    socket.on('display:resize', (data) => {
+      try {
+        this.rdpSessionManager.resizeDisplay(socket.id, data.width, data.height, data.displayId);
+      } catch (error) {
+        logger.error('Display resize error:', error);
+        socket.emit('display:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('frame:refresh', (data) => {
+      connectionStats?.recordRetransmit();
+      this.rdpSessionManager.requestRefresh(socket.id, data?.displayIds);
+    });
+
+    socket.on('frame:ack', (data) => {
+      this.rdpSessionManager.acknowledgeFrame(socket.id, data.frameId);
+    });
+
+    socket.on('display:subscribe', (data) => {
+      try {
+        const result = this.rdpSessionManager.subscribeDisplays(socket.id, data.mode, data.displayIds);
+        socket.emit('display:subscribed', result);
+      } catch (error) {
+        logger.error('Display subscription error:', error);
+        socket.emit('display:error', { error: (error as Error).message });
+      }
+    });
+
+    // Preview stream for dashboards; does not require an RDP session of its own
+    socket.on('thumbnails:subscribe', (data) => {
+      try {
+        const session = this.rdpSessionManager.getSession(data.sessionId);
+        const user = socket.data.user;
+        if (!session || !user || (user.role !== 'admin' && session.socketId !== socket.id)) {
+          throw new Error('Session not found');
+        }
+
+        this.rdpSessionManager.subscribeThumbnails(session.id, socket.id);
+        socket.join(`thumbnails:${session.id}`);
+        socket.emit('thumbnails:subscribed', { sessionId: session.id, displays: session.displays });
+      } catch (error) {
+        logger.error('Thumbnail subscription error:', error);
+        socket.emit('thumbnails:error', { error: (error as Error).message });
+      }
+    });
+
+    socket.on('thumbnails:unsubscribe', (data) => {
+      socket.leave(`thumbnails:${data.sessionId}`);
+      this.rdpSessionManager.unsubscribeThumbnails(data.sessionId, socket.id);
+    });
+
+    // Disconnect
+    socket.on('disconnect', async (reason) => {
+      if (reason === 'ping timeout') {
+        logger.warn(`Client ${socket.id} stopped answering heartbeats`);
+      } else {
         logger.info(`Client disconnected: ${socket.id}`);
-        await this.rdpSessionManager.disconnectSession(socket.id);
+      }
+      frameQueue.clear();
+      frameBatcher.clear();
+      this.frameQueues.delete(socket.id);
+      this.rdpSessionManager.unsubscribeAllThumbnails(socket.id);
+
+      // A viewer leaving does not affect the session
+      if (this.rdpSessionManager.removeViewer(socket.id)) {
         this.auditLogger.logDisconnect(socket.id);
-      });
-    });
+        return;
+      }
 
-    logger.info('Socket handlers configured');
+      // Dropped connections keep their session for a while so the client can
+      // resume it; an explicit client disconnect ends it right away
+      const detail = reason.replace(/ /g, '_');
+      if (reason === 'client namespace disconnect' || !this.rdpSessionManager.detachSession(socket.id, detail)) {
+        await this.rdpSessionManager.disconnectSession(socket.id, 'connection_lost', detail);
+      }
+      this.auditLogger.logDisconnect(socket.id);
+    });
   }
 
   public start(): void {
     const port = config.get('server').port;
     const host = config.get('server').host;
 
-    this.server.listen(port, host, () => {
-      logger.info(`RDP Gateway Server running on ${host}:${port}`);
+    // Bad settings fall back to their defaults, so they are worth a warning
+    for (const error of config.validate()) {
+      logger.warn(`Configuration: ${error}`);
+    }
+
+    this.transports.start(this.server);
+
+    const socketConfig = config.get('server').socket;
+    const listenHost = resolveBindAddress(host, socketConfig.bindInterface);
+    this.server.listen({ port, host: listenHost, ipv6Only: socketConfig.ipv6Only }, () => {
+      logger.info(`RDP Gateway Server running on ${listenHost}:${port}`);
       logger.info(`Environment: ${process.env.NODE_ENV || 'development'}`);
-      logger.info(`WebSocket endpoint: ws://${host}:${port}/socket.io/`);
+      const scheme = config.get('server').ssl.enabled ? 'wss' : 'ws';
+      logger.info(`WebSocket endpoint: ${scheme}://${host}:${port}/socket.io/`);
+      if (config.get('websocket').rawPath) {
+        logger.info(`Plain WebSocket endpoint: ${scheme}://${host}:${port}${config.get('websocket').rawPath}`);
+      }
+
+      if (config.get('server').discovery.enabled) {
+        this.discoveryAdvertiser = new DiscoveryAdvertiser();
+        this.discoveryAdvertiser.start(port, this.capabilityReporter.getCapabilities());
+      }
     });
 
+    const websocketConfig = config.get('websocket');
+    if (websocketConfig.relayUrl && this.rawSocketServer) {
+      const rawSocketServer = this.rawSocketServer;
+      this.relayClient = new RelayClient({
+        url: websocketConfig.relayUrl,
+        token: websocketConfig.relayToken,
+        name: websocketConfig.relayName,
+        pingInterval: websocketConfig.pingInterval
+      });
+      this.relayClient.on('connection', (ws, remoteAddress: string) => rawSocketServer.adopt(ws, remoteAddress));
+      this.relayClient.start();
+      logger.info(`Relay mode: registering with ${websocketConfig.relayUrl}`);
+    }
+
+    const controlSocket = config.get('server').controlSocket;
+    if (controlSocket.enabled) {
+      this.controlChannel = new ControlChannel(this.app, this.authManager, controlSocket.path);
+      this.controlChannel.start().catch((error) => {
+        logger.error('Failed to start control channel:', error);
+        this.controlChannel = null;
+      });
+    }
+
     // Graceful shutdown
     process.on('SIGTERM', () => {
       logger.info('SIGTERM received, shutting down gracefully');
@@ -289,17 +1105,54 @@ class RDPGateway {
       logger.info('SIGINT received, shutting down gracefully');
       this.shutdown();
     });
+
+    // Configuration reload (e.g. after TURN credential rotation)
+    process.on('SIGHUP', () => {
+      logger.info('SIGHUP received, reloading ICE server configuration');
+      try {
+        this.iceServerManager.reloadFromConfig();
+      } catch (error) {
+        logger.error('Failed to reload ICE servers:', error);
+      }
+    });
   }
 
   private async shutdown(): Promise<void> {
     try {
       logger.info('Shutting down RDP Gateway...');
-      
+      const serverConfig = config.get('server');
+
+      // Tell every client first, so they can show why the connection goes
+      // away; queued video is dropped so the notice is not stuck behind it
+      for (const queue of this.frameQueues.values()) {
+        queue.clear();
+      }
+      this.broadcast('server:shutdown', {
+        reason: 'server_shutdown',
+        message: 'The gateway is shutting down',
+        reconnectHint: {
+          reconnect: serverConfig.shutdownRetryAfter > 0,
+          retryAfter: serverConfig.shutdownRetryAfter
+        }
+      });
+
       // Close all RDP sessions
       await this.rdpSessionManager.shutdown();
+
+      if (!(await this.transports.drain(serverConfig.shutdownTimeout))) {
+        logger.warn(`Closing connections with messages still queued after ${serverConfig.shutdownTimeout}ms`);
+      }
+      this.fileTransferManager.stopWatchFolder();
+      await this.discoveryAdvertiser?.stop();
+      this.iceServerManager.shutdown();
+
+      // Close the local control channel
+      this.controlChannel?.stop();
       
       // Close WebSocket connections
-      this.io.close();
+      this.transports.close();
+      this.signalingServer?.close();
+      this.relayClient?.close();
       
       // Close HTTP server
       this.server.close(() => {
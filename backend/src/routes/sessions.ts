@@ -1,13 +1,49 @@
 import express from 'express';
 import { RDPSessionManager } from '../core/RDPSessionManager';
+import { RuntimeMetrics } from '../core/RuntimeMetrics';
+import { TransportRegistry } from '../ws/Transport';
 import { AuthManager } from '../services/AuthManager';
 import { Logger } from '../utils/Logger';
 
 const router = express.Router();
 const logger = new Logger('SessionRoutes');
-const rdpSessionManager = new RDPSessionManager();
+const rdpSessionManager = RDPSessionManager.getInstance();
 const authManager = new AuthManager();
 
+// Get end-of-session summaries (admin only)
+router.get('/summaries', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+    
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+    
+    if (!user || user.role !== 'admin') {
+      return res.status(403).json({
+        success: false,
+        error: 'Admin access required'
+      });
+    }
+
+    return res.json({
+      success: true,
+      summaries: rdpSessionManager.getSessionSummaries()
+    });
+  } catch (error) {
+    logger.error('Get session summaries error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
 // Get all active sessions
 router.get('/', async (req, res) => {
   try {
@@ -55,6 +91,7 @@ router.get('/', async (req, res) => {
           width: session.config.width,
           height: session.config.height
         },
+        networkLinkType: session.networkLink?.type || 'unknown',
         error: session.error
       }))
     });
@@ -118,6 +155,8 @@ router.get('/:sessionId', async (req, res) => {
         bytesReceived: session.bytesReceived,
         bytesSent: session.bytesSent,
         config: session.config,
+        permissions: session.permissions,
+        summary: session.summary,
         error: session.error
       }
     });
@@ -304,7 +343,11 @@ router.delete('/:sessionId', async (req, res) => {
       });
     }
 
-    await rdpSessionManager.disconnectSession(session.socketId);
+    await rdpSessionManager.disconnectSession(
+      session.socketId,
+      user.role === 'admin' ? 'admin_kick' : 'client_request',
+      user.username
+    );
     
     logger.info(`RDP session disconnected: ${sessionId}`);
     
@@ -376,6 +419,17 @@ router.get('/:sessionId/stats', async (req, res) => {
         bytesReceived: session.bytesReceived,
         bytesSent: session.bytesSent,
         bandwidth,
+        maxBitrate: session.maxBitrate,
+        targetBitrate: session.targetBitrate,
+        effectiveQuality: session.effectiveQuality,
+        targetFps: session.targetFps,
+        estimatedBandwidth: session.pipelineMetrics.estimatedBandwidth(),
+        bitrateCeiling: session.bitrateCeiling,
+        networkLink: session.networkLink,
+        pipeline: session.pipelineMetrics.snapshot(),
+        input: session.inputLimiter.snapshot(),
+        runtime: RuntimeMetrics.getInstance().snapshot(),
+        transport: TransportRegistry.getInstance().getStats(session.socketId),
         lastActivity: session.lastActivity,
         error: session.error
       }
@@ -389,6 +443,54 @@ router.get('/:sessionId/stats', async (req, res) => {
   }
 });
 
+// Get session metrics history (admin only)
+router.get('/:sessionId/metrics/history', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+    
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+    
+    if (!user || user.role !== 'admin') {
+      return res.status(403).json({
+        success: false,
+        error: 'Admin access required'
+      });
+    }
+
+    const { sessionId } = req.params;
+    const since = req.query.since ? parseInt(req.query.since as string) : undefined;
+    const until = req.query.until ? parseInt(req.query.until as string) : undefined;
+
+    const samples = rdpSessionManager.getMetricsHistory(sessionId, since, until);
+    
+    if (!samples) {
+      return res.status(404).json({
+        success: false,
+        error: 'Session not found'
+      });
+    }
+
+    return res.json({
+      success: true,
+      sessionId,
+      samples
+    });
+  } catch (error) {
+    logger.error('Get metrics history error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
 // Change session quality
 router.patch('/:sessionId/quality', async (req, res) => {
   try {
@@ -413,10 +515,10 @@ router.patch('/:sessionId/quality', async (req, res) => {
     const { sessionId } = req.params;
     const { quality } = req.body;
     
-    if (!quality || !['low', 'medium', 'high', 'ultra'].includes(quality)) {
+    if (!quality || !['low', 'medium', 'high', 'ultra', 'lossless'].includes(quality)) {
       return res.status(400).json({
         success: false,
-        error: 'Valid quality level is required (low, medium, high, ultra)'
+        error: 'Valid quality level is required (low, medium, high, ultra, lossless)'
       });
     }
 
@@ -455,6 +557,72 @@ router.patch('/:sessionId/quality', async (req, res) => {
   }
 });
 
+// Change what the controller of a session may do (admin only)
+router.patch('/:sessionId/permissions', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+    
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+    
+    if (!user || user.role !== 'admin') {
+      return res.status(403).json({
+        success: false,
+        error: 'Admin access required'
+      });
+    }
+
+    const { sessionId } = req.params;
+    const changes = req.body || {};
+    const invalid = Object.keys(changes).filter(key =>
+      !['mouse', 'keyboard', 'clipboard', 'fileTransfer'].includes(key) || typeof changes[key] !== 'boolean');
+
+    if (invalid.length > 0) {
+      return res.status(400).json({
+        success: false,
+        error: 'Permissions must be booleans (mouse, keyboard, clipboard, fileTransfer)'
+      });
+    }
+
+    const session = rdpSessionManager.getSession(sessionId);
+    
+    if (!session || session.status === 'disconnected') {
+      return res.status(404).json({
+        success: false,
+        error: 'Session not found'
+      });
+    }
+
+    if (session.viewOnly) {
+      return res.status(400).json({
+        success: false,
+        error: 'Session is view-only'
+      });
+    }
+
+    const permissions = rdpSessionManager.setPermissions(sessionId, changes);
+    
+    logger.info(`Session permissions changed: ${sessionId} by ${user.username}`);
+    
+    return res.json({
+      success: true,
+      permissions
+    });
+  } catch (error) {
+    logger.error('Change session permissions error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
 // Get all sessions statistics (admin only)
 router.get('/stats/overview', async (req, res) => {
   try {
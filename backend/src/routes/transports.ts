@@ -0,0 +1,88 @@
+import express from 'express';
+import { TransportRegistry } from '../ws/Transport';
+import { AuthManager } from '../services/AuthManager';
+import { Logger } from '../utils/Logger';
+
+const router = express.Router();
+const logger = new Logger('TransportRoutes');
+const transportRegistry = TransportRegistry.getInstance();
+const authManager = new AuthManager();
+
+// Get aggregate statistics for every transport (admin only)
+router.get('/stats', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+
+    if (!user || user.role !== 'admin') {
+      return res.status(403).json({
+        success: false,
+        error: 'Admin access required'
+      });
+    }
+
+    return res.json({
+      success: true,
+      total: transportRegistry.totals(),
+      transports: transportRegistry.stats()
+    });
+  } catch (error) {
+    logger.error('Get transport stats error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
+// Get statistics for one client connection (admin only)
+router.get('/connections/:connectionId/stats', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+
+    if (!user || user.role !== 'admin') {
+      return res.status(403).json({
+        success: false,
+        error: 'Admin access required'
+      });
+    }
+
+    const stats = transportRegistry.getStats(req.params.connectionId);
+    if (!stats) {
+      return res.status(404).json({
+        success: false,
+        error: 'Connection not found'
+      });
+    }
+
+    return res.json({
+      success: true,
+      stats
+    });
+  } catch (error) {
+    logger.error('Get connection stats error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
+export default router;
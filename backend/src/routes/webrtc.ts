@@ -0,0 +1,134 @@
+import express from 'express';
+import { IceServerManager } from '../services/IceServerManager';
+import { AuthManager } from '../services/AuthManager';
+import { Logger } from '../utils/Logger';
+
+const router = express.Router();
+const logger = new Logger('WebRTCRoutes');
+const iceServerManager = IceServerManager.getInstance();
+const authManager = new AuthManager();
+
+// Get current ICE servers
+router.get('/ice-servers', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+    
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+    
+    if (!user) {
+      return res.status(401).json({
+        success: false,
+        error: 'Invalid or expired token'
+      });
+    }
+
+    return res.json({
+      success: true,
+      ...iceServerManager.getIceServers(user.username)
+    });
+  } catch (error) {
+    logger.error('Get ICE servers error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
+// Replace ICE servers (admin only)
+router.put('/ice-servers', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+    
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+    
+    if (!user || user.role !== 'admin') {
+      return res.status(403).json({
+        success: false,
+        error: 'Admin access required'
+      });
+    }
+
+    const { iceServers, restartIce } = req.body;
+
+    try {
+      const update = iceServerManager.setIceServers(iceServers, restartIce === true);
+      logger.info(`ICE servers updated by ${user.username}`);
+
+      return res.json({
+        success: true,
+        ...update
+      });
+    } catch (error) {
+      return res.status(400).json({
+        success: false,
+        error: (error as Error).message
+      });
+    }
+  } catch (error) {
+    logger.error('Update ICE servers error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
+// Reload ICE servers from configuration (admin only)
+router.post('/ice-servers/reload', async (req, res) => {
+  try {
+    const token = req.headers.authorization?.replace('Bearer ', '');
+    
+    if (!token) {
+      return res.status(401).json({
+        success: false,
+        error: 'No token provided'
+      });
+    }
+
+    const user = authManager.getUserFromToken(token);
+    
+    if (!user || user.role !== 'admin') {
+      return res.status(403).json({
+        success: false,
+        error: 'Admin access required'
+      });
+    }
+
+    try {
+      const update = iceServerManager.reloadFromConfig(req.body?.restartIce === true);
+
+      return res.json({
+        success: true,
+        ...update
+      });
+    } catch (error) {
+      return res.status(400).json({
+        success: false,
+        error: (error as Error).message
+      });
+    }
+  } catch (error) {
+    logger.error('Reload ICE servers error:', error);
+    return res.status(500).json({
+      success: false,
+      error: 'Internal server error'
+    });
+  }
+});
+
+export default router;
@@ -383,6 +383,24 @@ export class AuditLogger extends EventEmitter {
     }
   }
 
+  public logSessionSummary(summary: any): void {
+    const event: AuditEvent = {
+      id: `rdp_summary_${Date.now()}_${Math.random().toString(36).substr(2, 9)}`,
+      timestamp: new Date(),
+      sessionId: summary.sessionId,
+      clientId: summary.socketId,
+      eventType: 'rdp_session_summary',
+      eventData: summary,
+      severity: 'low',
+      source: 'rdp'
+    };
+
+    this.logAuditEvent(event);
+
+    const logLine = JSON.stringify({ type: 'summary', ...summary }) + '\n';
+    appendFileSync(this.sessionLogFile, logLine);
+  }
+
   public getAuditEvents(filters?: any): AuditEvent[] {
     let events = Array.from(this.auditEvents.values());
     
@@ -1,5 +1,4 @@
 import { Logger } from '../utils/Logger';
-import { Socket } from 'socket.io';
 import bcrypt from 'bcryptjs';
 import jwt from 'jsonwebtoken';
 import { Config } from '../utils/Config';
@@ -60,7 +59,7 @@ export class AuthManager {
     this.logger.info('Default users initialized');
   }
 
-  public async authenticateSocket(socket: Socket, data: any): Promise<AuthResult> {
+  public async authenticateSocket(socket: { id: string }, data: any): Promise<AuthResult> {
     try {
       const { username, password, token } = data;
 
@@ -162,7 +161,7 @@ export class AuthManager {
     }
   }
 
-  private authenticateAnonymous(socket: Socket): AuthResult {
+  private authenticateAnonymous(socket: { id: string }): AuthResult {
     const anonymousUser: User = {
       id: `anon_${socket.id}`,
       username: 'anonymous',
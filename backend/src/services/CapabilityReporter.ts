@@ -0,0 +1,140 @@
+import { Logger } from '../utils/Logger';
+import { Config } from '../utils/Config';
+import { CodecRegistry } from '../core/CodecRegistry';
+import ffmpeg from 'fluent-ffmpeg';
+
+export interface HostCapabilities {
+  codecs: string[];
+  hardwareEncoders: string[];
+  audio: boolean;
+  touchInput: boolean;
+  clipboard: boolean;
+  fileTransfer: boolean;
+  deviceRedirection: boolean;
+  multiMonitor: boolean;
+  displayCount?: number;
+}
+
+// Version of the gateway's client protocol; bumped on incompatible changes
+export const GATEWAY_PROTOCOL_VERSION = 1;
+export const MIN_CLIENT_PROTOCOL_VERSION = 1;
+
+export type NegotiableFeature = 'audio' | 'clipboard' | 'fileTransfer' | 'deviceRedirection' | 'multiMonitor';
+
+const NEGOTIABLE_FEATURES: NegotiableFeature[] = ['audio', 'clipboard', 'fileTransfer', 'deviceRedirection', 'multiMonitor'];
+
+// What a client advertises in its `hello`; codecs are in order of preference
+export interface ClientHello {
+  protocolVersion: number;
+  codecs?: string[];
+  maxWidth?: number;
+  maxHeight?: number;
+  features?: Partial<Record<NegotiableFeature, boolean>>;
+}
+
+export interface NegotiatedCapabilities extends Record<NegotiableFeature, boolean> {
+  protocolVersion: number;
+  codec: string;
+  codecs: string[];
+  // 0 means the client did not limit the resolution
+  maxWidth: number;
+  maxHeight: number;
+}
+
+const HARDWARE_ENCODERS = [
+  'h264_nvenc',
+  'hevc_nvenc',
+  'h264_qsv',
+  'hevc_qsv',
+  'h264_vaapi',
+  'hevc_vaapi',
+  'h264_videotoolbox',
+  'hevc_videotoolbox',
+  'h264_amf',
+  'hevc_amf'
+];
+
+export class CapabilityReporter {
+  private logger = new Logger('CapabilityReporter');
+  private config = Config.getInstance();
+  private hardwareEncoders: string[] = [];
+
+  // Probing ffmpeg is slow, so it happens once at startup
+  public async probe(): Promise<void> {
+    try {
+      this.hardwareEncoders = await new Promise<string[]>((resolve, reject) => {
+        ffmpeg.getAvailableEncoders((error, encoders) => {
+          if (error) {
+            reject(error);
+            return;
+          }
+          resolve(HARDWARE_ENCODERS.filter(name => name in encoders));
+        });
+      });
+
+      this.logger.info(`Hardware encoders available: ${this.hardwareEncoders.join(', ') || 'none'}`);
+    } catch (error) {
+      this.hardwareEncoders = [];
+      this.logger.warn('Failed to probe ffmpeg encoders:', error);
+    }
+  }
+
+  public getCapabilities(sessionConfig?: any, displayCount?: number): HostCapabilities {
+    const rdp = this.config.get('rdp');
+
+    // A feature is only reported when both the gateway and the session allow it
+    const enabled = (gatewayFlag: boolean, sessionFlag?: boolean): boolean =>
+      gatewayFlag && (sessionConfig ? sessionFlag === true : true);
+
+    return {
+      codecs: CodecRegistry.getInstance().list(),
+      hardwareEncoders: [...this.hardwareEncoders],
+      audio: enabled(rdp.enableAudio, sessionConfig?.enableAudio),
+      touchInput: true,
+      clipboard: enabled(rdp.enableClipboard, sessionConfig?.enableClipboard),
+      fileTransfer: enabled(rdp.enableFileTransfer, sessionConfig?.enableFileTransfer),
+      deviceRedirection: enabled(rdp.enableDeviceRedirection, sessionConfig?.enableDeviceRedirection),
+      multiMonitor: enabled(rdp.enableMultiMonitor, sessionConfig?.enableMultiMonitor),
+      displayCount
+    };
+  }
+
+  // Intersects what the client advertised with what the gateway allows
+  public negotiate(hello: ClientHello): NegotiatedCapabilities {
+    const clientVersion = Number(hello?.protocolVersion);
+    if (!Number.isInteger(clientVersion) || clientVersion < MIN_CLIENT_PROTOCOL_VERSION) {
+      throw new Error(`Unsupported protocol version: ${hello?.protocolVersion} (minimum ${MIN_CLIENT_PROTOCOL_VERSION})`);
+    }
+
+    const gatewayCodecs = CodecRegistry.getInstance().list();
+    const codecs = Array.isArray(hello.codecs)
+      ? hello.codecs.filter(codec => gatewayCodecs.includes(codec))
+      : gatewayCodecs;
+    if (codecs.length === 0) {
+      throw new Error(`No common codec; gateway supports ${gatewayCodecs.join(', ')}`);
+    }
+
+    const gateway = this.getCapabilities();
+    const features = {} as Record<NegotiableFeature, boolean>;
+    for (const feature of NEGOTIABLE_FEATURES) {
+      features[feature] = gateway[feature] && hello.features?.[feature] === true;
+    }
+
+    // Prefer the gateway's configured codec when the client accepts it
+    const configured = this.config.get('rdp').codec;
+
+    return {
+      protocolVersion: Math.min(clientVersion, GATEWAY_PROTOCOL_VERSION),
+      codec: codecs.includes(configured) ? configured : codecs[0],
+      codecs,
+      maxWidth: this.positiveInteger(hello.maxWidth),
+      maxHeight: this.positiveInteger(hello.maxHeight),
+      ...features
+    };
+  }
+
+  private positiveInteger(value: unknown): number {
+    const number = Math.floor(Number(value));
+    return Number.isFinite(number) && number > 0 ? number : 0;
+  }
+}
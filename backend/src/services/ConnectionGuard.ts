@@ -0,0 +1,154 @@
+import { EventEmitter } from 'events';
+import { BlockList, isIP } from 'net';
+import { Logger } from '../utils/Logger';
+import { Config } from '../utils/Config';
+import { parseAllowedIp } from '../utils/Network';
+
+export type ConnectionViolation = 'ip_not_allowed' | 'rate_limited';
+
+export interface ConnectionViolationEvent {
+  ip: string;
+  violation: ConnectionViolation;
+  transport: string;
+}
+
+interface RateWindow {
+  startedAt: number;
+  count: number;
+}
+
+// Enforces the security allowlist and per-IP handshake rate limit for every
+// way a client can reach the gateway. Emits 'violation' once per IP per
+// window, so a flood of rejected attempts does not flood the audit log.
+export class ConnectionGuard extends EventEmitter {
+  private static instance: ConnectionGuard;
+  private logger = new Logger('ConnectionGuard');
+  private config = Config.getInstance();
+  private allowList: BlockList | null = null;
+  private windows: Map<string, RateWindow> = new Map();
+  private lastSweep = Date.now();
+  private reportedDenials: Map<string, number> = new Map();
+
+  constructor() {
+    super();
+    this.loadAllowList();
+  }
+
+  public static getInstance(): ConnectionGuard {
+    if (!ConnectionGuard.instance) {
+      ConnectionGuard.instance = new ConnectionGuard();
+    }
+    return ConnectionGuard.instance;
+  }
+
+  public isAllowed(address: string | undefined, transport: string): boolean {
+    if (!this.allowList) return true;
+
+    const ip = this.normalize(address);
+    const family = isIP(ip);
+    if (family !== 0 && this.allowList.check(ip, family === 6 ? 'ipv6' : 'ipv4')) {
+      return true;
+    }
+
+    this.report(ip, 'ip_not_allowed', transport);
+    return false;
+  }
+
+  // Called for each handshake (socket.io, plain WebSocket, signaling, relay)
+  public admitHandshake(address: string | undefined, transport: string): boolean {
+    if (!this.isAllowed(address, transport)) return false;
+
+    const { rateLimitRequests, rateLimitWindow } = this.config.get('security');
+    if (rateLimitRequests <= 0) return true;
+
+    const ip = this.normalize(address);
+    const now = Date.now();
+    this.sweep(now, rateLimitWindow);
+
+    let window = this.windows.get(ip);
+    if (!window || now - window.startedAt >= rateLimitWindow) {
+      window = { startedAt: now, count: 0 };
+      this.windows.set(ip, window);
+    }
+
+    window.count++;
+    if (window.count > rateLimitRequests) {
+      this.report(ip, 'rate_limited', transport);
+      return false;
+    }
+    return true;
+  }
+
+  public reload(): void {
+    this.loadAllowList();
+    this.windows.clear();
+    this.reportedDenials.clear();
+  }
+
+  private loadAllowList(): void {
+    const { allowedIps, allowLoopback } = this.config.get('security');
+    if (allowedIps.length === 0) {
+      this.allowList = null;
+      return;
+    }
+
+    // Malformed entries are also reported by Config.validate
+    const allowList = new BlockList();
+    for (const entry of allowedIps) {
+      const allowed = parseAllowedIp(entry);
+      if (!allowed) {
+        this.logger.warn(`Ignoring invalid allowed IP entry: ${entry}`);
+        continue;
+      }
+
+      if (allowed.prefix === undefined) {
+        allowList.addAddress(allowed.address, allowed.type);
+      } else {
+        allowList.addSubnet(allowed.address, allowed.prefix, allowed.type);
+      }
+    }
+
+    // For local health checks; off by default, see SecurityConfig.allowLoopback
+    if (allowLoopback) {
+      allowList.addAddress('127.0.0.1', 'ipv4');
+      allowList.addAddress('::1', 'ipv6');
+    }
+
+    this.allowList = allowList;
+    this.logger.info(`IP allowlist enabled with ${allowedIps.length} entries`);
+  }
+
+  // IPv4 clients on a dual-stack listener show up as ::ffff:a.b.c.d
+  private normalize(address: string | undefined): string {
+    if (!address) return 'unknown';
+    return address.startsWith('::ffff:') && isIP(address.slice(7)) === 4 ? address.slice(7) : address;
+  }
+
+  private report(ip: string, violation: ConnectionViolation, transport: string): void {
+    const { rateLimitWindow } = this.config.get('security');
+    const key = `${violation}:${ip}`;
+    const now = Date.now();
+    const last = this.reportedDenials.get(key);
+    if (last !== undefined && now - last < rateLimitWindow) return;
+
+    this.reportedDenials.set(key, now);
+    this.emit('violation', { ip, violation, transport } as ConnectionViolationEvent);
+  }
+
+  // Drops expired windows at most once per window length
+  private sweep(now: number, rateLimitWindow: number): void {
+    if (now - this.lastSweep < rateLimitWindow) return;
+    this.lastSweep = now;
+
+    for (const [ip, window] of this.windows) {
+      if (now - window.startedAt >= rateLimitWindow) {
+        this.windows.delete(ip);
+      }
+    }
+    for (const [key, reportedAt] of this.reportedDenials) {
+      if (now - reportedAt >= rateLimitWindow) {
+        this.reportedDenials.delete(key);
+      }
+    }
+  }
+}
@@ -0,0 +1,73 @@
+import { hostname } from 'os';
+import { Bonjour, Service } from 'bonjour-service';
+import { Logger } from '../utils/Logger';
+import { Config } from '../utils/Config';
+import { HostCapabilities, GATEWAY_PROTOCOL_VERSION } from './CapabilityReporter';
+
+// DNS-SD service type, i.e. _rrd._tcp.local
+const SERVICE_TYPE = 'rrd';
+
+// Advertises the gateway over mDNS so clients on the LAN can list hosts
+// without typing an address. TXT values are kept short; a DNS TXT string
+// holds at most 255 bytes.
+export class DiscoveryAdvertiser {
+  private logger = new Logger('DiscoveryAdvertiser');
+  private config = Config.getInstance();
+  private bonjour: Bonjour | null = null;
+  private service: Service | null = null;
+
+  public start(port: number, capabilities: HostCapabilities): void {
+    const { instanceName } = this.config.get('server').discovery;
+    const name = instanceName || hostname();
+
+    try {
+      this.bonjour = new Bonjour();
+      this.service = this.bonjour.publish({
+        name,
+        type: SERVICE_TYPE,
+        port,
+        txt: this.buildTxtRecord(capabilities)
+      });
+      this.service.on('error', (error: Error) => this.logger.warn('mDNS advertisement error:', error));
+
+      this.logger.info(`Advertising ${name} as _${SERVICE_TYPE}._tcp on port ${port}`);
+    } catch (error) {
+      this.logger.warn('Failed to start mDNS advertisement:', error);
+      this.stop();
+    }
+  }
+
+  // Sends goodbye packets so clients drop the host straight away
+  public stop(): Promise<void> {
+    const bonjour = this.bonjour;
+    this.bonjour = null;
+    this.service = null;
+    if (!bonjour) return Promise.resolve();
+
+    return new Promise((resolve) => {
+      bonjour.unpublishAll(() => {
+        bonjour.destroy();
+        resolve();
+      });
+    });
+  }
+
+  private buildTxtRecord(capabilities: HostCapabilities): Record<string, string> {
+    const server = this.config.get('server');
+    const websocket = this.config.get('websocket');
+    const features = (['audio', 'clipboard', 'fileTransfer', 'deviceRedirection', 'multiMonitor'] as const)
+      .filter(feature => capabilities[feature]);
+
+    const txt: Record<string, string> = {
+      version: String(GATEWAY_PROTOCOL_VERSION),
+      tls: server.ssl.enabled ? '1' : '0',
+      auth: this.config.get('auth').allowAnonymous ? 'optional' : 'required',
+      codecs: capabilities.codecs.join(','),
+      features: features.join(',')
+    };
+    if (websocket.rawPath) {
+      txt.path = websocket.rawPath;
+    }
+    return txt;
+  }
+}
@@ -1,6 +1,6 @@
 import { Logger } from '../utils/Logger';
 import { EventEmitter } from 'events';
-import { createReadStream, createWriteStream, existsSync, mkdirSync, statSync } from 'fs';
+import { createReadStream, createWriteStream, existsSync, mkdirSync, statSync, watch, FSWatcher } from 'fs';
 import { join, dirname, basename, extname } from 'path';
 import { v4 as uuidv4 } from 'uuid';
 import sharp from 'sharp';
@@ -31,10 +31,41 @@ export interface VirtualFolder {
   createdAt: Date;
 }
 
+export interface FileOffer {
+  id: string;
+  fileName: string;
+  filePath: string;
+  fileSize: number;
+  mimeType: string;
+  offeredAt: Date;
+}
+
+const MIME_TYPES: Record<string, string> = {
+  '.txt': 'text/plain',
+  '.csv': 'text/csv',
+  '.json': 'application/json',
+  '.xml': 'application/xml',
+  '.pdf': 'application/pdf',
+  '.zip': 'application/zip',
+  '.png': 'image/png',
+  '.jpg': 'image/jpeg',
+  '.jpeg': 'image/jpeg',
+  '.xlsx': 'application/vnd.openxmlformats-officedocument.spreadsheetml.sheet',
+  '.docx': 'application/vnd.openxmlformats-officedocument.wordprocessingml.document'
+};
+
 export class FileTransferManager extends EventEmitter {
   private logger = new Logger('FileTransferManager');
   private transfers: Map<string, FileTransfer> = new Map();
   private virtualFolders: Map<string, VirtualFolder> = new Map();
+  private fileOffers: Map<string, FileOffer> = new Map();
+  // Offers each RDP session has accepted or declined; sessions decide on an
+  // offer independently of each other
+  private resolvedOffers: Map<string, Set<string>> = new Map();
+  private offeredFiles: Map<string, number> = new Map();
+  private pendingWatchEvents: Map<string, NodeJS.Timeout> = new Map();
+  private watcher: FSWatcher | null = null;
+  private watchDir: string | null = null;
   private uploadDir: string;
   private downloadDir: string;
 
@@ -371,6 +402,161 @@ export class FileTransferManager extends EventEmitter {
     }
   }
 
+  public startWatchFolder(dir: string, settleDelay: number = 2000): void {
+    if (this.watcher) {
+      this.stopWatchFolder();
+    }
+
+    if (!existsSync(dir)) {
+      mkdirSync(dir, { recursive: true });
+    }
+
+    this.watchDir = dir;
+    this.watcher = watch(dir, (eventType, fileName) => {
+      if (fileName) {
+        this.scheduleWatchCheck(fileName.toString(), settleDelay, -1);
+      }
+    });
+
+    this.watcher.on('error', (error) => {
+      this.logger.error(`Watch folder error: ${dir}`, error);
+    });
+
+    this.logger.info(`Watching folder for outbound files: ${dir}`);
+  }
+
+  public stopWatchFolder(): void {
+    if (!this.watcher) return;
+
+    this.watcher.close();
+    this.watcher = null;
+
+    for (const timer of this.pendingWatchEvents.values()) {
+      clearTimeout(timer);
+    }
+    this.pendingWatchEvents.clear();
+
+    this.logger.info(`Stopped watching folder: ${this.watchDir}`);
+    this.watchDir = null;
+  }
+
+  private scheduleWatchCheck(fileName: string, settleDelay: number, lastSize: number): void {
+    const pending = this.pendingWatchEvents.get(fileName);
+    if (pending) {
+      clearTimeout(pending);
+    }
+
+    // Wait for the file size to settle so half-written files are not offered
+    const timer = setTimeout(() => {
+      this.pendingWatchEvents.delete(fileName);
+      if (!this.watchDir) return;
+
+      const filePath = join(this.watchDir, fileName);
+      if (!existsSync(filePath)) return;
+
+      try {
+        const stats = statSync(filePath);
+        if (!stats.isFile()) return;
+
+        if (stats.size !== lastSize) {
+          this.scheduleWatchCheck(fileName, settleDelay, stats.size);
+          return;
+        }
+
+        // Only offer each version of a file once
+        if (this.offeredFiles.get(filePath) === stats.mtimeMs) return;
+        this.offeredFiles.set(filePath, stats.mtimeMs);
+
+        this.createFileOffer(filePath, stats.size);
+      } catch (error) {
+        this.logger.warn(`Failed to inspect watched file: ${fileName}`, error);
+      }
+    }, settleDelay);
+
+    this.pendingWatchEvents.set(fileName, timer);
+  }
+
+  private createFileOffer(filePath: string, fileSize: number): FileOffer {
+    const offer: FileOffer = {
+      id: uuidv4(),
+      fileName: basename(filePath),
+      filePath,
+      fileSize,
+      mimeType: MIME_TYPES[extname(filePath).toLowerCase()] || 'application/octet-stream',
+      offeredAt: new Date()
+    };
+
+    // A new version of a file replaces the offer for the old one
+    for (const existing of this.fileOffers.values()) {
+      if (existing.filePath === filePath) {
+        this.removeFileOffer(existing.id);
+      }
+    }
+
+    this.fileOffers.set(offer.id, offer);
+    this.logger.info(`File offered from watch folder: ${offer.fileName} (${offer.fileSize} bytes)`);
+
+    this.emit('fileOffered', offer);
+    return offer;
+  }
+
+  // Offers the RDP session has not accepted or declined yet
+  public getFileOffers(sessionId: string): FileOffer[] {
+    const resolved = this.resolvedOffers.get(sessionId);
+    return Array.from(this.fileOffers.values()).filter(offer => !resolved?.has(offer.id));
+  }
+
+  // The download goes to `socketId`, the client that accepted
+  public async acceptFileOffer(sessionId: string, offerId: string, socketId: string): Promise<any> {
+    const offer = this.resolveFileOffer(sessionId, offerId);
+    if (!offer) {
+      throw new Error(`File offer not found: ${offerId}`);
+    }
+
+    this.emit('fileOfferResolved', { offerId, accepted: true, sessionId });
+
+    return this.handleDownload(socketId, {
+      fileName: offer.fileName,
+      filePath: offer.filePath,
+      mimeType: offer.mimeType
+    });
+  }
+
+  public declineFileOffer(sessionId: string, offerId: string): boolean {
+    if (!this.resolveFileOffer(sessionId, offerId)) {
+      return false;
+    }
+
+    this.logger.info(`File offer ${offerId} declined in session ${sessionId}`);
+    this.emit('fileOfferResolved', { offerId, accepted: false, sessionId });
+    return true;
+  }
+
+  // Drops what an ended session decided, so the record does not outlive it
+  public forgetFileOffers(sessionId: string): void {
+    this.resolvedOffers.delete(sessionId);
+  }
+
+  private resolveFileOffer(sessionId: string, offerId: string): FileOffer | undefined {
+    const offer = this.fileOffers.get(offerId);
+    let resolved = this.resolvedOffers.get(sessionId);
+    if (!offer || resolved?.has(offerId)) return undefined;
+
+    if (!resolved) {
+      resolved = new Set();
+      this.resolvedOffers.set(sessionId, resolved);
+    }
+    resolved.add(offerId);
+    return offer;
+  }
+
+  private removeFileOffer(offerId: string): void {
+    this.fileOffers.delete(offerId);
+    for (const resolved of this.resolvedOffers.values()) {
+      resolved.delete(offerId);
+    }
+  }
+
   public getTransfer(transferId: string): FileTransfer | undefined {
     return this.transfers.get(transferId);
   }
@@ -427,7 +613,8 @@ export class FileTransferManager extends EventEmitter {
       failed,
       inProgress,
       totalSize,
-      virtualFolders: this.virtualFolders.size
+      virtualFolders: this.virtualFolders.size,
+      pendingOffers: this.fileOffers.size
     };
   }
 } 
\ No newline at end of file
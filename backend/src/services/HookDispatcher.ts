@@ -0,0 +1,51 @@
+import { Logger } from '../utils/Logger';
+import { Config } from '../utils/Config';
+import * as http from 'http';
+import * as https from 'https';
+
+export class HookDispatcher {
+  private logger = new Logger('HookDispatcher');
+  private config = Config.getInstance();
+
+  public dispatchSessionSummary(summary: any): void {
+    const url = this.config.get('hooks').sessionSummaryUrl;
+    if (!url) return;
+
+    this.post(url, { event: 'session.summary', data: summary });
+  }
+
+  // Fire-and-forget; a slow or failing hook must never hold up session teardown
+  private post(url: string, payload: any): void {
+    try {
+      const body = JSON.stringify(payload);
+      const target = new URL(url);
+      const transport = target.protocol === 'https:' ? https : http;
+
+      const req = transport.request(target, {
+        method: 'POST',
+        timeout: this.config.get('hooks').timeout,
+        headers: {
+          'Content-Type': 'application/json',
+          'Content-Length': Buffer.byteLength(body)
+        }
+      }, (res) => {
+        res.resume();
+        if (res.statusCode && res.statusCode >= 400) {
+          this.logger.warn(`Hook ${target.host} responded with ${res.statusCode}`);
+        }
+      });
+
+      req.on('timeout', () => {
+        req.destroy(new Error('Hook request timed out'));
+      });
+
+      req.on('error', (error) => {
+        this.logger.warn(`Hook delivery to ${target.host} failed:`, error);
+      });
+
+      req.end(body);
+    } catch (error) {
+      this.logger.error('Failed to dispatch hook:', error);
+    }
+  }
+}
@@ -0,0 +1,131 @@
+import { Logger } from '../utils/Logger';
+import { Config, IceServerConfig } from '../utils/Config';
+import { EventEmitter } from 'events';
+import * as crypto from 'crypto';
+
+// setInterval fires almost at once for delays above this (or not a number)
+const MAX_TIMER_DELAY = 0x7fffffff;
+
+// Carries the servers as configured; recipients fetch their own TURN
+// credentials with getIceServers, so no two peers share a username
+export interface IceServersUpdate {
+  iceServers: IceServerConfig[];
+  version: number;
+  restartIce: boolean;
+}
+
+export class IceServerManager extends EventEmitter {
+  private static instance: IceServerManager;
+  private logger = new Logger('IceServerManager');
+  private config = Config.getInstance();
+  private iceServers: IceServerConfig[];
+  private version = 1;
+  private credentialRefreshTimer: NodeJS.Timeout | null = null;
+
+  constructor() {
+    super();
+    // Every connected peer listens for updates
+    this.setMaxListeners(0);
+    this.iceServers = this.config.get('webrtc').iceServers;
+    this.startCredentialRefresh();
+  }
+
+  // Shared by the gateway and the REST routes so updates reach connected clients
+  public static getInstance(): IceServerManager {
+    if (!IceServerManager.instance) {
+      IceServerManager.instance = new IceServerManager();
+    }
+    return IceServerManager.instance;
+  }
+
+  // `label` ends up in the TURN username, so TURN server logs show who relayed
+  public getIceServers(label: string = 'rdp-gateway'): { iceServers: IceServerConfig[]; version: number } {
+    return { iceServers: this.withTurnCredentials(this.iceServers, label), version: this.version };
+  }
+
+  // TURN REST API credentials: username is "<expiry>:<label>", credential is
+  // base64(HMAC-SHA1(secret, username)), verified by the TURN server itself
+  public generateTurnCredentials(label: string): { username: string; credential: string; ttl: number } {
+    const { turnSecret, turnCredentialTtl } = this.config.get('webrtc');
+    const expiry = Math.floor(Date.now() / 1000) + turnCredentialTtl;
+    const username = `${expiry}:${label.replace(/:/g, '_')}`;
+    const credential = crypto.createHmac('sha1', turnSecret).update(username).digest('base64');
+
+    return { username, credential, ttl: turnCredentialTtl };
+  }
+
+  public shutdown(): void {
+    if (this.credentialRefreshTimer) {
+      clearInterval(this.credentialRefreshTimer);
+      this.credentialRefreshTimer = null;
+    }
+  }
+
+  public setIceServers(iceServers: IceServerConfig[], restartIce: boolean = false): IceServersUpdate {
+    const errors = this.validate(iceServers);
+    if (errors.length > 0) {
+      throw new Error(`Invalid ICE servers: ${errors.join(', ')}`);
+    }
+
+    this.iceServers = iceServers;
+    this.version++;
+    this.config.set('webrtc.iceServers', iceServers);
+
+    const update: IceServersUpdate = { iceServers, version: this.version, restartIce };
+    this.logger.info(`ICE servers updated to version ${this.version} (${iceServers.length} servers${restartIce ? ', ICE restart requested' : ''})`);
+    this.emit('iceServersUpdated', update);
+
+    return update;
+  }
+
+  public reloadFromConfig(restartIce: boolean = false): IceServersUpdate {
+    this.config.reload();
+    return this.setIceServers(this.config.get('webrtc').iceServers, restartIce);
+  }
+
+  private withTurnCredentials(iceServers: IceServerConfig[], label: string): IceServerConfig[] {
+    const { turnSecret, turnUrls } = this.config.get('webrtc');
+    if (!turnSecret || turnUrls.length === 0) return iceServers;
+
+    const { username, credential } = this.generateTurnCredentials(label);
+    return [...iceServers, { urls: turnUrls, username, credential }];
+  }
+
+  // Have connected clients fetch fresh credentials well before the old ones expire
+  private startCredentialRefresh(): void {
+    const { turnSecret, turnUrls, turnCredentialTtl } = this.config.get('webrtc');
+    if (!turnSecret || turnUrls.length === 0) return;
+
+    const halfLife = (turnCredentialTtl * 1000) / 2;
+    const refreshInterval = Number.isFinite(halfLife) ? Math.max(1000, Math.min(halfLife, MAX_TIMER_DELAY)) : MAX_TIMER_DELAY;
+    this.credentialRefreshTimer = setInterval(() => {
+      this.version++;
+      this.emit('iceServersUpdated', { iceServers: this.iceServers, version: this.version, restartIce: false });
+    }, refreshInterval);
+    this.credentialRefreshTimer.unref();
+
+    this.logger.info(`Issuing TURN credentials for ${turnUrls.join(', ')} (valid ${turnCredentialTtl}s)`);
+  }
+
+  private validate(iceServers: IceServerConfig[]): string[] {
+    const errors: string[] = [];
+
+    if (!Array.isArray(iceServers)) {
+      return ['iceServers must be an array'];
+    }
+
+    iceServers.forEach((server, index) => {
+      const urls = Array.isArray(server.urls) ? server.urls : [server.urls];
+      if (urls.length === 0 || urls.some(url => typeof url !== 'string' || !/^(stun|stuns|turn|turns):/.test(url))) {
+        errors.push(`server ${index} must have stun:, stuns:, turn: or turns: urls`);
+      }
+
+      const isTurn = urls.some(url => typeof url === 'string' && url.startsWith('turn'));
+      if (isTurn && (!server.username || !server.credential)) {
+        errors.push(`server ${index} is a TURN server and needs username and credential`);
+      }
+    });
+
+    return errors;
+  }
+}
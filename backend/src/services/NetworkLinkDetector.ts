@@ -0,0 +1,135 @@
+import { Logger } from '../utils/Logger';
+import { networkInterfaces, platform } from 'os';
+import { existsSync, readFileSync } from 'fs';
+import { join } from 'path';
+
+export type NetworkLinkType = 'ethernet' | 'wifi' | 'cellular' | 'unknown';
+
+export interface NetworkLinkInfo {
+  interface: string | null;
+  type: NetworkLinkType;
+  speedMbps: number | null;
+  detectedAt: Date;
+}
+
+export class NetworkLinkDetector {
+  private logger = new Logger('NetworkLinkDetector');
+  private sysNetDir = '/sys/class/net';
+
+  public detect(): NetworkLinkInfo {
+    const iface = this.findActiveInterface();
+
+    const link: NetworkLinkInfo = {
+      interface: iface,
+      type: 'unknown',
+      speedMbps: null,
+      detectedAt: new Date()
+    };
+
+    if (!iface) {
+      this.logger.warn('No active network interface found');
+      return link;
+    }
+
+    try {
+      if (platform() === 'linux' && existsSync(join(this.sysNetDir, iface))) {
+        link.type = this.detectLinuxLinkType(iface);
+        link.speedMbps = this.readLinuxLinkSpeed(iface);
+      } else {
+        link.type = this.guessLinkTypeFromName(iface);
+      }
+    } catch (error) {
+      this.logger.warn(`Failed to detect link type for ${iface}:`, error);
+    }
+
+    this.logger.info(`Active network link: ${iface} (${link.type}${link.speedMbps ? `, ${link.speedMbps} Mbps` : ''})`);
+    return link;
+  }
+
+  private findActiveInterface(): string | null {
+    // Prefer the interface carrying the default route
+    if (platform() === 'linux') {
+      const routeInterface = this.findDefaultRouteInterface();
+      if (routeInterface) {
+        return routeInterface;
+      }
+    }
+
+    const interfaces = networkInterfaces();
+    for (const [name, addresses] of Object.entries(interfaces)) {
+      if (addresses && addresses.some(address => !address.internal && address.family === 'IPv4')) {
+        return name;
+      }
+    }
+
+    return null;
+  }
+
+  private findDefaultRouteInterface(): string | null {
+    try {
+      const routes = readFileSync('/proc/net/route', 'utf8').trim().split('\n').slice(1);
+      let best: { iface: string; metric: number } | null = null;
+
+      for (const line of routes) {
+        const [iface, destination, , , , , metric] = line.trim().split(/\s+/);
+        if (destination !== '00000000') continue;
+
+        const routeMetric = parseInt(metric) || 0;
+        if (!best || routeMetric < best.metric) {
+          best = { iface, metric: routeMetric };
+        }
+      }
+
+      return best ? best.iface : null;
+    } catch (error) {
+      return null;
+    }
+  }
+
+  private detectLinuxLinkType(iface: string): NetworkLinkType {
+    const ifaceDir = join(this.sysNetDir, iface);
+
+    if (existsSync(join(ifaceDir, 'wireless')) || existsSync(join(ifaceDir, 'phy80211'))) {
+      return 'wifi';
+    }
+
+    if (/^(wwan|rmnet|usb|ppp)/.test(iface)) {
+      return 'cellular';
+    }
+
+    // ARPHRD_ETHER backed by a physical device
+    const type = readFileSync(join(ifaceDir, 'type'), 'utf8').trim();
+    if (type === '1' && existsSync(join(ifaceDir, 'device'))) {
+      return 'ethernet';
+    }
+
+    return this.guessLinkTypeFromName(iface);
+  }
+
+  private readLinuxLinkSpeed(iface: string): number | null {
+    try {
+      // Wireless and virtual interfaces report EINVAL or -1 here
+      const speed = parseInt(readFileSync(join(this.sysNetDir, iface, 'speed'), 'utf8').trim());
+      return speed > 0 ? speed : null;
+    } catch (error) {
+      return null;
+    }
+  }
+
+  private guessLinkTypeFromName(iface: string): NetworkLinkType {
+    if (/^(wl|wlan|wifi|wi-fi|wireless|airport)/i.test(iface)) {
+      return 'wifi';
+    }
+
+    if (/^(wwan|cellular|mobile)/i.test(iface)) {
+      return 'cellular';
+    }
+
+    // macOS names Wi-Fi and Ethernet alike (en0, en1), so only trust eth-style names
+    if (/^(eth|eno|ens|enp|ethernet|local area connection)/i.test(iface)) {
+      return 'ethernet';
+    }
+
+    return 'unknown';
+  }
+}
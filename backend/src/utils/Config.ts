@@ -1,5 +1,7 @@
 import { join } from 'path';
 import { existsSync, readFileSync } from 'fs';
+import { parseHotkey } from '../core/HotkeyFilter';
+import { parseAllowedIp } from './Network';
 
 export interface ServerConfig {
   host: string;
@@ -8,7 +10,43 @@ export interface ServerConfig {
     enabled: boolean;
     cert: string;
     key: string;
+    // CA bundle used to verify client certificates
+    ca: string;
+    requireClientCert: boolean;
+    // Extra certificates selected by SNI server name
+    sniCerts: Record<string, { cert: string; key: string }>;
+    minVersion: 'TLSv1.2' | 'TLSv1.3';
   };
+  controlSocket: {
+    enabled: boolean;
+    path: string;
+  };
+  // Concurrent client connections across all transports; 0 is unlimited
+  maxConnections: number;
+  // Longest wait for the shutdown notice to reach clients before sockets close
+  shutdownTimeout: number;
+  // Suggested delay before clients reconnect after a shutdown; 0 asks them not to
+  shutdownRetryAfter: number;
+  // Applied to the HTTP/WebSocket listener and the native transport
+  socket: SocketConfig;
+  // mDNS/DNS-SD advertisement for LAN clients
+  discovery: {
+    enabled: boolean;
+    // Defaults to the hostname
+    instanceName: string;
+  };
+}
+
+export interface SocketConfig {
+  noDelay: boolean;
+  // Kernel buffer sizes for the UDP video channel in bytes; 0 keeps the
+  // OS default. Node has no way to set them on TCP sockets.
+  sendBufferSize: number;
+  receiveBufferSize: number;
+  // With an IPv6 host (e.g. ::), accept IPv6 only instead of dual-stack
+  ipv6Only: boolean;
+  // Interface (e.g. eth1) whose address listeners bind to instead of host
+  bindInterface: string;
 }
 
 export interface AuthConfig {
@@ -19,11 +57,21 @@ export interface AuthConfig {
   lockoutDuration: number;
 }
 
+export interface ResolutionCap {
+  maxWidth: number; // 0 = uncapped
+  maxHeight: number; // 0 = uncapped
+  scale: number; // Applied before the caps; 1 = native
+}
+
+export type FrameQueuePolicy = 'drop-oldest' | 'drop-newest' | 'coalesce';
+
 export interface RDPConfig {
   defaultPort: number;
   timeout: number;
   reconnectAttempts: number;
   reconnectDelay: number;
+  // How long a session survives its client dropping, waiting to be resumed
+  resumeGracePeriod: number;
   enableNLA: boolean;
   enableTLS: boolean;
   enableCredSSP: boolean;
@@ -40,15 +88,70 @@ export interface RDPConfig {
   enableSpeakerRedirection: boolean;
   enableMultiMonitor: boolean;
   maxSessions: number;
+  // View-only clients per session, besides the controller
+  maxViewersPerSession: number;
   maxSessionDuration: number;
+  backpressureQueueDepth: number;
+  backpressureTimeInQueue: number;
+  backpressureSustainFor: number;
+  clipboardConflictPolicy: 'last-writer-wins' | 'host-priority';
+  clipboardConflictWindow: number;
+  metricsSampleInterval: number;
+  metricsHistorySize: number;
+  // Disconnected sessions kept for their summaries and metrics; the oldest go first
+  finishedSessionLimit: number;
+  adaptiveBitrate: boolean;
+  adaptiveMinBitrate: number;
+  adaptiveCongestionQueueTime: number;
+  adaptiveCongestionLatency: number;
+  adaptiveRecoveryPeriod: number;
+  codec: string;
+  frameQueueSize: number;
+  frameQueuePolicy: FrameQueuePolicy;
+  thumbnailInterval: number;
+  thumbnailWidth: number;
+  thumbnailQuality: number;
+  // Opus bitrate for host audio, in bits/s
+  audioBitrate: number;
+  resolutionCaps: {
+    low: ResolutionCap;
+    medium: ResolutionCap;
+    high: ResolutionCap;
+    ultra: ResolutionCap;
+    lossless: ResolutionCap;
+  };
 }
 
 export interface WebSocketConfig {
   pingInterval: number;
+  // Plain WebSocket clients silent for this many ping intervals are closed
+  heartbeatMisses: number;
   pingTimeout: number;
   upgradeTimeout: number;
   maxHttpBufferSize: number;
   allowEIO3: boolean;
+  rawPath: string;
+  rawMaxPayload: number;
+  // Embedded WebRTC signaling endpoint; empty disables it
+  signalingPath: string;
+  // Outbound relay (wss://) for gateways that cannot accept inbound
+  // connections; empty disables it. The relay terminates clients' TLS and
+  // sees their traffic unencrypted, so only point this at a relay you run.
+  relayUrl: string;
+  relayToken: string;
+  // Name the gateway registers under; defaults to the hostname
+  relayName: string;
+  // Buffered messages above which a client's video frames are coalesced
+  maxPendingFrames: number;
+  // Small dirty-rect updates are batched into one message: at most
+  // frameBatchSize updates or frameBatchBytes, waiting frameBatchLatency ms
+  // at most; a latency of 0 sends every update on its own
+  frameBatchSize: number;
+  frameBatchBytes: number;
+  frameBatchLatency: number;
+  // permessage-deflate for non-video messages at least this many bytes long
+  compression: boolean;
+  compressionThreshold: number;
   cors: {
     origin: string[];
     methods: string[];
@@ -56,6 +159,19 @@ export interface WebSocketConfig {
   };
 }
 
+// TCP/UDP transport for native clients that skip WebSocket and WebRTC
+export interface NativeTransportConfig {
+  // 0 disables the transport
+  tcpPort: number;
+  // Optional UDP channel for video; 0 keeps video on the TCP stream
+  udpPort: number;
+  maxPayload: number;
+  // XOR parity datagram per this many UDP fragments; 0 disables FEC
+  fecGroupSize: number;
+  // Client-reported UDP loss rate (0-1) at which FEC is switched on
+  fecLossThreshold: number;
+}
+
 export interface FileTransferConfig {
   maxFileSize: number;
   allowedTypes: string[];
@@ -64,6 +180,8 @@ export interface FileTransferConfig {
   enableCompression: boolean;
   enableEncryption: boolean;
   maxConcurrentTransfers: number;
+  watchDir: string;
+  watchSettleDelay: number;
 }
 
 export interface DeviceConfig {
@@ -78,6 +196,21 @@ export interface DeviceConfig {
   maxDevicesPerSession: number;
 }
 
+export interface InputConfig {
+  // Pointer moves and wheel events forwarded per second per session; the
+  // excess is merged into the next event. 0 disables the limit.
+  maxMoveRate: number;
+  maxWheelRate: number;
+  // Key combinations never injected into the host, e.g. "win+r", "alt+f4",
+  // for kiosk-style restricted access
+  blockedHotkeys: string[];
+  // ms without input or client activity before a session counts as idle;
+  // 0 disables idle detection. The client is always told; 'lock' also locks
+  // the host workstation and 'disconnect' ends the session.
+  idleTimeout: number;
+  idleAction: 'notify' | 'lock' | 'disconnect';
+}
+
 export interface AuditConfig {
   enabled: boolean;
   logLevel: string;
@@ -94,20 +227,68 @@ export interface CORSConfig {
   maxAge: number;
 }
 
+export interface NetworkConfig {
+  enableLinkDetection: boolean;
+  wiredBitrateCeiling: number;
+  wifiBitrateCeiling: number;
+  cellularBitrateCeiling: number;
+  defaultBitrateCeiling: number;
+}
+
+export interface IceServerConfig {
+  urls: string | string[];
+  username?: string;
+  credential?: string;
+}
+
+export interface WebRTCConfig {
+  iceServers: IceServerConfig[];
+  // Shared secret for time-limited TURN credentials (TURN REST API, e.g.
+  // coturn's use-auth-secret); empty disables generation
+  turnSecret: string;
+  turnUrls: string[];
+  turnCredentialTtl: number;
+}
+
+export interface SecurityConfig {
+  // IPs or CIDR ranges allowed to connect; empty allows everyone
+  allowedIps: string[];
+  // Also admits 127.0.0.1 and ::1 while the allowlist is on. Behind a reverse
+  // proxy on the same host every client appears as loopback, which would
+  // bypass the allowlist and share one rate-limit bucket, so this is opt-in.
+  allowLoopback: boolean;
+  // Handshakes allowed per IP per window; 0 disables the limit
+  rateLimitRequests: number;
+  rateLimitWindow: number;
+}
+
+export interface HooksConfig {
+  sessionSummaryUrl: string;
+  timeout: number;
+}
+
 export interface AppConfig {
   server: ServerConfig;
   auth: AuthConfig;
   rdp: RDPConfig;
   websocket: WebSocketConfig;
+  native: NativeTransportConfig;
   fileTransfer: FileTransferConfig;
   device: DeviceConfig;
+  input: InputConfig;
   audit: AuditConfig;
   cors: CORSConfig;
+  network: NetworkConfig;
+  webrtc: WebRTCConfig;
+  security: SecurityConfig;
+  hooks: HooksConfig;
 }
 
 export class Config {
   private static instance: Config;
   private config: AppConfig;
+  // Settings that could not be parsed, reported by validate()
+  private parseErrors: string[] = [];
 
   private constructor() {
     this.config = this.loadConfig();
@@ -121,6 +302,7 @@ export class Config {
   }
 
   private loadConfig(): AppConfig {
+    this.parseErrors = [];
     // Load from environment variables first, then config file
     const config: AppConfig = {
       server: {
@@ -129,7 +311,30 @@ export class Config {
         ssl: {
           enabled: process.env.SSL_ENABLED === 'true',
           cert: process.env.SSL_CERT || '',
-          key: process.env.SSL_KEY || ''
+          key: process.env.SSL_KEY || '',
+          ca: process.env.SSL_CA || '',
+          requireClientCert: process.env.SSL_REQUIRE_CLIENT_CERT === 'true',
+          sniCerts: this.parseJsonEnv('SSL_SNI_CERTS', {}),
+          minVersion: process.env.SSL_MIN_VERSION === 'TLSv1.3' ? 'TLSv1.3' : 'TLSv1.2'
+        },
+        // Grants local admin to whoever can connect; opt in, Unix only
+        controlSocket: {
+          enabled: process.env.CONTROL_SOCKET_ENABLED === 'true',
+          path: process.env.CONTROL_SOCKET_PATH || join(process.cwd(), 'gateway.sock')
+        },
+        maxConnections: parseInt(process.env.SERVER_MAX_CONNECTIONS || '0'),
+        shutdownTimeout: parseInt(process.env.SERVER_SHUTDOWN_TIMEOUT || '5000'), // ms
+        shutdownRetryAfter: parseInt(process.env.SERVER_SHUTDOWN_RETRY_AFTER || '5000'), // ms
+        socket: {
+          noDelay: process.env.SOCKET_NO_DELAY !== 'false',
+          sendBufferSize: parseInt(process.env.SOCKET_SEND_BUFFER_SIZE || '0'),
+          receiveBufferSize: parseInt(process.env.SOCKET_RECEIVE_BUFFER_SIZE || '0'),
+          ipv6Only: process.env.SOCKET_IPV6_ONLY === 'true',
+          bindInterface: process.env.SOCKET_BIND_INTERFACE || ''
+        },
+        discovery: {
+          enabled: process.env.MDNS_ENABLED === 'true',
+          instanceName: process.env.MDNS_INSTANCE_NAME || ''
         }
       },
       auth: {
@@ -144,6 +349,7 @@ export class Config {
         timeout: parseInt(process.env.RDP_TIMEOUT || '30000'),
         reconnectAttempts: parseInt(process.env.RDP_RECONNECT_ATTEMPTS || '3'),
         reconnectDelay: parseInt(process.env.RDP_RECONNECT_DELAY || '5000'),
+        resumeGracePeriod: parseInt(process.env.RDP_RESUME_GRACE_PERIOD || '30000'), // 0 disables resume
         enableNLA: process.env.RDP_ENABLE_NLA !== 'false',
         enableTLS: process.env.RDP_ENABLE_TLS !== 'false',
         enableCredSSP: process.env.RDP_ENABLE_CREDSSP !== 'false',
@@ -160,20 +366,87 @@ export class Config {
         enableSpeakerRedirection: process.env.RDP_ENABLE_SPEAKER_REDIRECTION !== 'false',
         enableMultiMonitor: process.env.RDP_ENABLE_MULTI_MONITOR !== 'false',
         maxSessions: parseInt(process.env.RDP_MAX_SESSIONS || '100'),
-        maxSessionDuration: parseInt(process.env.RDP_MAX_SESSION_DURATION || '28800000') // 8 hours
+        maxViewersPerSession: parseInt(process.env.RDP_MAX_VIEWERS_PER_SESSION || '4'),
+        maxSessionDuration: parseInt(process.env.RDP_MAX_SESSION_DURATION || '28800000'), // 8 hours
+        backpressureQueueDepth: parseInt(process.env.RDP_BACKPRESSURE_QUEUE_DEPTH || '30'),
+        backpressureTimeInQueue: parseInt(process.env.RDP_BACKPRESSURE_TIME_IN_QUEUE || '250'), // ms
+        backpressureSustainFor: parseInt(process.env.RDP_BACKPRESSURE_SUSTAIN_FOR || '3000'), // ms
+        clipboardConflictPolicy: process.env.RDP_CLIPBOARD_CONFLICT_POLICY === 'host-priority' ? 'host-priority' : 'last-writer-wins',
+        clipboardConflictWindow: parseInt(process.env.RDP_CLIPBOARD_CONFLICT_WINDOW || '500'), // ms
+        metricsSampleInterval: parseInt(process.env.RDP_METRICS_SAMPLE_INTERVAL || '1000'), // ms
+        metricsHistorySize: parseInt(process.env.RDP_METRICS_HISTORY_SIZE || '900'), // 15 minutes at 1s
+        finishedSessionLimit: parseInt(process.env.RDP_FINISHED_SESSION_LIMIT || '100'),
+        adaptiveBitrate: process.env.RDP_ADAPTIVE_BITRATE !== 'false',
+        adaptiveMinBitrate: parseInt(process.env.RDP_ADAPTIVE_MIN_BITRATE || '500000'),
+        adaptiveCongestionQueueTime: parseInt(process.env.RDP_ADAPTIVE_CONGESTION_QUEUE_TIME || '150'), // ms
+        adaptiveCongestionLatency: parseInt(process.env.RDP_ADAPTIVE_CONGESTION_LATENCY || '400'), // ms
+        adaptiveRecoveryPeriod: parseInt(process.env.RDP_ADAPTIVE_RECOVERY_PERIOD || '5000'), // ms
+        codec: process.env.RDP_CODEC || 'deflate', // Any name registered in CodecRegistry
+        frameQueueSize: parseInt(process.env.RDP_FRAME_QUEUE_SIZE || '8'), // Per display
+        frameQueuePolicy: (['drop-oldest', 'drop-newest', 'coalesce'].includes(process.env.RDP_FRAME_QUEUE_POLICY || '')
+          ? process.env.RDP_FRAME_QUEUE_POLICY
+          : 'drop-oldest') as FrameQueuePolicy,
+        thumbnailInterval: parseInt(process.env.RDP_THUMBNAIL_INTERVAL || '500'), // 2 fps
+        thumbnailWidth: parseInt(process.env.RDP_THUMBNAIL_WIDTH || '320'),
+        thumbnailQuality: parseInt(process.env.RDP_THUMBNAIL_QUALITY || '60'),
+        audioBitrate: parseInt(process.env.RDP_AUDIO_BITRATE || '64000'),
+        resolutionCaps: {
+          low: {
+            maxWidth: parseInt(process.env.RDP_LOW_MAX_WIDTH || '1280'),
+            maxHeight: parseInt(process.env.RDP_LOW_MAX_HEIGHT || '720'),
+            scale: parseFloat(process.env.RDP_LOW_SCALE || '1')
+          },
+          medium: {
+            maxWidth: parseInt(process.env.RDP_MEDIUM_MAX_WIDTH || '1920'),
+            maxHeight: parseInt(process.env.RDP_MEDIUM_MAX_HEIGHT || '1080'),
+            scale: parseFloat(process.env.RDP_MEDIUM_SCALE || '1')
+          },
+          high: {
+            maxWidth: parseInt(process.env.RDP_HIGH_MAX_WIDTH || '2560'),
+            maxHeight: parseInt(process.env.RDP_HIGH_MAX_HEIGHT || '1440'),
+            scale: parseFloat(process.env.RDP_HIGH_SCALE || '1')
+          },
+          ultra: {
+            maxWidth: parseInt(process.env.RDP_ULTRA_MAX_WIDTH || '0'),
+            maxHeight: parseInt(process.env.RDP_ULTRA_MAX_HEIGHT || '0'),
+            scale: parseFloat(process.env.RDP_ULTRA_SCALE || '1')
+          },
+          // Scaling would defeat pixel-exact output
+          lossless: { maxWidth: 0, maxHeight: 0, scale: 1 }
+        }
       },
       websocket: {
         pingInterval: parseInt(process.env.WS_PING_INTERVAL || '25000'),
+        heartbeatMisses: parseInt(process.env.WS_HEARTBEAT_MISSES || '3'),
         pingTimeout: parseInt(process.env.WS_PING_TIMEOUT || '60000'),
         upgradeTimeout: parseInt(process.env.WS_UPGRADE_TIMEOUT || '10000'),
         maxHttpBufferSize: parseInt(process.env.WS_MAX_HTTP_BUFFER_SIZE || '1e6'),
         allowEIO3: process.env.WS_ALLOW_EIO3 === 'true',
+        rawPath: process.env.WS_RAW_PATH ?? '/ws', // Plain WebSocket endpoint; empty disables it
+        rawMaxPayload: parseInt(process.env.WS_RAW_MAX_PAYLOAD || '52428800'), // 50MB
+        signalingPath: process.env.WS_SIGNALING_PATH ?? '/webrtc-signaling',
+        relayUrl: process.env.WS_RELAY_URL || '',
+        relayToken: process.env.WS_RELAY_TOKEN || '',
+        relayName: process.env.WS_RELAY_NAME || '',
+        maxPendingFrames: parseInt(process.env.WS_MAX_PENDING_FRAMES || '4'),
+        frameBatchSize: parseInt(process.env.WS_FRAME_BATCH_SIZE || '16'),
+        frameBatchBytes: parseInt(process.env.WS_FRAME_BATCH_BYTES || '65536'),
+        frameBatchLatency: parseInt(process.env.WS_FRAME_BATCH_LATENCY || '4'), // ms
+        compression: process.env.WS_COMPRESSION !== 'false',
+        compressionThreshold: parseInt(process.env.WS_COMPRESSION_THRESHOLD || '1024'),
         cors: {
           origin: process.env.WS_CORS_ORIGIN ? process.env.WS_CORS_ORIGIN.split(',') : ['*'],
           methods: process.env.WS_CORS_METHODS ? process.env.WS_CORS_METHODS.split(',') : ['GET', 'POST'],
           credentials: process.env.WS_CORS_CREDENTIALS === 'true'
         }
       },
+      native: {
+        tcpPort: parseInt(process.env.NATIVE_TCP_PORT || '0'),
+        udpPort: parseInt(process.env.NATIVE_UDP_PORT || '0'),
+        maxPayload: parseInt(process.env.NATIVE_MAX_PAYLOAD || '52428800'), // 50MB
+        fecGroupSize: parseInt(process.env.NATIVE_FEC_GROUP_SIZE || '8'),
+        fecLossThreshold: parseFloat(process.env.NATIVE_FEC_LOSS_THRESHOLD || '0.01')
+      },
       fileTransfer: {
         maxFileSize: parseInt(process.env.FT_MAX_FILE_SIZE || '100000000'), // 100MB
         allowedTypes: process.env.FT_ALLOWED_TYPES ? process.env.FT_ALLOWED_TYPES.split(',') : ['*'],
@@ -181,7 +454,9 @@ export class Config {
         downloadDir: process.env.FT_DOWNLOAD_DIR || join(process.cwd(), 'downloads'),
         enableCompression: process.env.FT_ENABLE_COMPRESSION !== 'false',
         enableEncryption: process.env.FT_ENABLE_ENCRYPTION !== 'false',
-        maxConcurrentTransfers: parseInt(process.env.FT_MAX_CONCURRENT_TRANSFERS || '5')
+        maxConcurrentTransfers: parseInt(process.env.FT_MAX_CONCURRENT_TRANSFERS || '5'),
+        watchDir: process.env.FT_WATCH_DIR || '', // Disabled when empty
+        watchSettleDelay: parseInt(process.env.FT_WATCH_SETTLE_DELAY || '2000')
       },
       device: {
         enableUSB: process.env.DEVICE_ENABLE_USB !== 'false',
@@ -194,6 +469,15 @@ export class Config {
         enableStorage: process.env.DEVICE_ENABLE_STORAGE !== 'false',
         maxDevicesPerSession: parseInt(process.env.DEVICE_MAX_DEVICES_PER_SESSION || '10')
       },
+      input: {
+        maxMoveRate: parseInt(process.env.INPUT_MAX_MOVE_RATE || '250'),
+        maxWheelRate: parseInt(process.env.INPUT_MAX_WHEEL_RATE || '60'),
+        blockedHotkeys: process.env.INPUT_BLOCKED_HOTKEYS ? process.env.INPUT_BLOCKED_HOTKEYS.split(',') : [],
+        idleTimeout: parseInt(process.env.INPUT_IDLE_TIMEOUT || '0'),
+        idleAction: (['notify', 'lock', 'disconnect'].includes(process.env.INPUT_IDLE_ACTION || '')
+          ? process.env.INPUT_IDLE_ACTION
+          : 'lock') as InputConfig['idleAction']
+      },
       audit: {
         enabled: process.env.AUDIT_ENABLED !== 'false',
         logLevel: process.env.AUDIT_LOG_LEVEL || 'info',
@@ -207,6 +491,32 @@ export class Config {
         allowedHeaders: process.env.CORS_ALLOWED_HEADERS ? process.env.CORS_ALLOWED_HEADERS.split(',') : ['Content-Type', 'Authorization', 'X-Requested-With'],
         credentials: process.env.CORS_CREDENTIALS === 'true',
         maxAge: parseInt(process.env.CORS_MAX_AGE || '86400')
+      },
+      network: {
+        enableLinkDetection: process.env.NETWORK_ENABLE_LINK_DETECTION !== 'false',
+        wiredBitrateCeiling: parseInt(process.env.NETWORK_WIRED_BITRATE_CEILING || '20000000'), // 20 Mbps
+        wifiBitrateCeiling: parseInt(process.env.NETWORK_WIFI_BITRATE_CEILING || '4000000'), // 4 Mbps
+        cellularBitrateCeiling: parseInt(process.env.NETWORK_CELLULAR_BITRATE_CEILING || '2000000'), // 2 Mbps
+        defaultBitrateCeiling: parseInt(process.env.NETWORK_DEFAULT_BITRATE_CEILING || '6000000') // 6 Mbps
+      },
+      webrtc: {
+        iceServers: this.parseJsonEnv('WEBRTC_ICE_SERVERS', [
+          { urls: 'stun:stun.l.google.com:19302' },
+          { urls: 'stun:stun1.l.google.com:19302' }
+        ]),
+        turnSecret: process.env.WEBRTC_TURN_SECRET || '',
+        turnUrls: process.env.WEBRTC_TURN_URLS ? process.env.WEBRTC_TURN_URLS.split(',') : [],
+        turnCredentialTtl: parseInt(process.env.WEBRTC_TURN_CREDENTIAL_TTL || '86400') // seconds
+      },
+      security: {
+        allowedIps: process.env.SECURITY_ALLOWED_IPS ? process.env.SECURITY_ALLOWED_IPS.split(',') : [],
+        allowLoopback: process.env.SECURITY_ALLOW_LOOPBACK === 'true',
+        rateLimitRequests: parseInt(process.env.SECURITY_RATE_LIMIT_REQUESTS || '30'),
+        rateLimitWindow: parseInt(process.env.SECURITY_RATE_LIMIT_WINDOW || '60000') // ms
+      },
+      hooks: {
+        sessionSummaryUrl: process.env.HOOK_SESSION_SUMMARY_URL || '',
+        timeout: parseInt(process.env.HOOK_TIMEOUT || '5000')
       }
     };
 
@@ -224,6 +534,20 @@ export class Config {
     return config;
   }
 
+  // Falls back to the default when unset or malformed; the latter is
+  // reported by validate() rather than failing at load
+  private parseJsonEnv<T>(name: string, fallback: T): T {
+    const value = process.env[name];
+    if (!value) return fallback;
+
+    try {
+      return JSON.parse(value);
+    } catch (error) {
+      this.parseErrors.push(`${name} is not valid JSON: ${(error as Error).message}`);
+      return fallback;
+    }
+  }
+
   private mergeConfig(base: any, override: any): void {
     for (const key in override) {
       if (override.hasOwnProperty(key)) {
@@ -278,7 +602,7 @@ export class Config {
   }
 
   public validate(): string[] {
-    const errors: string[] = [];
+    const errors: string[] = [...this.parseErrors];
     
     // Validate required fields
     if (!this.config.auth.jwtSecret || this.config.auth.jwtSecret === 'your-secret-key-change-in-production') {
@@ -296,6 +620,27 @@ export class Config {
     if (this.config.fileTransfer.maxFileSize < 1) {
       errors.push('FT_MAX_FILE_SIZE must be at least 1');
     }
+
+    for (const hotkey of this.config.input.blockedHotkeys) {
+      if (!parseHotkey(hotkey)) {
+        errors.push(`INPUT_BLOCKED_HOTKEYS has an unknown key combination: ${hotkey}`);
+      }
+    }
+
+    const sniCerts = this.config.server.ssl.sniCerts;
+    if (typeof sniCerts !== 'object' || sniCerts === null || Array.isArray(sniCerts)) {
+      errors.push('SSL_SNI_CERTS must be a JSON object of hostname to { cert, key }');
+    }
+
+    for (const entry of this.config.security.allowedIps) {
+      if (!parseAllowedIp(entry)) {
+        errors.push(`SECURITY_ALLOWED_IPS has an invalid address or range: ${entry}`);
+      }
+    }
+
+    if (!Array.isArray(this.config.webrtc.iceServers)) {
+      errors.push('WEBRTC_ICE_SERVERS must be a JSON array');
+    }
     
     return errors;
   }
@@ -0,0 +1,44 @@
+import { networkInterfaces } from 'os';
+import { isIP } from 'net';
+
+export interface AllowedIp {
+  address: string;
+  // Absent for a single address
+  prefix?: number;
+  type: 'ipv4' | 'ipv6';
+}
+
+// Parses an allowlist entry, an address or CIDR range; null if malformed
+export function parseAllowedIp(entry: string): AllowedIp | null {
+  const [address, prefix, ...rest] = entry.trim().split('/');
+  const family = isIP(address);
+  if (family === 0 || rest.length > 0) return null;
+
+  const type = family === 6 ? 'ipv6' : 'ipv4';
+  if (prefix === undefined) return { address, type };
+
+  const bits = Number(prefix);
+  if (!/^\d+$/.test(prefix) || bits > (family === 6 ? 128 : 32)) return null;
+  return { address, prefix: bits, type };
+}
+
+// The address listeners should bind to: `host`, or the address of
+// `bindInterface` when one is configured (Node cannot bind to a device
+// directly). The interface's address family follows `host`'s, falling back
+// to the other; link-local IPv6 addresses are skipped.
+export function resolveBindAddress(host: string, bindInterface: string): string {
+  if (!bindInterface) return host;
+
+  const addresses = networkInterfaces()[bindInterface];
+  if (!addresses) {
+    throw new Error(`Network interface ${bindInterface} not found`);
+  }
+
+  const usable = addresses.filter(address => !(address.family === 'IPv6' && address.address.startsWith('fe80:')));
+  const preferred = host.includes(':') ? 'IPv6' : 'IPv4';
+  const match = usable.find(address => address.family === preferred) ?? usable[0];
+  if (!match) {
+    throw new Error(`Network interface ${bindInterface} has no usable address`);
+  }
+  return match.address;
+}
@@ -0,0 +1,110 @@
+import { BinaryProtocol, FrameReassembler, FRAGMENT_HEADER_SIZE, PARITY_HEADER_SIZE } from './BinaryProtocol';
+
+// Sized like TcpSocket's datagrams when FEC may be used
+const MAX_DATAGRAM = 1200 - (PARITY_HEADER_SIZE - FRAGMENT_HEADER_SIZE);
+const GROUP_SIZE = 4;
+
+function encodedFrame(frameId: number, size: number): Buffer {
+  const data = Buffer.alloc(size);
+  for (let i = 0; i < size; i++) {
+    data[i] = (i * 31 + frameId) & 0xff;
+  }
+  return BinaryProtocol.encodeFrame({
+    displayId: 0,
+    frameId,
+    codec: 'jpeg',
+    x: 0,
+    y: 0,
+    width: 1920,
+    height: 1080,
+    timestamp: 1700000000000,
+    data
+  });
+}
+
+// Feeds datagrams in order and returns what completed the frame, if anything
+function reassemble(reassembler: FrameReassembler, datagrams: Buffer[]): Buffer | null {
+  let result: Buffer | null = null;
+  for (const datagram of datagrams) {
+    result = reassembler.push(datagram) ?? result;
+  }
+  return result;
+}
+
+describe('FrameReassembler', () => {
+  it('reassembles a frame from its fragments', () => {
+    const encoded = encodedFrame(1, 10000);
+    const fragments = BinaryProtocol.fragmentFrame(1, encoded, MAX_DATAGRAM);
+
+    expect(fragments.length).toBeGreaterThan(1);
+    expect(reassemble(new FrameReassembler(), fragments)).toEqual(encoded);
+  });
+
+  it('rebuilds one lost fragment per group from parity', () => {
+    const encoded = encodedFrame(2, 10000);
+    const fragments = BinaryProtocol.fragmentFrame(2, encoded, MAX_DATAGRAM);
+    const parity = BinaryProtocol.encodeParity(2, fragments, GROUP_SIZE);
+
+    // Each pass loses the fragment at the same position in every group
+    for (let dropped = 0; dropped < GROUP_SIZE; dropped++) {
+      const received = fragments.filter((_, index) => index % GROUP_SIZE !== dropped);
+      expect(received.length).toBeLessThan(fragments.length);
+
+      expect(reassemble(new FrameReassembler(), [...received, ...parity])).toEqual(encoded);
+    }
+  });
+
+  it('rebuilds the short last fragment', () => {
+    const encoded = encodedFrame(3, 5000);
+    const fragments = BinaryProtocol.fragmentFrame(3, encoded, MAX_DATAGRAM);
+    const parity = BinaryProtocol.encodeParity(3, fragments, GROUP_SIZE);
+
+    expect(reassemble(new FrameReassembler(), [...fragments.slice(0, -1), ...parity])).toEqual(encoded);
+  });
+
+  it('uses parity that arrives before the fragments', () => {
+    const encoded = encodedFrame(4, 10000);
+    const fragments = BinaryProtocol.fragmentFrame(4, encoded, MAX_DATAGRAM);
+    const parity = BinaryProtocol.encodeParity(4, fragments, GROUP_SIZE);
+
+    expect(reassemble(new FrameReassembler(), [...parity, ...fragments.slice(1)])).toEqual(encoded);
+  });
+
+  it('gives up on a group that lost two fragments', () => {
+    const encoded = encodedFrame(5, 10000);
+    const fragments = BinaryProtocol.fragmentFrame(5, encoded, MAX_DATAGRAM);
+    const parity = BinaryProtocol.encodeParity(5, fragments, GROUP_SIZE);
+
+    expect(reassemble(new FrameReassembler(), [...fragments.slice(2), ...parity])).toBeNull();
+  });
+
+  it('ignores parity for a frame that already completed', () => {
+    const reassembler = new FrameReassembler(2);
+    const encoded = encodedFrame(9, 5000);
+    const fragments = BinaryProtocol.fragmentFrame(9, encoded, MAX_DATAGRAM);
+    const parity = BinaryProtocol.encodeParity(9, fragments, GROUP_SIZE);
+    const next = encodedFrame(10, 5000);
+    const nextFragments = BinaryProtocol.fragmentFrame(10, next, MAX_DATAGRAM);
+
+    // Fragments first, then parity, as TcpSocket sends them. Frame 10 is in
+    // flight meanwhile; a stale entry for frame 9 would take the second slot,
+    // and frame 11 starting would evict frame 10.
+    reassembler.push(nextFragments[0]);
+    expect(reassemble(reassembler, fragments)).toEqual(encoded);
+    expect(reassemble(reassembler, parity)).toBeNull();
+    reassembler.push(BinaryProtocol.fragmentFrame(11, encodedFrame(11, 5000), MAX_DATAGRAM)[0]);
+    expect(reassemble(reassembler, nextFragments.slice(1))).toEqual(next);
+  });
+
+  it('drops the oldest incomplete frame when too many are pending', () => {
+    const reassembler = new FrameReassembler(2);
+    const first = BinaryProtocol.fragmentFrame(6, encodedFrame(6, 5000), MAX_DATAGRAM);
+
+    reassembler.push(first[0]);
+    for (const frameId of [7, 8]) {
+      reassembler.push(BinaryProtocol.fragmentFrame(frameId, encodedFrame(frameId, 5000), MAX_DATAGRAM)[0]);
+    }
+    // Frame 6 was evicted, so the rest of it starts over and cannot complete
+    expect(reassemble(reassembler, first.slice(1))).toBeNull();
+  });
+});
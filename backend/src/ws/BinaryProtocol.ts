@@ -0,0 +1,451 @@
+// Binary wire format for the plain WebSocket endpoint, negotiated with the
+// `rdp.binary.v1` subprotocol, and for the native TCP/UDP transport. Every
+// message starts with a version byte and a type byte; integers are big-endian.
+// Frames carry their payload raw instead of base64 inside JSON. Mirrored by
+// frontend/services/rdp/BinaryProtocol.ts.
+
+export const PROTOCOL_VERSION = 1;
+export const BINARY_SUBPROTOCOL = 'rdp.binary.v1';
+export const JSON_SUBPROTOCOL = 'rdp.json.v1';
+
+export enum BinaryMessageType {
+  Frame = 0x01,
+  // One UDP datagram's share of an encoded Frame message
+  FrameFragment = 0x02,
+  // Several Frame messages behind an index of their lengths
+  FrameBatch = 0x03,
+  // XOR of a group of FrameFragment payloads, so any one of them can be rebuilt
+  FecParity = 0x04,
+  MouseInput = 0x10,
+  KeyboardInput = 0x11,
+  TouchInput = 0x12,
+  // UTF-8 text to type as-is, e.g. IME output or emoji
+  TextInput = 0x13,
+  // Pointer motion as deltas, for clients holding a pointer lock
+  RelativeMouseInput = 0x14,
+  // Every finger currently touching, for multi-touch gestures
+  TouchFrame = 0x15,
+  // Stylus position with pressure, tilt and buttons
+  PenInput = 0x16,
+  Clipboard = 0x20,
+  // Any other event as a UTF-8 `{ type, data }` JSON envelope; native
+  // transports have no text messages to carry it
+  Event = 0x30,
+  // Client to gateway over UDP: the token from `udp:offer`
+  UdpBind = 0x40
+}
+
+const POINTER_ACTIONS = ['move', 'down', 'up', 'wheel'];
+const TOUCH_ACTIONS = ['move', 'down', 'up', 'cancel'];
+const PEN_ACTIONS = ['move', 'down', 'up', 'hover', 'cancel'];
+// Pen buttons byte
+const PEN_BARREL = 0x01;
+const PEN_ERASER = 0x02;
+
+// version, type, displayId, codec name length, frameId u32, x, y i16, width, height u16, timestamp f64
+const FRAME_HEADER_SIZE = 24;
+const MOUSE_SIZE = 10;
+// version, type, deltaX i16, deltaY i16, button, action
+const RELATIVE_MOUSE_SIZE = 8;
+const KEYBOARD_HEADER_SIZE = 8;
+const TOUCH_SIZE = 10;
+// version, type, displayId (0xff: positions are desktop pixels), contact count
+const TOUCH_FRAME_HEADER_SIZE = 4;
+// id u32, action, x, y, pressure, width, height, orientation u16
+const TOUCH_CONTACT_SIZE = 17;
+const NO_DISPLAY = 0xff;
+// version, type, displayId, action, x, y, pressure u16, tiltX, tiltY i8,
+// rotation u16, buttons
+const PEN_SIZE = 15;
+// version, type, frameId u32, fragment index u16, fragment count u16
+export const FRAGMENT_HEADER_SIZE = 10;
+// version, type, frameId u32, first fragment index u16, fragments covered u16,
+// then the XOR of the fragment payload lengths as a u16
+export const PARITY_HEADER_SIZE = 12;
+// version, type, frame count u16, then a u32 length per frame
+const BATCH_HEADER_SIZE = 4;
+const BATCH_INDEX_ENTRY_SIZE = 4;
+
+export interface FrameMessage {
+  displayId: number;
+  frameId: number;
+  codec: string;
+  x: number;
+  y: number;
+  width?: number;
+  height?: number;
+  timestamp: number;
+  data: Buffer;
+}
+
+// A decoded client message, shaped like the equivalent JSON event
+export interface DecodedMessage {
+  type: string;
+  data: any;
+}
+
+export class BinaryProtocol {
+  public static encodeFrame(frame: FrameMessage): Buffer {
+    const codec = Buffer.from(frame.codec || '', 'utf8');
+    const header = Buffer.alloc(FRAME_HEADER_SIZE);
+
+    header.writeUInt8(PROTOCOL_VERSION, 0);
+    header.writeUInt8(BinaryMessageType.Frame, 1);
+    header.writeUInt8(frame.displayId || 0, 2);
+    header.writeUInt8(codec.length, 3);
+    header.writeUInt32BE(frame.frameId >>> 0, 4);
+    header.writeInt16BE(frame.x || 0, 8);
+    header.writeInt16BE(frame.y || 0, 10);
+    header.writeUInt16BE(frame.width || 0, 12);
+    header.writeUInt16BE(frame.height || 0, 14);
+    header.writeDoubleBE(frame.timestamp, 16);
+
+    return Buffer.concat([header, codec, frame.data]);
+  }
+
+  public static encodeFrameBatch(frames: FrameMessage[]): Buffer {
+    if (frames.length > 0xffff) {
+      throw new Error(`Batch of ${frames.length} frames is too large`);
+    }
+
+    const encoded = frames.map(frame => BinaryProtocol.encodeFrame(frame));
+    const header = Buffer.alloc(BATCH_HEADER_SIZE + encoded.length * BATCH_INDEX_ENTRY_SIZE);
+    header.writeUInt8(PROTOCOL_VERSION, 0);
+    header.writeUInt8(BinaryMessageType.FrameBatch, 1);
+    header.writeUInt16BE(encoded.length, 2);
+    encoded.forEach((message, index) => {
+      header.writeUInt32BE(message.length, BATCH_HEADER_SIZE + index * BATCH_INDEX_ENTRY_SIZE);
+    });
+
+    return Buffer.concat([header, ...encoded]);
+  }
+
+  public static encodeEvent(type: string, data: any): Buffer {
+    const json = Buffer.from(JSON.stringify({ type, data }, encodeBuffers), 'utf8');
+    const header = Buffer.from([PROTOCOL_VERSION, BinaryMessageType.Event]);
+    return Buffer.concat([header, json]);
+  }
+
+  // Splits an encoded frame into datagrams of at most `maxDatagram` bytes
+  public static fragmentFrame(frameId: number, encoded: Buffer, maxDatagram: number): Buffer[] {
+    const chunkSize = maxDatagram - FRAGMENT_HEADER_SIZE;
+    const count = Math.ceil(encoded.length / chunkSize);
+    if (count > 0xffff) {
+      throw new Error(`Frame of ${encoded.length} bytes is too large to fragment`);
+    }
+
+    const fragments: Buffer[] = [];
+    for (let index = 0; index < count; index++) {
+      const header = Buffer.alloc(FRAGMENT_HEADER_SIZE);
+      header.writeUInt8(PROTOCOL_VERSION, 0);
+      header.writeUInt8(BinaryMessageType.FrameFragment, 1);
+      header.writeUInt32BE(frameId >>> 0, 2);
+      header.writeUInt16BE(index, 6);
+      header.writeUInt16BE(count, 8);
+      fragments.push(Buffer.concat([header, encoded.subarray(index * chunkSize, (index + 1) * chunkSize)]));
+    }
+    return fragments;
+  }
+
+  // One parity datagram per `groupSize` fragments of a frame. Payloads are
+  // zero-padded to the longest in the group; the XOR of their lengths lets
+  // the client trim a rebuilt short (last) fragment.
+  public static encodeParity(frameId: number, fragments: Buffer[], groupSize: number): Buffer[] {
+    const parity: Buffer[] = [];
+    for (let first = 0; first < fragments.length; first += groupSize) {
+      const payloads = fragments.slice(first, first + groupSize).map(fragment => fragment.subarray(FRAGMENT_HEADER_SIZE));
+      const xor = Buffer.alloc(Math.max(...payloads.map(payload => payload.length)));
+      let lengths = 0;
+      for (const payload of payloads) {
+        lengths ^= payload.length;
+        for (let i = 0; i < payload.length; i++) {
+          xor[i] ^= payload[i];
+        }
+      }
+
+      const header = Buffer.alloc(PARITY_HEADER_SIZE);
+      header.writeUInt8(PROTOCOL_VERSION, 0);
+      header.writeUInt8(BinaryMessageType.FecParity, 1);
+      header.writeUInt32BE(frameId >>> 0, 2);
+      header.writeUInt16BE(first, 6);
+      header.writeUInt16BE(payloads.length, 8);
+      header.writeUInt16BE(lengths, 10);
+      parity.push(Buffer.concat([header, xor]));
+    }
+    return parity;
+  }
+
+  public static decode(message: Buffer): DecodedMessage {
+    if (message.length < 2) {
+      throw new Error('Binary message too short');
+    }
+
+    const version = message.readUInt8(0);
+    if (version !== PROTOCOL_VERSION) {
+      throw new Error(`Unsupported protocol version: ${version}`);
+    }
+
+    const type = message.readUInt8(1);
+    switch (type) {
+      case BinaryMessageType.MouseInput:
+        BinaryProtocol.requireLength(message, MOUSE_SIZE);
+        return {
+          type: 'input:mouse',
+          data: {
+            x: message.readUInt16BE(2),
+            y: message.readUInt16BE(4),
+            button: message.readUInt8(6),
+            action: POINTER_ACTIONS[message.readUInt8(7)] ?? 'move',
+            wheel: message.readInt16BE(8),
+            // Trailing display id: x and y are normalized within that display
+            displayId: message.length > MOUSE_SIZE ? message.readUInt8(MOUSE_SIZE) : undefined
+          }
+        };
+
+      case BinaryMessageType.RelativeMouseInput:
+        BinaryProtocol.requireLength(message, RELATIVE_MOUSE_SIZE);
+        return {
+          type: 'input:mouse',
+          data: {
+            deltaX: message.readInt16BE(2),
+            deltaY: message.readInt16BE(4),
+            button: message.readUInt8(6),
+            action: POINTER_ACTIONS[message.readUInt8(7)] ?? 'move'
+          }
+        };
+
+      case BinaryMessageType.KeyboardInput:
+        BinaryProtocol.requireLength(message, KEYBOARD_HEADER_SIZE);
+        return {
+          type: 'input:keyboard',
+          data: {
+            keyCode: message.readUInt16BE(2),
+            scanCode: message.readUInt16BE(4),
+            flags: message.readUInt16BE(6),
+            unicode: message.length > KEYBOARD_HEADER_SIZE ? message.toString('utf8', KEYBOARD_HEADER_SIZE) : undefined
+          }
+        };
+
+      case BinaryMessageType.TouchInput:
+        BinaryProtocol.requireLength(message, TOUCH_SIZE);
+        return {
+          type: 'input:touch',
+          data: {
+            x: message.readUInt16BE(2),
+            y: message.readUInt16BE(4),
+            pressure: message.readUInt8(6),
+            action: POINTER_ACTIONS[message.readUInt8(7)] ?? 'move'
+          }
+        };
+
+      case BinaryMessageType.TouchFrame: {
+        BinaryProtocol.requireLength(message, TOUCH_FRAME_HEADER_SIZE);
+        const displayId = message.readUInt8(2);
+        const count = message.readUInt8(3);
+        BinaryProtocol.requireLength(message, TOUCH_FRAME_HEADER_SIZE + count * TOUCH_CONTACT_SIZE);
+
+        const contacts = [];
+        for (let i = 0; i < count; i++) {
+          const offset = TOUCH_FRAME_HEADER_SIZE + i * TOUCH_CONTACT_SIZE;
+          contacts.push({
+            id: message.readUInt32BE(offset),
+            action: TOUCH_ACTIONS[message.readUInt8(offset + 4)] ?? 'move',
+            x: message.readUInt16BE(offset + 5),
+            y: message.readUInt16BE(offset + 7),
+            pressure: message.readUInt16BE(offset + 9),
+            width: message.readUInt16BE(offset + 11),
+            height: message.readUInt16BE(offset + 13),
+            orientation: message.readUInt16BE(offset + 15)
+          });
+        }
+        return {
+          type: 'input:touch',
+          data: { displayId: displayId === NO_DISPLAY ? undefined : displayId, contacts }
+        };
+      }
+
+      case BinaryMessageType.PenInput: {
+        BinaryProtocol.requireLength(message, PEN_SIZE);
+        const displayId = message.readUInt8(2);
+        const buttons = message.readUInt8(14);
+        return {
+          type: 'input:pen',
+          data: {
+            displayId: displayId === NO_DISPLAY ? undefined : displayId,
+            action: PEN_ACTIONS[message.readUInt8(3)] ?? 'move',
+            x: message.readUInt16BE(4),
+            y: message.readUInt16BE(6),
+            pressure: message.readUInt16BE(8),
+            tiltX: message.readInt8(10),
+            tiltY: message.readInt8(11),
+            rotation: message.readUInt16BE(12),
+            barrel: (buttons & PEN_BARREL) !== 0,
+            eraser: (buttons & PEN_ERASER) !== 0
+          }
+        };
+      }
+
+      case BinaryMessageType.TextInput:
+        return {
+          type: 'input:text',
+          data: { text: message.toString('utf8', 2) }
+        };
+
+      case BinaryMessageType.Clipboard:
+        return {
+          type: 'clipboard:set',
+          data: { text: message.toString('utf8', 2) }
+        };
+
+      case BinaryMessageType.Event: {
+        const event = JSON.parse(message.toString('utf8', 2));
+        if (!event || typeof event.type !== 'string') {
+          throw new Error('Invalid event message');
+        }
+        return { type: event.type, data: event.data ?? {} };
+      }
+
+      case BinaryMessageType.UdpBind:
+        return {
+          type: 'udp:bind',
+          data: { token: message.toString('hex', 2) }
+        };
+
+      default:
+        throw new Error(`Unknown binary message type: ${type}`);
+    }
+  }
+
+  private static requireLength(message: Buffer, length: number): void {
+    if (message.length < length) {
+      throw new Error(`Binary message type ${message.readUInt8(1)} truncated`);
+    }
+  }
+}
+
+interface ParityGroup {
+  first: number;
+  covered: number;
+  lengths: number;
+  xor: Buffer;
+}
+
+interface PendingFrame {
+  // Known once the first fragment arrives; parity alone does not carry it
+  count: number;
+  fragments: Map<number, Buffer>;
+  parity: ParityGroup[];
+}
+
+// The receiving end of fragmentFrame and encodeParity, for native clients on
+// the UDP video channel: collects a frame's datagrams and returns the encoded
+// frame once every fragment has arrived or been rebuilt. Any one fragment
+// lost from a parity group is rebuilt; frames still incomplete when
+// `maxPendingFrames` newer ones are in flight are given up on.
+export class FrameReassembler {
+  private frames: Map<number, PendingFrame> = new Map();
+  // Ids of the last frames completed, oldest first; their parity follows the
+  // fragments and still arrives after them, and is dropped
+  private completed: number[] = [];
+
+  constructor(private maxPendingFrames: number = 8) {}
+
+  public push(datagram: Buffer): Buffer | null {
+    if (datagram.length < 2 || datagram.readUInt8(0) !== PROTOCOL_VERSION) {
+      throw new Error('Not a frame datagram');
+    }
+
+    const type = datagram.readUInt8(1);
+    if (type === BinaryMessageType.FrameFragment && datagram.length >= FRAGMENT_HEADER_SIZE) {
+      const frame = this.pending(datagram.readUInt32BE(2));
+      if (!frame) return null;
+      const index = datagram.readUInt16BE(6);
+      frame.count = datagram.readUInt16BE(8);
+      if (index < frame.count) {
+        frame.fragments.set(index, datagram.subarray(FRAGMENT_HEADER_SIZE));
+      }
+      return this.complete(datagram.readUInt32BE(2), frame);
+    }
+
+    if (type === BinaryMessageType.FecParity && datagram.length >= PARITY_HEADER_SIZE) {
+      const frame = this.pending(datagram.readUInt32BE(2));
+      if (!frame) return null;
+      frame.parity.push({
+        first: datagram.readUInt16BE(6),
+        covered: datagram.readUInt16BE(8),
+        lengths: datagram.readUInt16BE(10),
+        xor: datagram.subarray(PARITY_HEADER_SIZE)
+      });
+      return this.complete(datagram.readUInt32BE(2), frame);
+    }
+
+    throw new Error(`Not a frame datagram: type ${type}`);
+  }
+
+  private pending(frameId: number): PendingFrame | null {
+    if (this.completed.includes(frameId)) return null;
+
+    let frame = this.frames.get(frameId);
+    if (!frame) {
+      frame = { count: 0, fragments: new Map(), parity: [] };
+      this.frames.set(frameId, frame);
+      // Map order puts the oldest frame first
+      while (this.frames.size > this.maxPendingFrames) {
+        this.frames.delete(this.frames.keys().next().value as number);
+      }
+    }
+    return frame;
+  }
+
+  private complete(frameId: number, frame: PendingFrame): Buffer | null {
+    if (frame.count === 0) return null;
+
+    for (const group of frame.parity) {
+      this.rebuild(frame, group);
+    }
+    if (frame.fragments.size < frame.count) return null;
+
+    this.frames.delete(frameId);
+    this.completed.push(frameId);
+    if (this.completed.length > this.maxPendingFrames) {
+      this.completed.shift();
+    }
+
+    const fragments: Buffer[] = [];
+    for (let index = 0; index < frame.count; index++) {
+      fragments.push(frame.fragments.get(index) as Buffer);
+    }
+    return Buffer.concat(fragments);
+  }
+
+  // XOR of the parity payload with every fragment that did arrive leaves the
+  // missing one, zero-padded; the XOR of the lengths gives its real length
+  private rebuild(frame: PendingFrame, group: ParityGroup): void {
+    const last = Math.min(group.first + group.covered, frame.count);
+    let missing = -1;
+    for (let index = group.first; index < last; index++) {
+      if (frame.fragments.has(index)) continue;
+      if (missing !== -1) return;
+      missing = index;
+    }
+    if (missing === -1) return;
+
+    const payload = Buffer.from(group.xor);
+    let length = group.lengths;
+    for (let index = group.first; index < last; index++) {
+      const fragment = frame.fragments.get(index);
+      if (!fragment) continue;
+      length ^= fragment.length;
+      for (let i = 0; i < fragment.length; i++) {
+        payload[i] ^= fragment[i];
+      }
+    }
+    frame.fragments.set(missing, payload.subarray(0, length));
+  }
+}
+
+// Buffers (e.g. frame payloads) travel as base64 in JSON messages
+export function encodeBuffers(this: any, key: string, value: any): any {
+  const original = this[key];
+  return Buffer.isBuffer(original) ? original.toString('base64') : value;
+}
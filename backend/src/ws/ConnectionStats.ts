@@ -0,0 +1,108 @@
+// Rates are measured over windows of this length
+const RATE_WINDOW = 1000;
+// Weight of a new RTT measurement in the smoothed value (as in TCP's SRTT)
+const RTT_SMOOTHING = 0.125;
+
+export interface ConnectionStatsSnapshot {
+  connectionId: string;
+  transport: string;
+  remoteAddress?: string;
+  connectedAt: number;
+  bytesIn: number;
+  bytesOut: number;
+  bytesInPerSec: number;
+  bytesOutPerSec: number;
+  // Smoothed round trip in ms; null until the transport has measured one
+  rtt: number | null;
+  // Frames sent again because the client lost them and asked for a refresh
+  retransmits: number;
+  // Messages waiting in the gateway's and the transport's queues
+  queueDepth: number;
+  // Frames dropped or replaced before reaching the client
+  drops: number;
+}
+
+// Counters for one client connection. Transports feed bytes and RTT; the
+// gateway adds its own frame queue, drops and refresh-driven retransmits.
+export class ConnectionStats {
+  public readonly connectedAt = Date.now();
+  private bytesIn = 0;
+  private bytesOut = 0;
+  private rtt: number | null = null;
+  private retransmits = 0;
+  private drops = 0;
+  private queues: Array<() => number> = [];
+  private windowStart = Date.now();
+  private windowIn = 0;
+  private windowOut = 0;
+  private rateIn = 0;
+  private rateOut = 0;
+
+  constructor(
+    public readonly connectionId: string,
+    public readonly transport: string,
+    public readonly remoteAddress?: string
+  ) {}
+
+  public recordIn(bytes: number): void {
+    this.roll();
+    this.bytesIn += bytes;
+    this.windowIn += bytes;
+  }
+
+  public recordOut(bytes: number): void {
+    this.roll();
+    this.bytesOut += bytes;
+    this.windowOut += bytes;
+  }
+
+  public recordRtt(rtt: number): void {
+    this.rtt = this.rtt === null ? rtt : this.rtt + RTT_SMOOTHING * (rtt - this.rtt);
+  }
+
+  public recordRetransmit(): void {
+    this.retransmits++;
+  }
+
+  public recordDrop(): void {
+    this.drops++;
+  }
+
+  // Counts toward queueDepth for as long as the connection lives
+  public addQueue(depth: () => number): void {
+    this.queues.push(depth);
+  }
+
+  public snapshot(): ConnectionStatsSnapshot {
+    this.roll();
+    return {
+      connectionId: this.connectionId,
+      transport: this.transport,
+      remoteAddress: this.remoteAddress,
+      connectedAt: this.connectedAt,
+      bytesIn: this.bytesIn,
+      bytesOut: this.bytesOut,
+      bytesInPerSec: this.rateIn,
+      bytesOutPerSec: this.rateOut,
+      rtt: this.rtt === null ? null : Math.round(this.rtt * 10) / 10,
+      retransmits: this.retransmits,
+      queueDepth: this.queues.reduce((sum, depth) => sum + depth(), 0),
+      drops: this.drops
+    };
+  }
+
+  // Closes the current rate window once it is over; a connection idle for
+  // more than a whole window reads as zero
+  private roll(): void {
+    const now = Date.now();
+    const elapsed = now - this.windowStart;
+    if (elapsed < RATE_WINDOW) return;
+
+    const idle = elapsed >= 2 * RATE_WINDOW;
+    this.rateIn = idle ? 0 : Math.round(this.windowIn * 1000 / elapsed);
+    this.rateOut = idle ? 0 : Math.round(this.windowOut * 1000 / elapsed);
+    this.windowStart = now;
+    this.windowIn = 0;
+    this.windowOut = 0;
+  }
+}
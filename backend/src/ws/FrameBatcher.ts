@@ -0,0 +1,72 @@
+export interface FrameBatcherOptions {
+  // Most updates carried by one message
+  maxFrames: number;
+  // Updates at least this large go out on their own
+  maxBytes: number;
+  // How long the first update of a batch may wait for company; 0 disables batching
+  latency: number;
+}
+
+// Per-connection outbound batching for the dirty-rect path. A burst of small
+// updates becomes one transport message instead of one per rect; anything
+// large, or a batch that fills up or runs out of latency budget, is flushed
+// right away. Updates always leave in the order they were added.
+export class FrameBatcher<T> {
+  private batch: T[] = [];
+  private batchBytes = 0;
+  private timer: NodeJS.Timeout | null = null;
+
+  constructor(
+    private options: FrameBatcherOptions,
+    private size: (frame: T) => number,
+    private send: (frames: T[]) => void
+  ) {}
+
+  public get depth(): number {
+    return this.batch.length;
+  }
+
+  public add(frame: T): void {
+    const bytes = this.size(frame);
+    if (this.options.latency <= 0 || this.options.maxFrames <= 1 || bytes >= this.options.maxBytes) {
+      this.flush();
+      this.send([frame]);
+      return;
+    }
+
+    if (this.batchBytes + bytes > this.options.maxBytes) {
+      this.flush();
+    }
+
+    this.batch.push(frame);
+    this.batchBytes += bytes;
+
+    if (this.batch.length >= this.options.maxFrames) {
+      this.flush();
+    } else if (!this.timer) {
+      this.timer = setTimeout(() => this.flush(), this.options.latency);
+    }
+  }
+
+  public flush(): void {
+    if (this.timer) {
+      clearTimeout(this.timer);
+      this.timer = null;
+    }
+    if (this.batch.length === 0) return;
+
+    const frames = this.batch;
+    this.batch = [];
+    this.batchBytes = 0;
+    this.send(frames);
+  }
+
+  public clear(): void {
+    if (this.timer) {
+      clearTimeout(this.timer);
+      this.timer = null;
+    }
+    this.batch = [];
+    this.batchBytes = 0;
+  }
+}
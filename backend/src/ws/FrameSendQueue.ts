@@ -0,0 +1,54 @@
+import { GatewaySocket } from './Transport';
+
+export type FrameOfferResult = 'sent' | 'deferred' | 'replaced';
+
+// Per-connection outbound video queue. While a client's transport still has
+// `maxPending` messages buffered, newer frames replace the unsent frame for the
+// same display instead of piling up, so a slow viewer holds at most one frame
+// per display and never delays the capture loop or other clients.
+export class FrameSendQueue<T> {
+  private pending: Map<number, T> = new Map();
+
+  constructor(
+    private socket: GatewaySocket,
+    private maxPending: number,
+    private send: (frame: T) => void
+  ) {}
+
+  public get depth(): number {
+    return this.pending.size;
+  }
+
+  public offer(displayId: number, frame: T): FrameOfferResult {
+    if (this.pending.size === 0 && this.isWritable()) {
+      this.send(frame);
+      return 'sent';
+    }
+
+    const replaced = this.pending.has(displayId);
+    // Re-inserting keeps displays in the order their latest frame arrived
+    this.pending.delete(displayId);
+    this.pending.set(displayId, frame);
+    return replaced ? 'replaced' : 'deferred';
+  }
+
+  // Called when the transport drains; sends what fits under the limit
+  public flush(): number {
+    let sent = 0;
+    for (const [displayId, frame] of this.pending) {
+      if (!this.isWritable()) break;
+      this.pending.delete(displayId);
+      this.send(frame);
+      sent++;
+    }
+    return sent;
+  }
+
+  public clear(): void {
+    this.pending.clear();
+  }
+
+  private isWritable(): boolean {
+    return this.socket.conn.writeBuffer.length < this.maxPending;
+  }
+}
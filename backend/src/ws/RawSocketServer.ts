@@ -0,0 +1,362 @@
+import { Server as HttpServer, IncomingMessage } from 'http';
+import { Duplex } from 'stream';
+import { EventEmitter } from 'events';
+import { WebSocketServer, WebSocket, RawData } from 'ws';
+import { v4 as uuidv4 } from 'uuid';
+import { Logger } from '../utils/Logger';
+import { ConnectionGuard } from '../services/ConnectionGuard';
+import { Transport, TransportStats, GatewaySocket, OutboundFramePayload, aggregateStats } from './Transport';
+import { ConnectionStats } from './ConnectionStats';
+import { BinaryProtocol, BINARY_SUBPROTOCOL, JSON_SUBPROTOCOL, encodeBuffers } from './BinaryProtocol';
+
+// Large messages that go through the bulk lane; everything else (input acks,
+// cursor, control) is written straight away so it never waits behind video
+const BULK_EVENTS = new Set(['frame', 'frames', 'thumbnail', 'file:data', 'device:data']);
+// Payloads the codecs already compressed; deflating them again only costs CPU
+const PRECOMPRESSED_EVENTS = new Set(['frame', 'frames', 'thumbnail']);
+
+export function shouldCompress(event: string): boolean {
+  return !PRECOMPRESSED_EVENTS.has(event);
+}
+
+// Bulk messages are held while this much is still buffered in the socket
+const BULK_HIGH_WATER_MARK = 256 * 1024;
+// Starvation protection: bulk messages older than this go out regardless
+const MAX_BULK_DELAY = 250;
+
+interface BulkMessage {
+  message: string | Buffer;
+  compress: boolean;
+  queuedAt: number;
+}
+
+// Speaks the `{ type, data }` JSON envelope used by the browser RDPService,
+// or the binary protocol for frames and input when that subprotocol was chosen
+export class RawSocket implements GatewaySocket {
+  public readonly id = `ws_${uuidv4()}`;
+  public data: any = {};
+  public readonly rooms: Set<string> = new Set([this.id]);
+  public readonly conn: GatewaySocket['conn'];
+  private handlers = new EventEmitter();
+  private connEvents = new EventEmitter();
+  private pendingSends = 0;
+  private bulkQueue: BulkMessage[] = [];
+  // Any message or pong from the client counts as a sign of life
+  private lastActivity = Date.now();
+  private missedHeartbeats = 0;
+  private closeReason: string | null = null;
+  private pingSentAt = 0;
+  public readonly binary: boolean;
+  public readonly stats: ConnectionStats;
+
+  constructor(private ws: WebSocket, public readonly remoteAddress: string) {
+    this.binary = ws.protocol === BINARY_SUBPROTOCOL;
+
+    const pending = () => this.pendingSends + this.bulkQueue.length;
+    this.stats = new ConnectionStats(this.id, 'websocket', remoteAddress);
+    this.stats.addQueue(pending);
+    this.conn = {
+      writeBuffer: {
+        get length() {
+          return pending();
+        }
+      },
+      on: (event, listener) => this.connEvents.on(event, listener)
+    };
+
+    ws.on('message', (raw: RawData, isBinary: boolean) => {
+      this.lastActivity = Date.now();
+      this.stats.recordIn(rawLength(raw));
+      if (isBinary) {
+        this.handleBinaryMessage(raw as Buffer);
+      } else {
+        this.handleMessage(raw);
+      }
+    });
+    ws.on('pong', () => {
+      this.lastActivity = Date.now();
+      if (this.pingSentAt) {
+        this.stats.recordRtt(this.lastActivity - this.pingSentAt);
+        this.pingSentAt = 0;
+      }
+    });
+    ws.on('close', (code: number) => {
+      this.bulkQueue = [];
+      this.handlers.emit('disconnect', this.closeReason ?? (code === 1000 ? 'client namespace disconnect' : 'transport close'));
+    });
+  }
+
+  public on(event: string, listener: (...args: any[]) => void): this {
+    this.handlers.on(event, listener);
+    return this;
+  }
+
+  public emit(event: string, data?: any): boolean {
+    if (this.ws.readyState !== WebSocket.OPEN) return false;
+
+    const message = this.binary ? this.encodeBinary(event, data) : null;
+    const encoded = message ?? JSON.stringify({ type: event, data }, encodeBuffers);
+
+    const compress = shouldCompress(event);
+    if (BULK_EVENTS.has(event)) {
+      this.bulkQueue.push({ message: encoded, compress, queuedAt: Date.now() });
+      this.pumpBulk();
+    } else {
+      this.send(encoded, compress);
+    }
+    return true;
+  }
+
+  // Frames with a raw payload go binary; everything else stays JSON
+  private encodeBinary(event: string, data: any): Buffer | null {
+    if (event === 'frame' && Buffer.isBuffer(data?.data)) {
+      return BinaryProtocol.encodeFrame(data);
+    }
+    if (event === 'frames' && Array.isArray(data?.frames)) {
+      return BinaryProtocol.encodeFrameBatch(data.frames);
+    }
+    return null;
+  }
+
+  private send(message: string | Buffer, compress: boolean): void {
+    this.stats.recordOut(typeof message === 'string' ? Buffer.byteLength(message) : message.length);
+    this.pendingSends++;
+    this.ws.send(message, { compress }, () => {
+      this.pendingSends--;
+      this.pumpBulk();
+      if (this.pendingSends === 0 && this.bulkQueue.length === 0) {
+        this.connEvents.emit('drain');
+      }
+    });
+  }
+
+  // Send callbacks keep this going as the socket buffer empties
+  private pumpBulk(): void {
+    while (this.bulkQueue.length > 0 && this.ws.readyState === WebSocket.OPEN) {
+      const starved = Date.now() - this.bulkQueue[0].queuedAt >= MAX_BULK_DELAY;
+      if (this.ws.bufferedAmount >= BULK_HIGH_WATER_MARK && !starved) {
+        // Nothing in flight would call us back, so check again shortly
+        if (this.pendingSends === 0) {
+          setTimeout(() => this.pumpBulk(), MAX_BULK_DELAY);
+        }
+        return;
+      }
+      const bulk = this.bulkQueue.shift()!;
+      this.send(bulk.message, bulk.compress);
+    }
+  }
+
+  public join(room: string): void {
+    this.rooms.add(room);
+  }
+
+  public leave(room: string): void {
+    this.rooms.delete(room);
+  }
+
+  // Returns false once the client has missed `maxMisses` heartbeats in a row
+  // and was terminated. Browsers cannot see WebSocket pings, so an
+  // application-level heartbeat goes out too for the client's own watchdog.
+  public heartbeat(interval: number, maxMisses: number): boolean {
+    if (Date.now() - this.lastActivity < interval) {
+      this.missedHeartbeats = 0;
+    } else if (++this.missedHeartbeats >= maxMisses) {
+      this.terminate('ping timeout');
+      return false;
+    }
+
+    this.pingSentAt = Date.now();
+    this.ws.ping();
+    this.emit('heartbeat', { timestamp: Date.now(), interval, misses: maxMisses });
+    return true;
+  }
+
+  // Disconnect reasons match socket.io's so handlers treat both alike
+  public terminate(reason: string = 'transport close'): void {
+    this.closeReason = reason;
+    this.ws.terminate();
+  }
+
+  public close(): void {
+    this.ws.close(1001, 'Server shutting down');
+  }
+
+  public disconnect(): this {
+    this.closeReason = 'server namespace disconnect';
+    this.ws.close(1000);
+    return this;
+  }
+
+  private handleMessage(raw: RawData): void {
+    let message: any;
+    try {
+      message = JSON.parse(raw.toString());
+    } catch (error) {
+      this.emit('error', { error: 'Invalid message' });
+      return;
+    }
+
+    // 'disconnect' is reserved for the transport
+    if (!message || typeof message.type !== 'string' || message.type === 'disconnect') {
+      this.emit('error', { error: 'Invalid message' });
+      return;
+    }
+
+    // A throwing handler must not take the whole gateway down
+    try {
+      this.handlers.emit(message.type, message.data ?? {});
+    } catch (error) {
+      this.emit('error', { error: (error as Error).message });
+    }
+  }
+
+  private handleBinaryMessage(raw: Buffer): void {
+    if (!this.binary) {
+      this.emit('error', { error: 'Binary messages require the ' + BINARY_SUBPROTOCOL + ' subprotocol' });
+      return;
+    }
+
+    try {
+      const message = BinaryProtocol.decode(raw);
+      if (message.type === 'disconnect') {
+        throw new Error('Invalid message');
+      }
+      this.handlers.emit(message.type, message.data);
+    } catch (error) {
+      this.emit('error', { error: (error as Error).message });
+    }
+  }
+}
+
+function rawLength(raw: RawData): number {
+  if (Array.isArray(raw)) {
+    return raw.reduce((sum, chunk) => sum + chunk.length, 0);
+  }
+  return raw instanceof ArrayBuffer ? raw.byteLength : raw.length;
+}
+
+function selectProtocol(protocols: Set<string>): string | false {
+  if (protocols.has(BINARY_SUBPROTOCOL)) return BINARY_SUBPROTOCOL;
+  if (protocols.has(JSON_SUBPROTOCOL)) return JSON_SUBPROTOCOL;
+  return false;
+}
+
+// Plain WebSocket listener next to socket.io, for clients that do not use
+// the socket.io protocol
+export class RawSocketServer extends EventEmitter implements Transport {
+  public readonly name = 'websocket';
+  private logger = new Logger('RawSocketServer');
+  private wss: WebSocketServer;
+  private sockets: Map<string, RawSocket> = new Map();
+  private heartbeat: NodeJS.Timeout | null = null;
+
+  constructor(
+    private path: string,
+    private pingInterval: number,
+    private heartbeatMisses: number,
+    maxPayload: number,
+    compressionThreshold: number | false
+  ) {
+    super();
+    this.wss = new WebSocketServer({
+      noServer: true,
+      maxPayload,
+      handleProtocols: selectProtocol,
+      verifyClient: ({ req }: { req: IncomingMessage }) =>
+        ConnectionGuard.getInstance().admitHandshake(req.socket.remoteAddress, 'websocket'),
+      perMessageDeflate: compressionThreshold === false ? false : { threshold: compressionThreshold }
+    });
+  }
+
+  // Without a path only adopted connections (e.g. from a relay) arrive
+  public start(server: HttpServer): void {
+    if (this.path) {
+      this.attach(server);
+    }
+  }
+
+  public attach(server: HttpServer): void {
+    // socket.io has its own upgrade listener; only claim our path
+    server.on('upgrade', (req: IncomingMessage, socket: Duplex, head: Buffer) => {
+      const pathname = new URL(req.url || '/', 'http://localhost').pathname;
+      if (pathname !== this.path) return;
+
+      this.wss.handleUpgrade(req, socket, head, (ws) => {
+        this.adopt(ws, req.socket.remoteAddress || 'unknown');
+      });
+    });
+
+    this.logger.info(`Raw WebSocket endpoint listening on ${this.path}`);
+  }
+
+  // Also used for connections that did not arrive on our own listener, e.g.
+  // ones tunnelled through a relay
+  public adopt(ws: WebSocket, remoteAddress: string): RawSocket {
+    ws.on('error', (error) => this.logger.warn('WebSocket error:', error));
+    const rawSocket = new RawSocket(ws, remoteAddress);
+    this.sockets.set(rawSocket.id, rawSocket);
+    rawSocket.on('disconnect', () => this.sockets.delete(rawSocket.id));
+    this.startHeartbeat();
+    this.emit('connection', rawSocket);
+    return rawSocket;
+  }
+
+  private startHeartbeat(): void {
+    if (this.heartbeat) return;
+
+    this.heartbeat = setInterval(() => {
+      for (const socket of this.sockets.values()) {
+        if (!socket.heartbeat(this.pingInterval, this.heartbeatMisses)) {
+          this.logger.warn(`Closing ${socket.id} (${socket.remoteAddress}) after ${this.heartbeatMisses} missed heartbeats`);
+        }
+      }
+    }, this.pingInterval);
+  }
+
+  public get(socketId: string): RawSocket | undefined {
+    return this.sockets.get(socketId);
+  }
+
+  // Socket ids are rooms too, matching socket.io
+  public to(room: string, event: string, data?: any): void {
+    for (const socket of this.sockets.values()) {
+      if (socket.rooms.has(room)) {
+        socket.emit(event, data);
+      }
+    }
+  }
+
+  public broadcast(event: string, data?: any): void {
+    for (const socket of this.sockets.values()) {
+      socket.emit(event, data);
+    }
+  }
+
+  // Sent as a binary message to clients that negotiated the binary protocol
+  public emitFrame(socket: GatewaySocket, message: any, data: Buffer): void {
+    socket.emit('frame', { ...message, data });
+  }
+
+  public emitFrameBatch(socket: GatewaySocket, frames: OutboundFramePayload[]): void {
+    socket.emit('frames', { frames: frames.map(frame => ({ ...frame.message, data: frame.data })) });
+  }
+
+  public stats(): TransportStats {
+    return aggregateStats(this.name, Array.from(this.sockets.values(), socket => socket.stats));
+  }
+
+  public connectionStats(socketId: string): ConnectionStats | undefined {
+    return this.sockets.get(socketId)?.stats;
+  }
+
+  public close(): void {
+    if (this.heartbeat) {
+      clearInterval(this.heartbeat);
+      this.heartbeat = null;
+    }
+    for (const socket of this.sockets.values()) {
+      socket.close();
+    }
+    this.wss.close();
+  }
+}
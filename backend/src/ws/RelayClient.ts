@@ -0,0 +1,174 @@
+import { EventEmitter } from 'events';
+import { hostname } from 'os';
+import { WebSocket, RawData } from 'ws';
+import { Logger } from '../utils/Logger';
+import { ConnectionGuard } from '../services/ConnectionGuard';
+import { BINARY_SUBPROTOCOL, JSON_SUBPROTOCOL } from './BinaryProtocol';
+
+// Reconnect backoff for the control connection
+const MIN_RECONNECT_DELAY = 1000;
+const MAX_RECONNECT_DELAY = 60000;
+// Relay has this long to answer a data connection before it is abandoned
+const CHANNEL_OPEN_TIMEOUT = 10000;
+
+export interface RelayClientOptions {
+  url: string;
+  token: string;
+  name: string;
+  pingInterval: number;
+}
+
+// Outbound mode for gateways behind NAT: a control connection to the relay
+// stays open, and for every client the relay accepts it asks for a data
+// connection, which the gateway dials back and treats as a plain WebSocket
+// client. Clients' TLS ends at the relay, not here: it sees every message in
+// plaintext, credentials and session tokens included, so it must be trusted
+// like the gateway itself. Only the gateway's own auth still applies here.
+export class RelayClient extends EventEmitter {
+  private logger = new Logger('RelayClient');
+  private control: WebSocket | null = null;
+  private channels: Set<WebSocket> = new Set();
+  private reconnectDelay = MIN_RECONNECT_DELAY;
+  private reconnectTimer: NodeJS.Timeout | null = null;
+  private heartbeat: NodeJS.Timeout | null = null;
+  private closed = false;
+
+  constructor(private options: RelayClientOptions) {
+    super();
+  }
+
+  public start(): void {
+    this.closed = false;
+    this.connect();
+  }
+
+  public close(): void {
+    this.closed = true;
+    if (this.reconnectTimer) {
+      clearTimeout(this.reconnectTimer);
+      this.reconnectTimer = null;
+    }
+    this.stopHeartbeat();
+    this.control?.close(1001, 'Gateway shutting down');
+    this.control = null;
+    for (const channel of this.channels) {
+      channel.close(1001, 'Gateway shutting down');
+    }
+    this.channels.clear();
+  }
+
+  private connect(): void {
+    const url = new URL(this.options.url);
+    url.searchParams.set('role', 'agent');
+    url.searchParams.set('name', this.options.name || hostname());
+
+    const control = new WebSocket(url, { headers: this.authHeaders() });
+    this.control = control;
+    let alive = true;
+
+    control.on('open', () => {
+      this.reconnectDelay = MIN_RECONNECT_DELAY;
+      this.logger.info(`Registered with relay ${this.options.url}`);
+      this.emit('registered');
+
+      this.heartbeat = setInterval(() => {
+        if (!alive) {
+          control.terminate();
+          return;
+        }
+        alive = false;
+        control.ping();
+      }, this.options.pingInterval);
+    });
+    control.on('pong', () => {
+      alive = true;
+    });
+    control.on('message', (raw: RawData) => this.handleControlMessage(raw));
+    control.on('error', (error) => this.logger.warn('Relay connection error:', error));
+    control.on('close', (code: number) => {
+      this.stopHeartbeat();
+      if (this.control === control) {
+        this.control = null;
+      }
+      if (this.closed) return;
+
+      this.logger.warn(`Relay connection closed (${code}), reconnecting in ${this.reconnectDelay}ms`);
+      this.emit('unregistered');
+      this.reconnectTimer = setTimeout(() => {
+        this.reconnectTimer = null;
+        this.connect();
+      }, this.reconnectDelay);
+      this.reconnectDelay = Math.min(this.reconnectDelay * 2, MAX_RECONNECT_DELAY);
+    });
+  }
+
+  private handleControlMessage(raw: RawData): void {
+    let message: any;
+    try {
+      message = JSON.parse(raw.toString());
+    } catch (error) {
+      this.logger.warn('Invalid message from relay');
+      return;
+    }
+
+    switch (message?.type) {
+      case 'connect':
+        if (typeof message.channel !== 'string' || !message.channel) {
+          this.logger.warn('Relay connect request without a channel');
+          return;
+        }
+        // The relay reports the client's address; the same rules apply as for direct clients
+        if (!ConnectionGuard.getInstance().admitHandshake(message.remoteAddress, 'relay')) {
+          this.send({ type: 'reject', channel: message.channel });
+          return;
+        }
+        this.openChannel(message.channel, message.remoteAddress || 'relay');
+        break;
+      case 'error':
+        this.logger.error(`Relay error: ${message.error}`);
+        break;
+      default:
+        this.logger.debug(`Ignoring relay message: ${message?.type}`);
+    }
+  }
+
+  private openChannel(channelId: string, remoteAddress: string): void {
+    const url = new URL(this.options.url);
+    url.searchParams.set('channel', channelId);
+
+    // The relay answers with whichever subprotocol the client picked
+    const channel = new WebSocket(url, [BINARY_SUBPROTOCOL, JSON_SUBPROTOCOL], {
+      headers: this.authHeaders(),
+      handshakeTimeout: CHANNEL_OPEN_TIMEOUT
+    });
+    this.channels.add(channel);
+
+    channel.on('close', () => this.channels.delete(channel));
+    channel.once('open', () => {
+      this.logger.info(`Relayed client connected from ${remoteAddress} (channel ${channelId})`);
+      this.emit('connection', channel, remoteAddress);
+    });
+    channel.once('error', (error) => {
+      if (channel.readyState !== WebSocket.OPEN) {
+        this.logger.warn(`Failed to open relay channel ${channelId}:`, error);
+      }
+    });
+  }
+
+  private send(message: any): void {
+    if (this.control?.readyState === WebSocket.OPEN) {
+      this.control.send(JSON.stringify(message));
+    }
+  }
+
+  private authHeaders(): Record<string, string> {
+    return this.options.token ? { Authorization: `Bearer ${this.options.token}` } : {};
+  }
+
+  private stopHeartbeat(): void {
+    if (this.heartbeat) {
+      clearInterval(this.heartbeat);
+      this.heartbeat = null;
+    }
+  }
+}
@@ -0,0 +1,258 @@
+import { Server as HttpServer, IncomingMessage } from 'http';
+import { Duplex } from 'stream';
+import { WebSocketServer, WebSocket, RawData } from 'ws';
+import { v4 as uuidv4 } from 'uuid';
+import { Logger } from '../utils/Logger';
+import { AuthManager } from '../services/AuthManager';
+import { ConnectionGuard } from '../services/ConnectionGuard';
+import { IceServerManager, IceServersUpdate } from '../services/IceServerManager';
+import { RDPSessionManager } from '../core/RDPSessionManager';
+
+// Messages relayed between peers untouched
+const RELAYED_TYPES = new Set(['offer', 'answer', 'ice-candidate', 'hangup']);
+// Peers must authenticate within this long after connecting
+const AUTH_TIMEOUT = 10000;
+// The browser and the peer it negotiates with; a peer connection has one remote end
+const MAX_PEERS_PER_ROOM = 2;
+// Session descriptions are a few KB; nothing legitimate comes close
+const MAX_MESSAGE_SIZE = 256 * 1024;
+// A dropped peer's id stays reserved this long, and messages for it are held
+const RESUME_GRACE = 30000;
+const MAX_HELD_MESSAGES = 64;
+
+interface SignalingPeer {
+  id: string;
+  ws: WebSocket;
+  room: string;
+  username?: string;
+  authenticated: boolean;
+}
+
+interface DepartedPeer {
+  room: string;
+  username?: string;
+  held: any[];
+  timer: NodeJS.Timeout;
+}
+
+// Embedded WebRTC signaling: peers in the same room exchange SDP and ICE
+// candidates through the gateway, so no external signaling service is needed.
+// A room is an RDP session id, open to the same users as `session:join`.
+export class SignalingServer {
+  private logger = new Logger('SignalingServer');
+  private wss: WebSocketServer;
+  private peers: Map<string, SignalingPeer> = new Map();
+  private rooms: Map<string, Set<string>> = new Map();
+  private departed: Map<string, DepartedPeer> = new Map();
+
+  constructor(
+    private path: string,
+    private authManager: AuthManager,
+    private iceServerManager: IceServerManager,
+    private rdpSessionManager: RDPSessionManager
+  ) {
+    this.wss = new WebSocketServer({
+      noServer: true,
+      maxPayload: MAX_MESSAGE_SIZE,
+      verifyClient: ({ req }: { req: IncomingMessage }) =>
+        ConnectionGuard.getInstance().admitHandshake(req.socket.remoteAddress, 'signaling')
+    });
+    this.iceServerManager.on('iceServersUpdated', (update: IceServersUpdate) => this.broadcastIceServers(update));
+  }
+
+  public attach(server: HttpServer): void {
+    server.on('upgrade', (req: IncomingMessage, socket: Duplex, head: Buffer) => {
+      const url = new URL(req.url || '/', 'http://localhost');
+      if (url.pathname !== this.path) return;
+
+      this.wss.handleUpgrade(req, socket, head, (ws) => {
+        this.handleConnection(ws, url.searchParams.get('room') || '');
+      });
+    });
+
+    this.logger.info(`WebRTC signaling endpoint listening on ${this.path}`);
+  }
+
+  public close(): void {
+    for (const peer of this.peers.values()) {
+      peer.ws.close(1001, 'Server shutting down');
+    }
+    for (const departed of this.departed.values()) {
+      clearTimeout(departed.timer);
+    }
+    this.departed.clear();
+    this.wss.close();
+  }
+
+  private handleConnection(ws: WebSocket, room: string): void {
+    const peer: SignalingPeer = { id: `peer_${uuidv4()}`, ws, room, authenticated: false };
+    this.peers.set(peer.id, peer);
+
+    const authTimer = setTimeout(() => {
+      if (!peer.authenticated) {
+        ws.close(4001, 'Authentication timeout');
+      }
+    }, AUTH_TIMEOUT);
+
+    ws.on('message', (raw: RawData) => {
+      this.handleMessage(peer, raw).catch((error) => {
+        this.logger.error(`Signaling error for ${peer.id}:`, error);
+      });
+    });
+    ws.on('close', () => {
+      clearTimeout(authTimer);
+      this.leave(peer);
+    });
+    ws.on('error', (error) => this.logger.warn(`Signaling socket error for ${peer.id}:`, error));
+  }
+
+  private async handleMessage(peer: SignalingPeer, raw: RawData): Promise<void> {
+    let message: any;
+    try {
+      message = JSON.parse(raw.toString());
+    } catch (error) {
+      this.send(peer, { type: 'error', error: 'Invalid message' });
+      return;
+    }
+
+    if (!peer.authenticated) {
+      if (message?.type !== 'auth') {
+        this.send(peer, { type: 'error', error: 'Not authenticated' });
+        return;
+      }
+      await this.authenticate(peer, message);
+      return;
+    }
+
+    if (!RELAYED_TYPES.has(message?.type)) {
+      this.send(peer, { type: 'error', error: `Unsupported message type: ${message?.type}` });
+      return;
+    }
+
+    // Optionally addressed to one peer, otherwise to everyone else in the room;
+    // peers that dropped and may still resume get theirs when they return
+    const targets = message.to
+      ? [message.to]
+      : [...(this.rooms.get(peer.room) || []), ...this.departedIn(peer.room)];
+    const relayed = { ...message, from: peer.id, to: undefined };
+    for (const targetId of targets) {
+      const departed = this.departed.get(targetId);
+      if (departed && departed.room === peer.room) {
+        if (departed.held.length < MAX_HELD_MESSAGES) {
+          departed.held.push(relayed);
+        }
+        continue;
+      }
+
+      const target = this.peers.get(targetId);
+      if (!target || target.id === peer.id || target.room !== peer.room) continue;
+      this.send(target, relayed);
+    }
+  }
+
+  private async authenticate(peer: SignalingPeer, message: any): Promise<void> {
+    const result = await this.authManager.authenticateSocket({ id: peer.id }, { token: message.token });
+    if (!result.success) {
+      this.send(peer, { type: 'error', error: result.error || 'Authentication failed' });
+      peer.ws.close(4003, 'Authentication failed');
+      return;
+    }
+
+    const session = this.rdpSessionManager.getSession(peer.room);
+    const user = result.user;
+    if (!session || session.status === 'disconnected' || !user ||
+        (user.role !== 'admin' && session.owner !== user.username)) {
+      this.send(peer, { type: 'error', error: 'Session not found' });
+      peer.ws.close(4004, 'Session not found');
+      return;
+    }
+
+    // Slots held for dropped peers count, except the one this peer resumes
+    const members = this.rooms.get(peer.room) || new Set<string>();
+    const held = this.departedIn(peer.room).filter(id => id !== message.peerId);
+    if (members.size + held.length >= MAX_PEERS_PER_ROOM) {
+      this.send(peer, { type: 'error', error: 'Room is full' });
+      peer.ws.close(4009, 'Room is full');
+      return;
+    }
+
+    peer.authenticated = true;
+    peer.username = result.user?.username;
+    const queued = this.resume(peer, message.peerId);
+    members.add(peer.id);
+    this.rooms.set(peer.room, members);
+
+    const others = Array.from(members).filter(id => id !== peer.id);
+    this.send(peer, { type: 'joined', peerId: peer.id, room: peer.room, peers: others, resumed: queued !== null });
+    this.send(peer, { type: 'ice-servers', ...this.iceServerManager.getIceServers(peer.username), restartIce: false });
+
+    if (queued) {
+      queued.forEach(relayed => this.send(peer, relayed));
+      this.logger.info(`Peer ${peer.id} (${peer.username || 'anonymous'}) resumed in signaling room ${peer.room}`);
+      return;
+    }
+
+    // Existing peers re-offer so a late joiner still gets a session description
+    for (const id of others) {
+      this.send(this.peers.get(id), { type: 'peer-joined', peerId: peer.id });
+    }
+
+    this.logger.info(`Peer ${peer.id} (${peer.username || 'anonymous'}) joined signaling room ${peer.room}`);
+  }
+
+  // A peer whose signaling socket dropped rejoins under its old id, so the
+  // remote side keeps one negotiation going; returns the messages held for
+  // it, or null if it joins as a new peer
+  private resume(peer: SignalingPeer, previousId: unknown): any[] | null {
+    const departed = typeof previousId === 'string' ? this.departed.get(previousId) : undefined;
+    if (!departed || departed.room !== peer.room || departed.username !== peer.username) return null;
+
+    clearTimeout(departed.timer);
+    this.departed.delete(previousId as string);
+    this.peers.delete(peer.id);
+    peer.id = previousId as string;
+    this.peers.set(peer.id, peer);
+    return departed.held;
+  }
+
+  private departedIn(room: string): string[] {
+    return Array.from(this.departed.entries())
+      .filter(([, departed]) => departed.room === room)
+      .map(([id]) => id);
+  }
+
+  private leave(peer: SignalingPeer): void {
+    this.peers.delete(peer.id);
+
+    const members = this.rooms.get(peer.room);
+    if (!members || !members.delete(peer.id)) return;
+
+    if (members.size === 0) {
+      this.rooms.delete(peer.room);
+    }
+
+    // The room hears it left only once it can no longer resume
+    const timer = setTimeout(() => {
+      this.departed.delete(peer.id);
+      for (const id of this.rooms.get(peer.room) || []) {
+        this.send(this.peers.get(id), { type: 'peer-left', peerId: peer.id });
+      }
+    }, RESUME_GRACE);
+    timer.unref();
+    this.departed.set(peer.id, { room: peer.room, username: peer.username, held: [], timer });
+  }
+
+  private broadcastIceServers(update: IceServersUpdate): void {
+    for (const peer of this.peers.values()) {
+      if (peer.authenticated) {
+        this.send(peer, { type: 'ice-servers', ...this.iceServerManager.getIceServers(peer.username), restartIce: update.restartIce });
+      }
+    }
+  }
+
+  private send(peer: SignalingPeer | undefined, message: any): void {
+    if (peer && peer.ws.readyState === WebSocket.OPEN) {
+      peer.ws.send(JSON.stringify(message));
+    }
+  }
+}
@@ -0,0 +1,84 @@
+import { Server as HttpServer } from 'http';
+import { EventEmitter } from 'events';
+import { Server as SocketIOServer, Socket } from 'socket.io';
+import { Transport, TransportStats, GatewaySocket, OutboundFramePayload, aggregateStats } from './Transport';
+import { ConnectionStats } from './ConnectionStats';
+import { shouldCompress } from './RawSocketServer';
+
+// socket.io already owns its listener; this adapts it to the Transport interface
+export class SocketIOTransport extends EventEmitter implements Transport {
+  public readonly name = 'socket.io';
+  private connections: Map<string, ConnectionStats> = new Map();
+
+  constructor(private io: SocketIOServer) {
+    super();
+  }
+
+  public start(server: HttpServer): void {
+    this.io.on('connection', (socket: Socket) => {
+      this.trackStats(socket);
+      this.emit('connection', socket);
+    });
+  }
+
+  public to(room: string, event: string, data?: any): void {
+    this.io.to(room).compress(shouldCompress(event)).emit(event, data);
+  }
+
+  public broadcast(event: string, data?: any): void {
+    this.io.emit(event, data);
+  }
+
+  // No binary framing here, so the payload travels as base64
+  public emitFrame(socket: GatewaySocket, message: any, data: Buffer): void {
+    (socket as Socket).compress(false).emit('frame', { ...message, data: data.toString('base64') });
+  }
+
+  public emitFrameBatch(socket: GatewaySocket, frames: OutboundFramePayload[]): void {
+    (socket as Socket).compress(false).emit('frames', {
+      frames: frames.map(frame => ({ ...frame.message, data: frame.data.toString('base64') }))
+    });
+  }
+
+  public close(): void {
+    this.io.close();
+  }
+
+  public stats(): TransportStats {
+    return aggregateStats(this.name, this.connections.values());
+  }
+
+  public connectionStats(socketId: string): ConnectionStats | undefined {
+    return this.connections.get(socketId);
+  }
+
+  // Engine.io reports every packet it reads or writes; its own ping/pong
+  // heartbeat gives the round trip
+  private trackStats(socket: Socket): void {
+    const stats = new ConnectionStats(socket.id, this.name, socket.handshake.address);
+    stats.addQueue(() => socket.conn.writeBuffer.length);
+    this.connections.set(socket.id, stats);
+
+    let pingSentAt = 0;
+    socket.conn.on('packet', (packet: { type: string; data?: any }) => {
+      stats.recordIn(packetSize(packet.data));
+      if (packet.type === 'pong' && pingSentAt) {
+        stats.recordRtt(Date.now() - pingSentAt);
+        pingSentAt = 0;
+      }
+    });
+    socket.conn.on('packetCreate', (packet: { type: string; data?: any }) => {
+      stats.recordOut(packetSize(packet.data));
+      if (packet.type === 'ping') {
+        pingSentAt = Date.now();
+      }
+    });
+    socket.on('disconnect', () => this.connections.delete(socket.id));
+  }
+}
+
+function packetSize(data: any): number {
+  if (typeof data === 'string') return Buffer.byteLength(data);
+  if (Buffer.isBuffer(data)) return data.length;
+  return data instanceof ArrayBuffer ? data.byteLength : 0;
+}
@@ -0,0 +1,426 @@
+import { Server as HttpServer } from 'http';
+import { createServer as createNetServer, Server as NetServer, Socket as NetSocket } from 'net';
+import { createServer as createTlsServer, TlsOptions } from 'tls';
+import { createSocket, Socket as UdpSocket, RemoteInfo } from 'dgram';
+import { EventEmitter } from 'events';
+import * as crypto from 'crypto';
+import { v4 as uuidv4 } from 'uuid';
+import { Logger } from '../utils/Logger';
+import { SocketConfig } from '../utils/Config';
+import { resolveBindAddress } from '../utils/Network';
+import { ConnectionGuard } from '../services/ConnectionGuard';
+import { Transport, TransportStats, GatewaySocket, OutboundFramePayload, aggregateStats } from './Transport';
+import { ConnectionStats } from './ConnectionStats';
+import { BinaryProtocol, FRAGMENT_HEADER_SIZE, PARITY_HEADER_SIZE } from './BinaryProtocol';
+
+// Every message on the stream is prefixed with its length as a u32
+const LENGTH_PREFIX_SIZE = 4;
+// Fits a typical path MTU after IP and UDP headers, so datagrams are not fragmented
+const MAX_DATAGRAM_SIZE = 1200;
+const UDP_TOKEN_SIZE = 16;
+
+export interface FecOptions {
+  // 0 disables FEC
+  groupSize: number;
+  lossThreshold: number;
+}
+
+export interface TcpTransportOptions {
+  host: string;
+  socket: SocketConfig;
+  tcpPort: number;
+  // 0 leaves video on the TCP stream
+  udpPort: number;
+  maxPayload: number;
+  fecGroupSize: number;
+  fecLossThreshold: number;
+  pingInterval: number;
+  heartbeatMisses: number;
+  tls?: TlsOptions;
+}
+
+// A native client on the length-prefixed TCP stream. Frames and input use the
+// binary protocol; every other event travels as a binary Event envelope, so
+// the gateway's socket handlers work unchanged.
+export class TcpSocket implements GatewaySocket {
+  public readonly id = `tcp_${uuidv4()}`;
+  public data: any = {};
+  public readonly rooms: Set<string> = new Set([this.id]);
+  public readonly conn: GatewaySocket['conn'];
+  public readonly udpToken = crypto.randomBytes(UDP_TOKEN_SIZE).toString('hex');
+  // Where frames go once the client has bound its UDP socket
+  public udpTarget: RemoteInfo | null = null;
+  private handlers = new EventEmitter();
+  private connEvents = new EventEmitter();
+  // Stream writes and datagrams not yet handed to the kernel; both count, so
+  // frame pacing also backs off when UDP sends pile up
+  private pendingWrites = 0;
+  // Reads of a partly received message, joined once it is complete
+  private received: Buffer[] = [];
+  private receivedLength = 0;
+  private lastActivity = Date.now();
+  private missedHeartbeats = 0;
+  private closeReason: string | null = null;
+  // Switched on while the client reports UDP loss at or above the threshold
+  private fecEnabled = false;
+  public readonly stats: ConnectionStats;
+
+  constructor(
+    private socket: NetSocket,
+    private maxPayload: number,
+    private sendDatagram: ((datagram: Buffer, target: RemoteInfo, sent: () => void) => void) | null,
+    private fec: FecOptions
+  ) {
+    const pending = () => this.pendingWrites;
+    this.conn = {
+      writeBuffer: {
+        get length() {
+          return pending();
+        }
+      },
+      on: (event, listener) => this.connEvents.on(event, listener)
+    };
+    this.stats = new ConnectionStats(this.id, 'tcp', socket.remoteAddress);
+    this.stats.addQueue(pending);
+
+    socket.on('data', (chunk: Buffer) => this.handleData(chunk));
+    socket.on('close', () => {
+      this.handlers.emit('disconnect', this.closeReason ?? 'transport close');
+    });
+  }
+
+  public get remoteAddress(): string {
+    return this.socket.remoteAddress || 'unknown';
+  }
+
+  public on(event: string, listener: (...args: any[]) => void): this {
+    this.handlers.on(event, listener);
+    return this;
+  }
+
+  public emit(event: string, data?: any): boolean {
+    if (this.socket.destroyed) return false;
+
+    const encoded = this.encodeFrames(event, data);
+    if (encoded) {
+      // Lost datagrams are rebuilt from parity when FEC is on, otherwise
+      // recovered by the client asking for a refresh; a batch is fragmented
+      // under the id of its first frame
+      if (this.udpTarget && this.sendDatagram) {
+        const frameId = event === 'frames' ? data.frames[0]?.frameId ?? 0 : data.frameId;
+        // Leaves room for the larger parity header when FEC may be used
+        const maxDatagram = this.fec.groupSize > 0
+          ? MAX_DATAGRAM_SIZE - (PARITY_HEADER_SIZE - FRAGMENT_HEADER_SIZE)
+          : MAX_DATAGRAM_SIZE;
+        const fragments = BinaryProtocol.fragmentFrame(frameId, encoded, maxDatagram);
+        const parity = this.fecEnabled ? BinaryProtocol.encodeParity(frameId, fragments, this.fec.groupSize) : [];
+        for (const datagram of [...fragments, ...parity]) {
+          this.pendingWrites++;
+          this.sendDatagram(datagram, this.udpTarget, () => this.writeDone());
+          this.stats.recordOut(datagram.length);
+        }
+        return true;
+      }
+      this.write(encoded);
+      return true;
+    }
+
+    this.write(BinaryProtocol.encodeEvent(event, data));
+    return true;
+  }
+
+  public join(room: string): void {
+    this.rooms.add(room);
+  }
+
+  public leave(room: string): void {
+    this.rooms.delete(room);
+  }
+
+  public disconnect(): this {
+    this.closeReason = 'server namespace disconnect';
+    this.socket.end();
+    return this;
+  }
+
+  public close(): void {
+    this.socket.end();
+  }
+
+  // Same rule as plain WebSocket clients; native clients answer the
+  // heartbeat event with anything at all, and echoing it back also gives
+  // the gateway a round-trip time
+  public heartbeat(interval: number, maxMisses: number): boolean {
+    if (Date.now() - this.lastActivity < interval) {
+      this.missedHeartbeats = 0;
+    } else if (++this.missedHeartbeats >= maxMisses) {
+      this.closeReason = 'ping timeout';
+      this.socket.destroy();
+      return false;
+    }
+
+    this.emit('heartbeat', { timestamp: Date.now(), interval, misses: maxMisses });
+    return true;
+  }
+
+  private encodeFrames(event: string, data: any): Buffer | null {
+    if (event === 'frame' && Buffer.isBuffer(data?.data)) {
+      return BinaryProtocol.encodeFrame(data);
+    }
+    if (event === 'frames' && Array.isArray(data?.frames)) {
+      return BinaryProtocol.encodeFrameBatch(data.frames);
+    }
+    return null;
+  }
+
+  // Clients periodically report `{ received, lost }` video datagrams. FEC
+  // turns off again only once loss falls well below the threshold, so it
+  // does not flap around it.
+  private handleLossReport(report: any): void {
+    const received = Number(report?.received) || 0;
+    const lost = Number(report?.lost) || 0;
+    if (this.fec.groupSize <= 0 || received + lost === 0) return;
+
+    const lossRate = lost / (received + lost);
+    const enabled = this.fecEnabled
+      ? lossRate >= this.fec.lossThreshold / 2
+      : lossRate >= this.fec.lossThreshold;
+    if (enabled === this.fecEnabled) return;
+
+    this.fecEnabled = enabled;
+    this.emit('udp:fec', { enabled, groupSize: this.fec.groupSize, lossRate });
+  }
+
+  private write(message: Buffer): void {
+    const prefix = Buffer.alloc(LENGTH_PREFIX_SIZE);
+    prefix.writeUInt32BE(message.length, 0);
+
+    this.stats.recordOut(LENGTH_PREFIX_SIZE + message.length);
+    this.pendingWrites++;
+    this.socket.write(Buffer.concat([prefix, message]), () => this.writeDone());
+  }
+
+  private writeDone(): void {
+    this.pendingWrites--;
+    if (this.pendingWrites === 0) {
+      this.connEvents.emit('drain');
+    }
+  }
+
+  // A message spread over many reads is copied once, when its last byte arrives
+  private handleData(chunk: Buffer): void {
+    this.lastActivity = Date.now();
+    this.stats.recordIn(chunk.length);
+    this.received.push(chunk);
+    this.receivedLength += chunk.length;
+
+    while (this.receivedLength >= LENGTH_PREFIX_SIZE) {
+      // The prefix itself may straddle reads
+      if (this.received[0].length < LENGTH_PREFIX_SIZE) {
+        this.received = [Buffer.concat(this.received, this.receivedLength)];
+      }
+      const length = this.received[0].readUInt32BE(0);
+      if (length > this.maxPayload) {
+        this.emit('error', { error: `Message of ${length} bytes exceeds the ${this.maxPayload} byte limit` });
+        this.socket.destroy();
+        return;
+      }
+      if (this.receivedLength < LENGTH_PREFIX_SIZE + length) return;
+
+      const buffered = this.received.length === 1
+        ? this.received[0]
+        : Buffer.concat(this.received, this.receivedLength);
+      const message = buffered.subarray(LENGTH_PREFIX_SIZE, LENGTH_PREFIX_SIZE + length);
+      const rest = buffered.subarray(LENGTH_PREFIX_SIZE + length);
+      this.received = rest.length > 0 ? [rest] : [];
+      this.receivedLength = rest.length;
+      this.handleMessage(message);
+    }
+  }
+
+  private handleMessage(raw: Buffer): void {
+    try {
+      const message = BinaryProtocol.decode(raw);
+      // 'disconnect' is reserved for the transport; 'close' is the client's goodbye
+      if (message.type === 'disconnect') {
+        throw new Error('Invalid message');
+      }
+      if (message.type === 'close') {
+        this.closeReason = 'client namespace disconnect';
+        this.socket.end();
+        return;
+      }
+      if (message.type === 'heartbeat') {
+        if (typeof message.data?.timestamp === 'number') {
+          this.stats.recordRtt(Date.now() - message.data.timestamp);
+        }
+        return;
+      }
+      if (message.type === 'udp:report') {
+        this.handleLossReport(message.data);
+        return;
+      }
+      this.handlers.emit(message.type, message.data);
+    } catch (error) {
+      this.emit('error', { error: (error as Error).message });
+    }
+  }
+}
+
+// Lightweight transport for native apps and CLI tools that have no use for
+// WebSocket or WebRTC framing: a TCP (or TLS) stream for everything, plus an
+// optional UDP socket that carries video once a client binds to it
+export class TcpSocketServer extends EventEmitter implements Transport {
+  public readonly name = 'tcp';
+  private logger = new Logger('TcpSocketServer');
+  private server: NetServer;
+  private udp: UdpSocket | null = null;
+  private sockets: Map<string, TcpSocket> = new Map();
+  private heartbeat: NodeJS.Timeout | null = null;
+
+  constructor(private options: TcpTransportOptions) {
+    super();
+    const onConnection = (socket: NetSocket) => this.handleConnection(socket);
+    this.server = options.tls ? createTlsServer(options.tls, onConnection) : createNetServer(onConnection);
+    this.server.on('error', (error) => this.logger.error('Native transport listener error:', error));
+  }
+
+  // Has its own listener; the HTTP server is not used
+  public start(server: HttpServer): void {
+    const { tcpPort, udpPort, pingInterval, heartbeatMisses, socket } = this.options;
+    const host = resolveBindAddress(this.options.host, socket.bindInterface);
+
+    this.server.listen({ port: tcpPort, host, ipv6Only: socket.ipv6Only }, () => {
+      this.logger.info(`Native ${this.options.tls ? 'TLS' : 'TCP'} transport listening on ${host}:${tcpPort}`);
+    });
+
+    if (udpPort > 0) {
+      this.udp = createSocket({
+        type: host.includes(':') ? 'udp6' : 'udp4',
+        ipv6Only: socket.ipv6Only,
+        sendBufferSize: socket.sendBufferSize || undefined,
+        recvBufferSize: socket.receiveBufferSize || undefined
+      });
+      this.udp.on('message', (message, rinfo) => this.handleDatagram(message, rinfo));
+      this.udp.on('error', (error) => this.logger.error('Native UDP socket error:', error));
+      this.udp.bind(udpPort, host, () => {
+        this.logger.info(`Native UDP video channel listening on ${host}:${udpPort}`);
+      });
+    }
+
+    this.heartbeat = setInterval(() => {
+      for (const socket of this.sockets.values()) {
+        if (!socket.heartbeat(pingInterval, heartbeatMisses)) {
+          this.logger.warn(`Closing ${socket.id} (${socket.remoteAddress}) after ${heartbeatMisses} missed heartbeats`);
+        }
+      }
+    }, pingInterval);
+  }
+
+  public get(socketId: string): TcpSocket | undefined {
+    return this.sockets.get(socketId);
+  }
+
+  public to(room: string, event: string, data?: any): void {
+    for (const socket of this.sockets.values()) {
+      if (socket.rooms.has(room)) {
+        socket.emit(event, data);
+      }
+    }
+  }
+
+  public broadcast(event: string, data?: any): void {
+    for (const socket of this.sockets.values()) {
+      socket.emit(event, data);
+    }
+  }
+
+  public emitFrame(socket: GatewaySocket, message: any, data: Buffer): void {
+    socket.emit('frame', { ...message, data });
+  }
+
+  public emitFrameBatch(socket: GatewaySocket, frames: OutboundFramePayload[]): void {
+    socket.emit('frames', { frames: frames.map(frame => ({ ...frame.message, data: frame.data })) });
+  }
+
+  public stats(): TransportStats {
+    return aggregateStats(this.name, Array.from(this.sockets.values(), socket => socket.stats));
+  }
+
+  public connectionStats(socketId: string): ConnectionStats | undefined {
+    return this.sockets.get(socketId)?.stats;
+  }
+
+  public close(): void {
+    if (this.heartbeat) {
+      clearInterval(this.heartbeat);
+      this.heartbeat = null;
+    }
+    for (const socket of this.sockets.values()) {
+      socket.close();
+    }
+    this.server.close();
+    this.udp?.close();
+    this.udp = null;
+  }
+
+  private handleConnection(netSocket: NetSocket): void {
+    if (!ConnectionGuard.getInstance().admitHandshake(netSocket.remoteAddress, 'tcp')) {
+      netSocket.destroy();
+      return;
+    }
+
+    netSocket.on('error', (error) => this.logger.warn('Native client socket error:', error));
+    netSocket.setNoDelay(this.options.socket.noDelay);
+    const sendDatagram = this.udp
+      ? (datagram: Buffer, target: RemoteInfo, sent: () => void) => {
+          if (!this.udp) return sent();
+          this.udp.send(datagram, target.port, target.address, () => sent());
+        }
+      : null;
+    const socket = new TcpSocket(netSocket, this.options.maxPayload, sendDatagram, {
+      groupSize: this.options.fecGroupSize,
+      lossThreshold: this.options.fecLossThreshold
+    });
+    this.sockets.set(socket.id, socket);
+    socket.on('disconnect', () => this.sockets.delete(socket.id));
+
+    this.emit('connection', socket);
+
+    if (this.udp) {
+      socket.emit('udp:offer', { port: this.options.udpPort, token: socket.udpToken, fec: this.options.fecGroupSize > 0 });
+    }
+  }
+
+  // Only a bind from the TCP client's own address with its token is accepted
+  private handleDatagram(message: Buffer, rinfo: RemoteInfo): void {
+    let token: string;
+    try {
+      const decoded = BinaryProtocol.decode(message);
+      if (decoded.type !== 'udp:bind') return;
+      token = decoded.data.token;
+    } catch (error) {
+      return;
+    }
+
+    for (const socket of this.sockets.values()) {
+      if (socket.udpToken !== token) continue;
+      if (normalizeAddress(socket.remoteAddress) !== normalizeAddress(rinfo.address)) {
+        this.logger.warn(`Ignoring UDP bind for ${socket.id} from ${rinfo.address}`);
+        return;
+      }
+
+      const rebind = socket.udpTarget !== null;
+      socket.udpTarget = rinfo;
+      if (!rebind) {
+        this.logger.info(`${socket.id} receives video over UDP at ${rinfo.address}:${rinfo.port}`);
+      }
+      socket.emit('udp:bound', { address: rinfo.address, port: rinfo.port });
+      return;
+    }
+  }
+}
+
+function normalizeAddress(address: string): string {
+  return address.startsWith('::ffff:') ? address.slice(7) : address;
+}
@@ -0,0 +1,199 @@
+import { Server as HttpServer } from 'http';
+import { EventEmitter } from 'events';
+import { Logger } from '../utils/Logger';
+import { ConnectionStats, ConnectionStatsSnapshot } from './ConnectionStats';
+
+// How often drain() checks the queues
+const DRAIN_POLL_INTERVAL = 50;
+
+// The subset of a socket.io Socket the gateway handlers rely on, so clients
+// of every transport can share the same handlers
+export interface GatewaySocket {
+  id: string;
+  data: any;
+  conn: {
+    writeBuffer: { length: number };
+    on(event: 'drain', listener: () => void): unknown;
+  };
+  on(event: string, listener: (...args: any[]) => void): unknown;
+  emit(event: string, ...args: any[]): boolean;
+  join(room: string): unknown;
+  leave(room: string): unknown;
+  disconnect(close?: boolean): unknown;
+}
+
+// A frame's metadata plus its raw payload, as handed to a transport
+export interface OutboundFramePayload {
+  message: any;
+  data: Buffer;
+}
+
+// Sums over a transport's open connections
+export interface TransportStats {
+  name: string;
+  connections: number;
+  bytesIn: number;
+  bytesOut: number;
+  bytesInPerSec: number;
+  bytesOutPerSec: number;
+  retransmits: number;
+  queueDepth: number;
+  drops: number;
+}
+
+export function aggregateStats(name: string, connections: Iterable<ConnectionStats>): TransportStats {
+  const totals: TransportStats = {
+    name,
+    connections: 0,
+    bytesIn: 0,
+    bytesOut: 0,
+    bytesInPerSec: 0,
+    bytesOutPerSec: 0,
+    retransmits: 0,
+    queueDepth: 0,
+    drops: 0
+  };
+  for (const stats of connections) {
+    const snapshot = stats.snapshot();
+    totals.connections++;
+    totals.bytesIn += snapshot.bytesIn;
+    totals.bytesOut += snapshot.bytesOut;
+    totals.bytesInPerSec += snapshot.bytesInPerSec;
+    totals.bytesOutPerSec += snapshot.bytesOutPerSec;
+    totals.retransmits += snapshot.retransmits;
+    totals.queueDepth += snapshot.queueDepth;
+    totals.drops += snapshot.drops;
+  }
+  return totals;
+}
+
+// A way for clients to reach the gateway. Implementations emit
+// 'connection' with a GatewaySocket for every client they accept; the
+// gateway's routing only ever talks to this interface.
+export interface Transport {
+  readonly name: string;
+  // Begin accepting clients; transports that share the HTTP listener hook
+  // into `server`, the others open their own
+  start(server: HttpServer): void;
+  to(room: string, event: string, data?: any): void;
+  broadcast(event: string, data?: any): void;
+  // Frames carry a raw payload; each transport picks its own encoding
+  emitFrame(socket: GatewaySocket, message: any, data: Buffer): void;
+  // Several small updates as one message
+  emitFrameBatch(socket: GatewaySocket, frames: OutboundFramePayload[]): void;
+  close(): void;
+  stats(): TransportStats;
+  connectionStats(socketId: string): ConnectionStats | undefined;
+  on(event: 'connection', listener: (socket: GatewaySocket) => void): unknown;
+}
+
+// Fans gateway output out to every registered transport and funnels their
+// clients into one 'connection' event, tagged with the transport they used
+export class TransportRegistry extends EventEmitter {
+  private static instance: TransportRegistry;
+  private logger = new Logger('TransportRegistry');
+  private transports: Map<string, Transport> = new Map();
+
+  public static getInstance(): TransportRegistry {
+    if (!TransportRegistry.instance) {
+      TransportRegistry.instance = new TransportRegistry();
+    }
+    return TransportRegistry.instance;
+  }
+
+  public register(transport: Transport): void {
+    if (this.transports.has(transport.name)) {
+      throw new Error(`Transport ${transport.name} is already registered`);
+    }
+
+    this.transports.set(transport.name, transport);
+    transport.on('connection', (socket: GatewaySocket) => this.emit('connection', socket, transport));
+    this.logger.info(`Registered ${transport.name} transport`);
+  }
+
+  public get(name: string): Transport | undefined {
+    return this.transports.get(name);
+  }
+
+  public list(): Transport[] {
+    return Array.from(this.transports.values());
+  }
+
+  public start(server: HttpServer): void {
+    for (const transport of this.transports.values()) {
+      transport.start(server);
+    }
+  }
+
+  public to(room: string, event: string, data?: any): void {
+    for (const transport of this.transports.values()) {
+      transport.to(room, event, data);
+    }
+  }
+
+  public broadcast(event: string, data?: any): void {
+    for (const transport of this.transports.values()) {
+      transport.broadcast(event, data);
+    }
+  }
+
+  public stats(): TransportStats[] {
+    return this.list().map(transport => transport.stats());
+  }
+
+  // Every transport together
+  public totals(): TransportStats {
+    const totals = aggregateStats('all', []);
+    for (const stats of this.stats()) {
+      totals.connections += stats.connections;
+      totals.bytesIn += stats.bytesIn;
+      totals.bytesOut += stats.bytesOut;
+      totals.bytesInPerSec += stats.bytesInPerSec;
+      totals.bytesOutPerSec += stats.bytesOutPerSec;
+      totals.retransmits += stats.retransmits;
+      totals.queueDepth += stats.queueDepth;
+      totals.drops += stats.drops;
+    }
+    return totals;
+  }
+
+  public connectionStats(connectionId: string): ConnectionStats | undefined {
+    for (const transport of this.transports.values()) {
+      const stats = transport.connectionStats(connectionId);
+      if (stats) return stats;
+    }
+    return undefined;
+  }
+
+  public getStats(connectionId: string): ConnectionStatsSnapshot | undefined {
+    return this.connectionStats(connectionId)?.snapshot();
+  }
+
+  // Resolves once nothing is queued for any client, or with false after
+  // `timeout` ms; used to let final messages out before closing
+  public drain(timeout: number): Promise<boolean> {
+    const deadline = Date.now() + timeout;
+    return new Promise(resolve => {
+      const check = () => {
+        if (this.totals().queueDepth === 0) {
+          resolve(true);
+        } else if (Date.now() >= deadline) {
+          resolve(false);
+        } else {
+          setTimeout(check, DRAIN_POLL_INTERVAL);
+        }
+      };
+      check();
+    });
+  }
+
+  public close(): void {
+    for (const transport of this.transports.values()) {
+      try {
+        transport.close();
+      } catch (error) {
+        this.logger.error(`Failed to close ${transport.name} transport:`, error);
+      }
+    }
+  }
+}
@@ -123,6 +123,10 @@ export class WebSocketManager extends EventEmitter {
       this.handleMonitorChange(client, data);
     });
 
+    socket.on('display:subscribe', (data) => {
+      this.handleDisplaySubscribe(client, data);
+    });
+
     // Ping/Pong for connection health
     socket.on('ping', () => {
       socket.emit('pong', { timestamp: Date.now() });
@@ -293,11 +297,22 @@ export class WebSocketManager extends EventEmitter {
     this.emit('monitorChange', { client, data });
   }
 
+  private handleDisplaySubscribe(client: WebSocketClient, data: any): void {
+    if (!client.authenticated || !client.sessionId) {
+      return;
+    }
+
+    client.lastActivity = new Date();
+    
+    // Emit display subscription event for external handling
+    this.emit('displaySubscribe', { client, data });
+  }
+
   // Public methods for sending data to clients
-  public sendFrame(clientId: string, frame: Buffer): void {
+  public sendFrame(clientId: string, frame: Buffer, displayId: number = 0): void {
     const client = this.clients.get(clientId);
     if (client && client.authenticated) {
-      client.socket.emit('frame', { data: frame.toString('base64') });
+      client.socket.emit('frame', { displayId, data: frame.toString('base64') });
       client.lastActivity = new Date();
     }
   }
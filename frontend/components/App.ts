@@ -1,21 +1,37 @@
 import { Logger } from '../utils/Logger';
 import { Config } from '../utils/Config';
-import { RDPService } from '../services/rdp/RDPService';
+import { RDPService, ScaleMode, KeyboardMode, TouchContact, PenSample, KeyCombo } from '../services/rdp/RDPService';
 import { CanvasRenderer } from './rdp/CanvasRenderer';
-import { InputHandler } from './rdp/InputHandler';
+import { AudioPlayer } from './rdp/AudioPlayer';
+import { InputHandler, CustomTouchEvent, CustomPenEvent } from './rdp/InputHandler';
 import { RDPConnectionPanel } from './rdp/RDPConnectionPanel';
 import { PerformanceOverlay } from './ui/PerformanceOverlay';
 import { SettingsPanel } from './panels/SettingsPanel';
 import { FileTransferPanel } from './panels/FileTransferPanel';
 import { DeviceRedirectionPanel } from './panels/DeviceRedirectionPanel';
 
+// InputHandler mouse actions to the gateway's pointer actions
+const POINTER_ACTIONS: Record<string, string> = {
+  mousedown: 'down',
+  mouseup: 'up',
+  mousemove: 'move'
+};
+
+// Action for the touches that changed in each InputHandler touch event
+const TOUCH_ACTIONS: Record<string, TouchContact['action']> = {
+  touchstart: 'down',
+  touchmove: 'move',
+  touchend: 'up',
+  touchcancel: 'cancel'
+};
+
 export interface RDPConnectionState {
   host: string;
   port: number;
   username: string;
   password: string;
   domain?: string;
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   enableAudio: boolean;
   enableClipboard: boolean;
   enableFileTransfer: boolean;
@@ -25,6 +41,18 @@ export interface RDPConnectionState {
   colorDepth: number;
   width: number;
   height: number;
+  initialDisplay?: number;
+  viewOnly?: boolean;
+  scaleMode?: ScaleMode;
+  keyboardMode?: KeyboardMode;
+}
+
+export interface DeepLinkOptions {
+  initialDisplay?: number;
+  quality?: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
+  viewOnly?: boolean;
+  scaleMode?: ScaleMode;
+  keyboardMode?: KeyboardMode;
 }
 
 export interface AppState {
@@ -36,7 +64,7 @@ export interface AppState {
   showFileTransfer: boolean;
   showDeviceRedirection: boolean;
   theme: 'light' | 'dark' | 'auto';
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   fullscreen: boolean;
   error: string | null;
 }
@@ -48,6 +76,7 @@ export class App {
   private rdpService: RDPService;
   private canvasRenderer!: CanvasRenderer;
   private inputHandler!: InputHandler;
+  private audioPlayer!: AudioPlayer;
   private rdpConnectionPanel!: RDPConnectionPanel;
   private performanceOverlay!: PerformanceOverlay;
   private settingsPanel!: SettingsPanel;
@@ -56,6 +85,7 @@ export class App {
   
   private appElement: HTMLElement | null = null;
   private state: AppState;
+  private resizeTimer: ReturnType<typeof setTimeout> | null = null;
   
   constructor() {
     this.rdpService = new RDPService();
@@ -100,6 +130,9 @@ export class App {
     
     // Initialize input handler for RDP input forwarding
     this.inputHandler = new InputHandler(this.rdpService);
+
+    // Plays the remote computer's sound when the session has audio
+    this.audioPlayer = new AudioPlayer();
     
     // Initialize UI components
     this.rdpConnectionPanel = new RDPConnectionPanel(this.rdpService);
@@ -119,18 +152,40 @@ export class App {
     });
 
     this.rdpService.on('connected', (connection: any) => {
+      this.canvasRenderer.setScaleMode(connection.scaleMode);
+      if (connection.viewOnly) {
+        this.inputHandler.disable();
+      } else {
+        this.inputHandler.enable();
+      }
+
       this.state.isConnected = true;
       this.state.isConnecting = false;
       this.state.connectionState = connection.config;
       this.state.error = null;
       this.updateUI();
+      this.scheduleViewportResize();
       this.logger.info('Connected to RDP server');
     });
 
-    this.rdpService.on('disconnected', () => {
+    this.canvasRenderer.on('renderError', () => {
+      this.rdpService.requestRefresh();
+    });
+
+    this.rdpService.on('displayChanged', (event: any) => {
+      this.canvasRenderer.resetFrames();
+      this.logger.info(`Remote display changed (${event.reason})`);
+    });
+
+    this.rdpService.on('disconnected', (connection: any) => {
+      this.audioPlayer.close();
       this.state.isConnected = false;
       this.state.isConnecting = false;
       this.state.connectionState = null;
+      const reason = connection?.disconnectReason;
+      if (reason && reason.code !== 'client_request') {
+        this.state.error = reason.message;
+      }
       this.updateUI();
       this.logger.info('Disconnected from RDP server');
     });
@@ -151,14 +206,175 @@ export class App {
 
     // Input handler events
     this.inputHandler.on('input', (inputData: any) => {
-      // Forward input to RDP server
-      this.rdpService.sendMouseInput(inputData.x, inputData.y, inputData.button, inputData.action);
+      // The session's keyboard mode decides between scan codes and text;
+      // keypress only repeats what keydown already sent
+      if (inputData.type === 'keyboard') {
+        if (inputData.action !== 'keypress') {
+          const { code, key, modifiers } = inputData.data;
+          const shortcut = (modifiers.ctrl || modifiers.alt || modifiers.meta) && !modifiers.altGraph;
+          this.rdpService.sendKey(code, key, inputData.action === 'keydown', shortcut);
+        }
+        return;
+      }
+      if (inputData.type === 'text') {
+        this.rdpService.sendTextInput(inputData.data.text);
+        return;
+      }
+
+      // Pointer positions go normalized within the displayed monitor, so
+      // the gateway can place them on the host's multi-monitor desktop
+      if (inputData.type === 'mouse') {
+        const action = POINTER_ACTIONS[inputData.action];
+        if (action && this.inputHandler.isPointerLocked()) {
+          this.rdpService.sendRelativePointerInput(inputData.data.deltaX || 0, inputData.data.deltaY || 0, inputData.data.button, action);
+          return;
+        }
+
+        const point = action && this.canvasRenderer.normalizePoint(inputData.data.x, inputData.data.y);
+        if (!point) return;
+
+        const displayId = this.rdpService.getConnection()?.subscribedDisplayIds[0] ?? 0;
+        this.rdpService.sendPointerInput(displayId, point.x, point.y, inputData.data.button, action);
+        return;
+      }
+      if (inputData.type === 'touch') {
+        this.sendTouchFrame(inputData.action, inputData.data);
+        return;
+      }
+      if (inputData.type === 'pen') {
+        this.sendPenSample(inputData.data);
+      }
+    });
+
+    // The gateway drops input it no longer permits; stop capturing it too
+    this.rdpService.on('permissionsChanged', (permissions: any) => {
+      if (this.rdpService.getConnection()?.viewOnly || (!permissions.mouse && !permissions.keyboard)) {
+        this.inputHandler.disable();
+      } else {
+        this.inputHandler.enable();
+      }
+      this.logger.info('Session permissions changed:', permissions);
+    });
+
+    // The gateway injects motion deltas for as long as the pointer is locked
+    this.inputHandler.on('pointerLockChange', (locked: boolean) => {
+      this.rdpService.setPointerMode(locked ? 'relative' : 'absolute');
     });
 
     // Keyboard shortcuts
     document.addEventListener('keydown', (event) => {
       this.handleKeyboardShortcut(event);
     });
+
+    this.rdpService.on('audioConfig', (config: any) => {
+      this.audioPlayer.configure(config);
+    });
+
+    this.rdpService.on('audioPacket', (packet: any) => {
+      this.audioPlayer.decode(packet);
+    });
+
+    // The audio context may only start after a user gesture
+    document.addEventListener('pointerdown', () => this.audioPlayer.resume());
+    document.addEventListener('keydown', () => this.audioPlayer.resume());
+
+    // Presence without remote input still counts against the idle timeout
+    document.addEventListener('pointermove', () => this.rdpService.reportActivity());
+    document.addEventListener('visibilitychange', () => {
+      if (document.visibilityState === 'visible') {
+        this.rdpService.reportActivity();
+      }
+    });
+
+    this.rdpService.on('idle', (event: any) => {
+      if (event?.action === 'lock') {
+        this.logger.warn('The remote computer was locked after a period of inactivity');
+      }
+    });
+
+    // Match the remote resolution to the browser viewport
+    window.addEventListener('resize', () => {
+      this.scheduleViewportResize();
+    });
+  }
+
+  // A touch event lists the fingers that changed; the host expects every
+  // finger in each frame, the unchanged ones as moves
+  private sendTouchFrame(action: string, event: CustomTouchEvent): void {
+    const changedAction = TOUCH_ACTIONS[action];
+    if (!changedAction) return;
+
+    const changedIds = new Set(event.changedTouches.map(touch => touch.id));
+    const points = [
+      ...event.touches.filter(touch => !changedIds.has(touch.id)).map(touch => ({ touch, action: 'move' as const })),
+      ...event.changedTouches.map(touch => ({ touch, action: changedAction }))
+    ];
+
+    const contacts: TouchContact[] = [];
+    for (const { touch, action: contactAction } of points) {
+      const center = this.canvasRenderer.normalizePoint(touch.x, touch.y);
+      const edge = this.canvasRenderer.normalizePoint(touch.x + touch.radiusX, touch.y + touch.radiusY);
+      if (!center || !edge) continue;
+
+      contacts.push({
+        id: touch.id,
+        action: contactAction,
+        x: center.x,
+        y: center.y,
+        pressure: Math.round(Math.min(Math.max(touch.pressure, 0), 1) * 1024),
+        width: Math.min((edge.x - center.x) * 2, 65535),
+        height: Math.min((edge.y - center.y) * 2, 65535),
+        orientation: Math.round(touch.rotationAngle) % 360
+      });
+    }
+
+    if (contacts.length > 0) {
+      const displayId = this.rdpService.getConnection()?.subscribedDisplayIds[0] ?? 0;
+      this.rdpService.sendTouchFrame(displayId, contacts);
+    }
+  }
+
+  private sendPenSample(event: CustomPenEvent): void {
+    const point = this.canvasRenderer.normalizePoint(event.x, event.y);
+    if (!point) return;
+
+    const pen: PenSample = {
+      action: event.type,
+      x: point.x,
+      y: point.y,
+      pressure: Math.round(Math.min(Math.max(event.pressure, 0), 1) * 1024),
+      tiltX: Math.round(event.tiltX),
+      tiltY: Math.round(event.tiltY),
+      rotation: Math.round(event.twist) % 360,
+      barrel: event.barrel,
+      eraser: event.eraser
+    };
+
+    const displayId = this.rdpService.getConnection()?.subscribedDisplayIds[0] ?? 0;
+    this.rdpService.sendPenInput(displayId, pen);
+  }
+
+  private scheduleViewportResize(): void {
+    const display = this.config.get('display') as any;
+    if (!display.dynamicResolution || !this.state.isConnected) return;
+
+    if (this.resizeTimer) {
+      clearTimeout(this.resizeTimer);
+    }
+
+    // Debounced so a window drag doesn't trigger a remote resize per pixel
+    this.resizeTimer = setTimeout(() => {
+      this.resizeTimer = null;
+
+      const container = document.querySelector('.rdp-container') as HTMLElement | null;
+      if (!container) return;
+
+      const ratio = window.devicePixelRatio || 1;
+      this.rdpService.requestResize(
+        Math.round(container.clientWidth * ratio),
+        Math.round(container.clientHeight * ratio)
+      );
+    }, display.resizeDebounce);
   }
 
   private render(): void {
@@ -185,6 +401,12 @@ export class App {
               <button class="btn btn-secondary" onclick="app.toggleDeviceRedirection()">
                 <i class="icon-device"></i> Devices
               </button>
+              <button class="btn btn-secondary" onclick="app.sendKeyCombo('ctrl-alt-del')" title="Ctrl+Alt+End">
+                <i class="icon-keyboard"></i> Ctrl+Alt+Del
+              </button>
+              <button class="btn btn-secondary" onclick="app.toggleAudioMute()">
+                <i class="icon-audio"></i> ${this.audioPlayer.isMuted() ? 'Unmute' : 'Mute'}
+              </button>
               <button class="btn btn-danger" onclick="app.disconnect()">
                 <i class="icon-disconnect"></i> Disconnect
               </button>
@@ -227,6 +449,7 @@ export class App {
                       <option value="medium" selected>Medium (Balanced)</option>
                       <option value="high">High (Quality)</option>
                       <option value="ultra">Ultra (Best)</option>
+                      <option value="lossless">Lossless (Pixel-exact)</option>
                     </select>
                   </div>
                   <div class="form-group">
@@ -342,13 +565,16 @@ export class App {
       return;
     }
 
+    // URL query parameters take precedence over form values
+    const deepLink = this.getDeepLinkOptions();
+
     const config = {
       host,
       port,
       username,
       password,
       domain: domain || undefined,
-      quality,
+      quality: deepLink.quality || quality,
       enableAudio: enableAudio || false,
       enableClipboard: enableClipboard || false,
       enableFileTransfer: enableFileTransfer || false,
@@ -357,16 +583,61 @@ export class App {
       enableMultiMonitor: false,
       colorDepth: 24,
       width: 1920,
-      height: 1080
+      height: 1080,
+      initialDisplay: deepLink.initialDisplay,
+      viewOnly: deepLink.viewOnly,
+      scaleMode: deepLink.scaleMode,
+      keyboardMode: deepLink.keyboardMode
     };
 
     this.rdpService.connect(config);
   }
 
+  private getDeepLinkOptions(): DeepLinkOptions {
+    const params = new URLSearchParams(window.location.search);
+    const options: DeepLinkOptions = {};
+
+    const display = params.get('display');
+    if (display !== null && /^\d+$/.test(display)) {
+      options.initialDisplay = parseInt(display);
+    }
+
+    const quality = params.get('quality');
+    if (quality === 'low' || quality === 'medium' || quality === 'high' || quality === 'ultra' || quality === 'lossless') {
+      options.quality = quality;
+    }
+
+    const viewOnly = params.get('viewOnly');
+    if (viewOnly !== null) {
+      options.viewOnly = viewOnly === '' || viewOnly === '1' || viewOnly === 'true';
+    }
+
+    const scale = params.get('scale');
+    if (scale === 'fit' || scale === 'fill' || scale === 'stretch') {
+      options.scaleMode = scale;
+    }
+
+    const keyboard = params.get('keyboard');
+    if (keyboard === 'auto' || keyboard === 'position' || keyboard === 'character') {
+      options.keyboardMode = keyboard;
+    }
+
+    return options;
+  }
+
   public disconnect(): void {
     this.rdpService.disconnect();
   }
 
+  public sendKeyCombo(combo: KeyCombo): void {
+    this.rdpService.sendKeyCombo(combo);
+  }
+
+  public toggleAudioMute(): void {
+    this.audioPlayer.setMuted(!this.audioPlayer.isMuted());
+    this.updateUI();
+  }
+
   public toggleSettings(): void {
     this.state.showSettings = !this.state.showSettings;
     this.updateUI();
@@ -408,10 +679,10 @@ export class App {
   }
 
   private handleKeyboardShortcut(event: KeyboardEvent): void {
-    // Ctrl+Alt+Del
-    if (event.ctrlKey && event.altKey && event.key === 'Delete') {
+    // Ctrl+Alt+End for Ctrl+Alt+Del, which the local OS never passes on
+    if (event.ctrlKey && event.altKey && (event.key === 'End' || event.key === 'Delete')) {
       event.preventDefault();
-      this.rdpService.sendKeyboardInput(0x2D, 0x53, 0x0001); // Ctrl+Alt+Del
+      this.sendKeyCombo('ctrl-alt-del');
     }
     
     // Ctrl+Alt+Break
@@ -426,6 +697,16 @@ export class App {
       this.toggleFullscreen();
     }
     
+    // Ctrl+Shift+L for pointer lock (relative mouse mode)
+    if (event.ctrlKey && event.shiftKey && event.key === 'L') {
+      event.preventDefault();
+      if (this.inputHandler.isPointerLocked()) {
+        this.inputHandler.exitPointerLock();
+      } else {
+        this.inputHandler.requestPointerLock();
+      }
+    }
+
     // Ctrl+Shift+O for performance overlay
     if (event.ctrlKey && event.shiftKey && event.key === 'O') {
       event.preventDefault();
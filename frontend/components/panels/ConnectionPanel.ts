@@ -7,7 +7,7 @@ export interface ConnectionFormData {
   port: number;
   secure: boolean;
   token?: string;
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   enableAudio: boolean;
   enableVideo: boolean;
   enableClipboard: boolean;
@@ -94,6 +94,7 @@ export class ConnectionPanel {
               <option value="medium" ${this.formData.quality === 'medium' ? 'selected' : ''}>Medium (720p)</option>
               <option value="high" ${this.formData.quality === 'high' ? 'selected' : ''}>High (1080p)</option>
               <option value="ultra" ${this.formData.quality === 'ultra' ? 'selected' : ''}>Ultra (4K)</option>
+              <option value="lossless" ${this.formData.quality === 'lossless' ? 'selected' : ''}>Lossless (Pixel-exact)</option>
             </select>
           </div>
           
@@ -333,7 +334,7 @@ export class ConnectionPanel {
         this.formData.token = value as string;
         break;
       case 'quality':
-        this.formData.quality = value as 'low' | 'medium' | 'high' | 'ultra';
+        this.formData.quality = value as 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
         break;
       case 'enableVideo':
         this.formData.enableVideo = value as boolean;
@@ -199,7 +199,8 @@ export class SettingsPanel {
               { value: 'low', label: 'Low (480p)' },
               { value: 'medium', label: 'Medium (720p)' },
               { value: 'high', label: 'High (1080p)' },
-              { value: 'ultra', label: 'Ultra (4K)' }
+              { value: 'ultra', label: 'Ultra (4K)' },
+              { value: 'lossless', label: 'Lossless (Text)' }
             ]
           },
           {
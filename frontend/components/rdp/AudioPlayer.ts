@@ -0,0 +1,117 @@
+import { Logger } from '../../utils/Logger';
+
+export interface AudioStreamConfig {
+  codec: string;
+  sampleRate: number;
+  channels: number;
+}
+
+// Decoded audio is scheduled this far ahead to absorb network jitter
+const PLAYBACK_DELAY = 0.06; // seconds
+// Further behind than this and playback skips ahead instead of drifting
+const MAX_LATENCY = 0.3; // seconds
+
+// Plays the host's Opus stream through WebCodecs and Web Audio
+export class AudioPlayer {
+  private logger = new Logger('AudioPlayer');
+
+  private context: AudioContext | null = null;
+  private decoder: AudioDecoder | null = null;
+  private nextStartTime = 0;
+  private lastSequence = -1;
+  private muted = false;
+
+  static isSupported(): boolean {
+    return typeof AudioDecoder !== 'undefined' && typeof AudioContext !== 'undefined';
+  }
+
+  configure(config: AudioStreamConfig): void {
+    if (!AudioPlayer.isSupported()) {
+      this.logger.warn('Audio playback is not supported by this browser');
+      return;
+    }
+    this.closeDecoder();
+
+    this.context = this.context || new AudioContext({ sampleRate: config.sampleRate, latencyHint: 'interactive' });
+    this.decoder = new AudioDecoder({
+      output: (data) => this.play(data),
+      error: (error) => {
+        this.logger.error('Audio decoding failed:', error);
+        this.closeDecoder();
+      }
+    });
+    this.decoder.configure({
+      codec: config.codec,
+      sampleRate: config.sampleRate,
+      numberOfChannels: config.channels
+    });
+    this.nextStartTime = 0;
+    this.lastSequence = -1;
+    this.logger.info(`Audio configured: ${config.codec}, ${config.sampleRate} Hz, ${config.channels} channel(s)`);
+  }
+
+  decode(packet: { sequence: number; data: ArrayBuffer; timestamp: number }): void {
+    if (!this.decoder || this.decoder.state !== 'configured') return;
+    // Packets can arrive twice after a reconnect
+    if (packet.sequence <= this.lastSequence) return;
+
+    this.lastSequence = packet.sequence;
+    this.decoder.decode(new EncodedAudioChunk({
+      type: 'key',
+      timestamp: packet.timestamp * 1000,
+      data: packet.data
+    }));
+  }
+
+  // Browsers only start an AudioContext after a user gesture
+  resume(): void {
+    if (this.context?.state === 'suspended') {
+      this.context.resume().catch(() => undefined);
+    }
+  }
+
+  setMuted(muted: boolean): void {
+    this.muted = muted;
+  }
+
+  isMuted(): boolean {
+    return this.muted;
+  }
+
+  close(): void {
+    this.closeDecoder();
+    this.context?.close().catch(() => undefined);
+    this.context = null;
+  }
+
+  private play(data: AudioData): void {
+    const context = this.context;
+    if (!context || this.muted) {
+      data.close();
+      return;
+    }
+
+    const buffer = context.createBuffer(data.numberOfChannels, data.numberOfFrames, data.sampleRate);
+    for (let channel = 0; channel < data.numberOfChannels; channel++) {
+      data.copyTo(buffer.getChannelData(channel), { planeIndex: channel, format: 'f32-planar' });
+    }
+    data.close();
+
+    const now = context.currentTime;
+    if (this.nextStartTime < now || this.nextStartTime > now + MAX_LATENCY) {
+      this.nextStartTime = now + PLAYBACK_DELAY;
+    }
+    const source = context.createBufferSource();
+    source.buffer = buffer;
+    source.connect(context.destination);
+    source.start(this.nextStartTime);
+    this.nextStartTime += buffer.duration;
+  }
+
+  private closeDecoder(): void {
+    if (this.decoder && this.decoder.state !== 'closed') {
+      this.decoder.close();
+    }
+    this.decoder = null;
+  }
+}
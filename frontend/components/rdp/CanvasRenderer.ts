@@ -7,7 +7,7 @@ export interface FrameData {
   height: number;
   data: ImageData | ArrayBuffer | Blob;
   timestamp: number;
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
 }
 
 export interface RenderStats {
@@ -45,6 +45,8 @@ export class CanvasRenderer extends EventEmitter {
   
   private scaleMode: 'fit' | 'fill' | 'stretch' = 'fit';
   private maintainAspectRatio = true;
+  // Where the last frame landed on the canvas, for mapping pointer positions
+  private drawRect: { x: number; y: number; width: number; height: number } | null = null;
 
   initialize(canvas: HTMLCanvasElement): void {
     this.canvas = canvas;
@@ -88,6 +90,12 @@ export class CanvasRenderer extends EventEmitter {
     this.stats.totalFrames++;
   }
 
+  // Called when the remote display geometry changes; queued frames are stale
+  resetFrames(): void {
+    this.frameQueue = [];
+    this.clearCanvas();
+  }
+
   setScaleMode(mode: 'fit' | 'fill' | 'stretch'): void {
     this.scaleMode = mode;
     this.logger.info(`Scale mode changed to ${mode}`);
@@ -170,15 +178,15 @@ export class CanvasRenderer extends EventEmitter {
     const frame = this.frameQueue.shift();
     if (!frame) return;
     
-    try {
-      this.renderFrame(frame);
+    this.renderFrame(frame).then(() => {
       this.stats.frameCount++;
       this.emit('frameReceived');
-      
-    } catch (error) {
+    }).catch((error) => {
       this.logger.error('Failed to render frame', error);
       this.stats.droppedFrames++;
-    }
+      // Lets the owner ask the server for a full frame to recover from
+      this.emit('renderError', error);
+    });
   }
 
   private async renderFrame(frame: FrameData): Promise<void> {
@@ -210,6 +218,8 @@ export class CanvasRenderer extends EventEmitter {
       this.canvas.height
     );
     
+    this.drawRect = { x, y, width, height };
+
     // Draw image
     if (imageData instanceof ImageData) {
       // Create temporary canvas for ImageData
@@ -310,13 +320,14 @@ export class CanvasRenderer extends EventEmitter {
   }
 
   // Public methods for external control
-  setQuality(quality: 'low' | 'medium' | 'high' | 'ultra'): void {
+  setQuality(quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless'): void {
     // Adjust queue size based on quality
     const qualitySettings = {
       low: 5,
       medium: 10,
       high: 15,
-      ultra: 20
+      ultra: 20,
+      lossless: 20
     };
     
     this.maxQueueSize = qualitySettings[quality];
@@ -334,10 +345,24 @@ export class CanvasRenderer extends EventEmitter {
     if (!this.ctx || !this.canvas) return;
     
     this.ctx.clearRect(0, 0, this.canvas.width, this.canvas.height);
+    this.drawRect = null;
     this.frameQueue = [];
     this.stats.frameCount = 0;
   }
 
+  // Maps a canvas position to 0..65535 within the displayed frame, so the
+  // gateway can place it on the remote display whatever the scaling.
+  // Positions in the letterbox are clamped to the frame's edge.
+  normalizePoint(x: number, y: number): { x: number; y: number } | null {
+    if (!this.drawRect || this.drawRect.width <= 0 || this.drawRect.height <= 0) return null;
+
+    const clamp = (value: number) => Math.min(Math.max(value, 0), 1);
+    return {
+      x: Math.round(clamp((x - this.drawRect.x) / this.drawRect.width) * 65535),
+      y: Math.round(clamp((y - this.drawRect.y) / this.drawRect.height) * 65535)
+    };
+  }
+
   takeScreenshot(): string {
     if (!this.canvas) return '';
     
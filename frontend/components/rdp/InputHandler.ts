@@ -4,7 +4,7 @@ import { EventEmitter } from '../../utils/EventEmitter';
 import { Config } from '../../utils/Config';
 
 export interface InputEvent {
-  type: 'mouse' | 'keyboard' | 'touch' | 'wheel';
+  type: 'mouse' | 'keyboard' | 'touch' | 'wheel' | 'text' | 'pen';
   action: string;
   data: any;
   timestamp: number;
@@ -35,24 +35,27 @@ export interface CustomKeyboardEvent {
     alt: boolean;
     shift: boolean;
     meta: boolean;
+    // AltGr; browsers on Windows also report it as Ctrl+Alt
+    altGraph?: boolean;
   };
   repeat: boolean;
 }
 
+// One finger, in canvas coordinates; the radii describe the contact area
+export interface TouchPoint {
+  id: number;
+  x: number;
+  y: number;
+  pressure: number;
+  radiusX: number;
+  radiusY: number;
+  rotationAngle: number;
+}
+
 export interface CustomTouchEvent {
-  type: 'touchstart' | 'touchend' | 'touchmove';
-  touches: Array<{
-    id: number;
-    x: number;
-    y: number;
-    pressure: number;
-  }>;
-  changedTouches: Array<{
-    id: number;
-    x: number;
-    y: number;
-    pressure: number;
-  }>;
+  type: 'touchstart' | 'touchend' | 'touchmove' | 'touchcancel';
+  touches: TouchPoint[];
+  changedTouches: TouchPoint[];
 }
 
 export interface CustomWheelEvent {
@@ -71,6 +74,23 @@ export interface CustomWheelEvent {
   };
 }
 
+// Pointer event button bits for a stylus
+const PEN_BARREL_BUTTON = 2;
+const PEN_ERASER_BUTTON = 32;
+
+// A stylus sample in canvas coordinates; pressure 0..1, tilt and twist in degrees
+export interface CustomPenEvent {
+  type: 'down' | 'move' | 'up' | 'hover' | 'cancel';
+  x: number;
+  y: number;
+  pressure: number;
+  tiltX: number;
+  tiltY: number;
+  twist: number;
+  barrel: boolean;
+  eraser: boolean;
+}
+
 export class InputHandler extends EventEmitter {
   private logger = new Logger('InputHandler');
   private config = Config.getInstance();
@@ -83,6 +103,8 @@ export class InputHandler extends EventEmitter {
   private mousePosition = { x: 0, y: 0 };
   private pressedKeys = new Set<string>();
   private pressedButtons = new Set<number>();
+  // Browsers also fire mouse events for a stylus; they are ignored while it is in range
+  private penInRange = false;
   
   private inputQueue: InputEvent[] = [];
   private maxQueueSize = 100;
@@ -128,6 +150,22 @@ export class InputHandler extends EventEmitter {
     this.logger.info('Input capture stopped');
   }
 
+  // Hides the cursor and reports raw motion, as 3D apps and games expect;
+  // the browser releases the lock on Escape
+  requestPointerLock(): void {
+    this.canvas?.requestPointerLock();
+  }
+
+  exitPointerLock(): void {
+    if (this.isPointerLocked()) {
+      document.exitPointerLock();
+    }
+  }
+
+  isPointerLocked(): boolean {
+    return this.canvas !== null && document.pointerLockElement === this.canvas;
+  }
+
   private setupEventListeners(): void {
     if (!this.canvas) return;
 
@@ -144,10 +182,22 @@ export class InputHandler extends EventEmitter {
     this.canvas.addEventListener('keyup', this.handleKeyUp.bind(this));
     this.canvas.addEventListener('keypress', this.handleKeyPress.bind(this));
 
+    // Text that is not a key sequence: committed IME compositions and pastes
+    this.canvas.addEventListener('compositionend', this.handleCompositionEnd.bind(this));
+    this.canvas.addEventListener('paste', this.handlePaste.bind(this));
+
     // Touch events
     this.canvas.addEventListener('touchstart', this.handleTouchStart.bind(this));
     this.canvas.addEventListener('touchend', this.handleTouchEnd.bind(this));
     this.canvas.addEventListener('touchmove', this.handleTouchMove.bind(this));
+    this.canvas.addEventListener('touchcancel', this.handleTouchCancel.bind(this));
+
+    // Pen events; mouse and touch pointers keep their own events
+    this.canvas.addEventListener('pointerdown', this.handlePenEvent.bind(this));
+    this.canvas.addEventListener('pointermove', this.handlePenEvent.bind(this));
+    this.canvas.addEventListener('pointerup', this.handlePenEvent.bind(this));
+    this.canvas.addEventListener('pointercancel', this.handlePenEvent.bind(this));
+    this.canvas.addEventListener('pointerleave', this.handlePenLeave.bind(this));
 
     // Wheel events
     this.canvas.addEventListener('wheel', this.handleWheel.bind(this));
@@ -156,6 +206,9 @@ export class InputHandler extends EventEmitter {
     this.canvas.addEventListener('focus', this.handleFocus.bind(this));
     this.canvas.addEventListener('blur', this.handleBlur.bind(this));
 
+    // Pointer lock
+    document.addEventListener('pointerlockchange', this.handlePointerLockChange.bind(this));
+
     this.logger.info('Event listeners attached');
   }
 
@@ -175,10 +228,22 @@ export class InputHandler extends EventEmitter {
     this.canvas.removeEventListener('keyup', this.handleKeyUp.bind(this));
     this.canvas.removeEventListener('keypress', this.handleKeyPress.bind(this));
 
+    // Text events
+    this.canvas.removeEventListener('compositionend', this.handleCompositionEnd.bind(this));
+    this.canvas.removeEventListener('paste', this.handlePaste.bind(this));
+
     // Touch events
     this.canvas.removeEventListener('touchstart', this.handleTouchStart.bind(this));
     this.canvas.removeEventListener('touchend', this.handleTouchEnd.bind(this));
     this.canvas.removeEventListener('touchmove', this.handleTouchMove.bind(this));
+    this.canvas.removeEventListener('touchcancel', this.handleTouchCancel.bind(this));
+
+    // Pen events
+    this.canvas.removeEventListener('pointerdown', this.handlePenEvent.bind(this));
+    this.canvas.removeEventListener('pointermove', this.handlePenEvent.bind(this));
+    this.canvas.removeEventListener('pointerup', this.handlePenEvent.bind(this));
+    this.canvas.removeEventListener('pointercancel', this.handlePenEvent.bind(this));
+    this.canvas.removeEventListener('pointerleave', this.handlePenLeave.bind(this));
 
     // Wheel events
     this.canvas.removeEventListener('wheel', this.handleWheel.bind(this));
@@ -187,11 +252,14 @@ export class InputHandler extends EventEmitter {
     this.canvas.removeEventListener('focus', this.handleFocus.bind(this));
     this.canvas.removeEventListener('blur', this.handleBlur.bind(this));
 
+    // Pointer lock
+    document.removeEventListener('pointerlockchange', this.handlePointerLockChange.bind(this));
+
     this.logger.info('Event listeners removed');
   }
 
   private handleMouseDown(event: globalThis.MouseEvent): void {
-    if (!this.isEnabled || !this.isCapturing) return;
+    if (!this.isEnabled || !this.isCapturing || this.penInRange) return;
 
     event.preventDefault();
     event.stopPropagation();
@@ -211,7 +279,7 @@ export class InputHandler extends EventEmitter {
   }
 
   private handleMouseUp(event: globalThis.MouseEvent): void {
-    if (!this.isEnabled || !this.isCapturing) return;
+    if (!this.isEnabled || !this.isCapturing || this.penInRange) return;
 
     event.preventDefault();
     event.stopPropagation();
@@ -231,15 +299,17 @@ export class InputHandler extends EventEmitter {
   }
 
   private handleMouseMove(event: globalThis.MouseEvent): void {
-    if (!this.isEnabled || !this.isCapturing) return;
+    if (!this.isEnabled || !this.isCapturing || this.penInRange) return;
 
     event.preventDefault();
     event.stopPropagation();
 
     const newX = this.getRelativeX(event.clientX);
     const newY = this.getRelativeY(event.clientY);
-    const deltaX = newX - this.mousePosition.x;
-    const deltaY = newY - this.mousePosition.y;
+    // Under a pointer lock the cursor stays put; only the motion counts
+    const locked = this.isPointerLocked();
+    const deltaX = locked ? event.movementX : newX - this.mousePosition.x;
+    const deltaY = locked ? event.movementY : newY - this.mousePosition.y;
 
     const customEvent: CustomMouseEvent = {
       type: 'mousemove',
@@ -309,6 +379,8 @@ export class InputHandler extends EventEmitter {
 
   private handleKeyDown(event: globalThis.KeyboardEvent): void {
     if (!this.isEnabled || !this.isCapturing) return;
+    // Keys that feed an IME composition arrive as text once it is committed
+    if (event.isComposing) return;
 
     event.preventDefault();
     event.stopPropagation();
@@ -365,6 +437,22 @@ export class InputHandler extends EventEmitter {
     this.addInputEvent('keyboard', 'keypress', customEvent);
   }
 
+  private handleCompositionEnd(event: globalThis.CompositionEvent): void {
+    if (!this.isEnabled || !this.isCapturing || !event.data) return;
+
+    this.addInputEvent('text', 'compose', { text: event.data });
+  }
+
+  private handlePaste(event: globalThis.ClipboardEvent): void {
+    if (!this.isEnabled || !this.isCapturing) return;
+
+    const text = event.clipboardData?.getData('text/plain');
+    if (!text) return;
+
+    event.preventDefault();
+    this.addInputEvent('text', 'paste', { text });
+  }
+
   private handleTouchStart(event: globalThis.TouchEvent): void {
     if (!this.isEnabled || !this.isCapturing) return;
 
@@ -410,6 +498,61 @@ export class InputHandler extends EventEmitter {
     this.addInputEvent('touch', 'touchmove', customEvent);
   }
 
+  private handleTouchCancel(event: globalThis.TouchEvent): void {
+    if (!this.isEnabled || !this.isCapturing) return;
+
+    const customEvent: CustomTouchEvent = {
+      type: 'touchcancel',
+      touches: this.getTouchArray(event.touches),
+      changedTouches: this.getTouchArray(event.changedTouches)
+    };
+
+    this.addInputEvent('touch', 'touchcancel', customEvent);
+  }
+
+  private handlePenEvent(event: globalThis.PointerEvent): void {
+    if (!this.isEnabled || !this.isCapturing || event.pointerType !== 'pen') return;
+
+    event.preventDefault();
+    event.stopPropagation();
+    this.penInRange = true;
+
+    let type: CustomPenEvent['type'];
+    switch (event.type) {
+      case 'pointerdown':
+        type = 'down';
+        break;
+      case 'pointerup':
+        type = 'up';
+        break;
+      case 'pointercancel':
+        type = 'cancel';
+        break;
+      default:
+        type = event.buttons & 1 ? 'move' : 'hover';
+    }
+
+    const customEvent: CustomPenEvent = {
+      type,
+      x: this.getRelativeX(event.clientX),
+      y: this.getRelativeY(event.clientY),
+      pressure: event.pressure,
+      tiltX: event.tiltX,
+      tiltY: event.tiltY,
+      twist: event.twist,
+      barrel: (event.buttons & PEN_BARREL_BUTTON) !== 0,
+      eraser: (event.buttons & PEN_ERASER_BUTTON) !== 0 || event.button === 5
+    };
+
+    this.addInputEvent('pen', type, customEvent);
+  }
+
+  private handlePenLeave(event: globalThis.PointerEvent): void {
+    if (event.pointerType === 'pen') {
+      this.penInRange = false;
+    }
+  }
+
   private handleWheel(event: globalThis.WheelEvent): void {
     if (!this.isEnabled || !this.isCapturing) return;
 
@@ -430,6 +573,12 @@ export class InputHandler extends EventEmitter {
     this.addInputEvent('wheel', 'wheel', customEvent);
   }
 
+  private handlePointerLockChange(): void {
+    const locked = this.isPointerLocked();
+    this.logger.info(`Pointer lock ${locked ? 'acquired' : 'released'}`);
+    this.emit('pointerLockChange', locked);
+  }
+
   private handleFocus(event: globalThis.FocusEvent): void {
     this.logger.info('Canvas focused');
   }
@@ -455,35 +604,32 @@ export class InputHandler extends EventEmitter {
     alt: boolean;
     shift: boolean;
     meta: boolean;
+    altGraph: boolean;
   } {
     return {
       ctrl: event.ctrlKey,
       alt: event.altKey,
       shift: event.shiftKey,
-      meta: event.metaKey
+      meta: event.metaKey,
+      altGraph: event.getModifierState('AltGraph')
     };
   }
 
-  private getTouchArray(touchList: globalThis.TouchList): Array<{
-    id: number;
-    x: number;
-    y: number;
-    pressure: number;
-  }> {
-    const touches: Array<{
-      id: number;
-      x: number;
-      y: number;
-      pressure: number;
-    }> = [];
+  private getTouchArray(touchList: globalThis.TouchList): TouchPoint[] {
+    const touches: TouchPoint[] = [];
 
     for (let i = 0; i < touchList.length; i++) {
       const touch = touchList[i];
+      const x = this.getRelativeX(touch.clientX);
+      const y = this.getRelativeY(touch.clientY);
       touches.push({
         id: touch.identifier,
-        x: this.getRelativeX(touch.clientX),
-        y: this.getRelativeY(touch.clientY),
-        pressure: touch.force || 1.0
+        x,
+        y,
+        pressure: touch.force || 1.0,
+        radiusX: this.getRelativeX(touch.clientX + (touch.radiusX || 0)) - x,
+        radiusY: this.getRelativeY(touch.clientY + (touch.radiusY || 0)) - y,
+        rotationAngle: touch.rotationAngle || 0
       });
     }
 
@@ -494,7 +640,7 @@ export class InputHandler extends EventEmitter {
     if (!this.isEnabled || !this.isCapturing) return;
 
     const inputEvent: InputEvent = {
-      type: type as 'mouse' | 'keyboard' | 'touch' | 'wheel',
+      type: type as InputEvent['type'],
       action,
       data,
       timestamp: Date.now()
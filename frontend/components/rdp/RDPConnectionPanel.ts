@@ -59,6 +59,7 @@ export class RDPConnectionPanel {
               <option value="medium" selected>Medium (Balanced)</option>
               <option value="high">High (Quality)</option>
               <option value="ultra">Ultra (Best)</option>
+              <option value="lossless">Lossless (Pixel-exact)</option>
             </select>
           </div>
           
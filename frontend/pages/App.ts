@@ -20,7 +20,7 @@ export interface AppState {
   showFileTransfer: boolean;
   showDeviceRedirection: boolean;
   theme: 'light' | 'dark' | 'auto';
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   connectionCode: string;
   screenShareActive: boolean;
 }
@@ -0,0 +1,182 @@
+import { RDPFrame, TouchContact, PenSample } from './RDPService';
+
+// Client side of the gateway's binary wire format (backend/src/ws/BinaryProtocol.ts).
+// Every message starts with a version byte and a type byte; integers are big-endian.
+
+export const PROTOCOL_VERSION = 1;
+export const BINARY_SUBPROTOCOL = 'rdp.binary.v1';
+export const JSON_SUBPROTOCOL = 'rdp.json.v1';
+
+export enum BinaryMessageType {
+  Frame = 0x01,
+  FrameBatch = 0x03,
+  MouseInput = 0x10,
+  KeyboardInput = 0x11,
+  TouchInput = 0x12,
+  TextInput = 0x13,
+  RelativeMouseInput = 0x14,
+  TouchFrame = 0x15,
+  PenInput = 0x16,
+  Clipboard = 0x20
+}
+
+const POINTER_ACTIONS = ['move', 'down', 'up', 'wheel'];
+const TOUCH_ACTIONS = ['move', 'down', 'up', 'cancel'];
+const TOUCH_CONTACT_SIZE = 17;
+const PEN_ACTIONS = ['move', 'down', 'up', 'hover', 'cancel'];
+const PEN_BARREL = 0x01;
+const PEN_ERASER = 0x02;
+const FRAME_HEADER_SIZE = 24;
+const BATCH_HEADER_SIZE = 4;
+const BATCH_INDEX_ENTRY_SIZE = 4;
+
+const encoder = new TextEncoder();
+const decoder = new TextDecoder();
+
+export class BinaryProtocol {
+  // A single Frame or a FrameBatch, in the order the gateway sent them
+  public static decodeFrames(message: ArrayBuffer): RDPFrame[] {
+    const view = new DataView(message);
+    if (view.getUint8(1) !== BinaryMessageType.FrameBatch) {
+      return [BinaryProtocol.decodeFrame(message)];
+    }
+
+    const count = view.getUint16(2);
+    const frames: RDPFrame[] = [];
+    let offset = BATCH_HEADER_SIZE + count * BATCH_INDEX_ENTRY_SIZE;
+    for (let index = 0; index < count; index++) {
+      const length = view.getUint32(BATCH_HEADER_SIZE + index * BATCH_INDEX_ENTRY_SIZE);
+      frames.push(BinaryProtocol.decodeFrame(message.slice(offset, offset + length)));
+      offset += length;
+    }
+    return frames;
+  }
+
+  public static decodeFrame(message: ArrayBuffer): RDPFrame {
+    const view = new DataView(message);
+
+    const version = view.getUint8(0);
+    if (version !== PROTOCOL_VERSION) {
+      throw new Error(`Unsupported protocol version: ${version}`);
+    }
+    if (view.getUint8(1) !== BinaryMessageType.Frame) {
+      throw new Error(`Unexpected binary message type: ${view.getUint8(1)}`);
+    }
+
+    const codecLength = view.getUint8(3);
+    const payloadOffset = FRAME_HEADER_SIZE + codecLength;
+
+    return {
+      displayId: view.getUint8(2),
+      frameId: view.getUint32(4),
+      x: view.getInt16(8),
+      y: view.getInt16(10),
+      width: view.getUint16(12) || undefined,
+      height: view.getUint16(14) || undefined,
+      timestamp: view.getFloat64(16),
+      codec: decoder.decode(new Uint8Array(message, FRAME_HEADER_SIZE, codecLength)) || undefined,
+      data: message.slice(payloadOffset)
+    };
+  }
+
+  // With a displayId, x and y are normalized (0..65535) within that display
+  public static encodeMouseInput(x: number, y: number, button: number, action: string, wheel: number = 0, displayId?: number): ArrayBuffer {
+    const view = BinaryProtocol.header(BinaryMessageType.MouseInput, displayId === undefined ? 10 : 11);
+    view.setUint16(2, x);
+    view.setUint16(4, y);
+    view.setUint8(6, button);
+    view.setUint8(7, BinaryProtocol.actionCode(action));
+    view.setInt16(8, wheel);
+    if (displayId !== undefined) {
+      view.setUint8(10, displayId);
+    }
+    return view.buffer as ArrayBuffer;
+  }
+
+  public static encodeRelativeMouseInput(deltaX: number, deltaY: number, button: number, action: string): ArrayBuffer {
+    const view = BinaryProtocol.header(BinaryMessageType.RelativeMouseInput, 8);
+    view.setInt16(2, deltaX);
+    view.setInt16(4, deltaY);
+    view.setUint8(6, button);
+    view.setUint8(7, BinaryProtocol.actionCode(action));
+    return view.buffer as ArrayBuffer;
+  }
+
+  public static encodeKeyboardInput(keyCode: number, scanCode: number, flags: number, unicode?: string): ArrayBuffer {
+    const text = unicode ? encoder.encode(unicode) : new Uint8Array(0);
+    const view = BinaryProtocol.header(BinaryMessageType.KeyboardInput, 8 + text.length);
+    view.setUint16(2, keyCode);
+    view.setUint16(4, scanCode);
+    view.setUint16(6, flags);
+    new Uint8Array(view.buffer).set(text, 8);
+    return view.buffer as ArrayBuffer;
+  }
+
+  public static encodeTouchInput(x: number, y: number, pressure: number, action: string): ArrayBuffer {
+    const view = BinaryProtocol.header(BinaryMessageType.TouchInput, 10);
+    view.setUint16(2, x);
+    view.setUint16(4, y);
+    view.setUint8(6, pressure);
+    view.setUint8(7, BinaryProtocol.actionCode(action));
+    return view.buffer as ArrayBuffer;
+  }
+
+  // Contacts are positioned 0..65535 within the display, pressure 0..1024
+  public static encodeTouchFrame(displayId: number, contacts: TouchContact[]): ArrayBuffer {
+    const view = BinaryProtocol.header(BinaryMessageType.TouchFrame, 4 + contacts.length * TOUCH_CONTACT_SIZE);
+    view.setUint8(2, displayId);
+    view.setUint8(3, contacts.length);
+    contacts.forEach((contact, index) => {
+      const offset = 4 + index * TOUCH_CONTACT_SIZE;
+      view.setUint32(offset, contact.id);
+      view.setUint8(offset + 4, Math.max(TOUCH_ACTIONS.indexOf(contact.action), 0));
+      view.setUint16(offset + 5, contact.x);
+      view.setUint16(offset + 7, contact.y);
+      view.setUint16(offset + 9, contact.pressure);
+      view.setUint16(offset + 11, contact.width);
+      view.setUint16(offset + 13, contact.height);
+      view.setUint16(offset + 15, contact.orientation);
+    });
+    return view.buffer as ArrayBuffer;
+  }
+
+  public static encodePenInput(displayId: number, pen: PenSample): ArrayBuffer {
+    const view = BinaryProtocol.header(BinaryMessageType.PenInput, 15);
+    view.setUint8(2, displayId);
+    view.setUint8(3, Math.max(PEN_ACTIONS.indexOf(pen.action), 0));
+    view.setUint16(4, pen.x);
+    view.setUint16(6, pen.y);
+    view.setUint16(8, pen.pressure);
+    view.setInt8(10, pen.tiltX);
+    view.setInt8(11, pen.tiltY);
+    view.setUint16(12, pen.rotation);
+    view.setUint8(14, (pen.barrel ? PEN_BARREL : 0) | (pen.eraser ? PEN_ERASER : 0));
+    return view.buffer as ArrayBuffer;
+  }
+
+  public static encodeTextInput(text: string): ArrayBuffer {
+    const bytes = encoder.encode(text);
+    const view = BinaryProtocol.header(BinaryMessageType.TextInput, 2 + bytes.length);
+    new Uint8Array(view.buffer).set(bytes, 2);
+    return view.buffer as ArrayBuffer;
+  }
+
+  public static encodeClipboard(text: string): ArrayBuffer {
+    const bytes = encoder.encode(text);
+    const view = BinaryProtocol.header(BinaryMessageType.Clipboard, 2 + bytes.length);
+    new Uint8Array(view.buffer).set(bytes, 2);
+    return view.buffer as ArrayBuffer;
+  }
+
+  private static header(type: BinaryMessageType, size: number): DataView {
+    const view = new DataView(new ArrayBuffer(size));
+    view.setUint8(0, PROTOCOL_VERSION);
+    view.setUint8(1, type);
+    return view;
+  }
+
+  private static actionCode(action: string): number {
+    const index = POINTER_ACTIONS.indexOf(action);
+    return index === -1 ? 0 : index;
+  }
+}
@@ -0,0 +1,199 @@
+// Maps KeyboardEvent.code (the physical key, independent of the layout) to
+// the PC/AT set 1 scan code RDP carries, plus the Windows virtual-key code.
+// Scan codes are what the host's own layout interprets, so this one table
+// serves Windows, Linux (xrdp) and macOS hosts alike.
+
+export interface KeyMapping {
+  scanCode: number;
+  virtualKey: number;
+  // E0-prefixed keys (arrows, right-hand modifiers, navigation block, ...)
+  extended?: boolean;
+  // E1-prefixed; only Pause
+  extended1?: boolean;
+}
+
+// Keyboard event flags, as in the RDP slow-path keyboard event
+export const KBDFLAGS_EXTENDED = 0x0100;
+export const KBDFLAGS_EXTENDED1 = 0x0200;
+export const KBDFLAGS_DOWN = 0x4000;
+export const KBDFLAGS_RELEASE = 0x8000;
+
+const key = (scanCode: number, virtualKey: number): KeyMapping => ({ scanCode, virtualKey });
+const extendedKey = (scanCode: number, virtualKey: number): KeyMapping => ({ scanCode, virtualKey, extended: true });
+
+const LETTER_SCAN_CODES = [
+  0x1e, 0x30, 0x2e, 0x20, 0x12, 0x21, 0x22, 0x23, 0x17, 0x24, 0x25, 0x26, 0x32,
+  0x31, 0x18, 0x19, 0x10, 0x13, 0x1f, 0x14, 0x16, 0x2f, 0x11, 0x2d, 0x15, 0x2c
+];
+const F13_TO_F24_SCAN_CODES = [0x64, 0x65, 0x66, 0x67, 0x68, 0x69, 0x6a, 0x6b, 0x6c, 0x6d, 0x6e, 0x76];
+
+const KEY_MAP: Record<string, KeyMapping> = {
+  // Main block
+  Escape: key(0x01, 0x1b),
+  Minus: key(0x0c, 0xbd),
+  Equal: key(0x0d, 0xbb),
+  Backspace: key(0x0e, 0x08),
+  Tab: key(0x0f, 0x09),
+  BracketLeft: key(0x1a, 0xdb),
+  BracketRight: key(0x1b, 0xdd),
+  Enter: key(0x1c, 0x0d),
+  Semicolon: key(0x27, 0xba),
+  Quote: key(0x28, 0xde),
+  Backquote: key(0x29, 0xc0),
+  Backslash: key(0x2b, 0xdc),
+  Comma: key(0x33, 0xbc),
+  Period: key(0x34, 0xbe),
+  Slash: key(0x35, 0xbf),
+  Space: key(0x39, 0x20),
+  CapsLock: key(0x3a, 0x14),
+  IntlBackslash: key(0x56, 0xe2),
+  IntlRo: key(0x73, 0xc1),
+  IntlYen: key(0x7d, 0xdc),
+  KanaMode: key(0x70, 0x15),
+  Convert: key(0x79, 0x1c),
+  NonConvert: key(0x7b, 0x1d),
+
+  // Modifiers
+  ShiftLeft: key(0x2a, 0xa0),
+  ShiftRight: key(0x36, 0xa1),
+  ControlLeft: key(0x1d, 0xa2),
+  ControlRight: extendedKey(0x1d, 0xa3),
+  AltLeft: key(0x38, 0xa4),
+  AltRight: extendedKey(0x38, 0xa5),
+  MetaLeft: extendedKey(0x5b, 0x5b),
+  MetaRight: extendedKey(0x5c, 0x5c),
+  ContextMenu: extendedKey(0x5d, 0x5d),
+
+  // Function row
+  F1: key(0x3b, 0x70),
+  F2: key(0x3c, 0x71),
+  F3: key(0x3d, 0x72),
+  F4: key(0x3e, 0x73),
+  F5: key(0x3f, 0x74),
+  F6: key(0x40, 0x75),
+  F7: key(0x41, 0x76),
+  F8: key(0x42, 0x77),
+  F9: key(0x43, 0x78),
+  F10: key(0x44, 0x79),
+  F11: key(0x57, 0x7a),
+  F12: key(0x58, 0x7b),
+  PrintScreen: extendedKey(0x37, 0x2c),
+  ScrollLock: key(0x46, 0x91),
+  Pause: { scanCode: 0x45, virtualKey: 0x13, extended1: true },
+
+  // Navigation block
+  Insert: extendedKey(0x52, 0x2d),
+  Delete: extendedKey(0x53, 0x2e),
+  Home: extendedKey(0x47, 0x24),
+  End: extendedKey(0x4f, 0x23),
+  PageUp: extendedKey(0x49, 0x21),
+  PageDown: extendedKey(0x51, 0x22),
+  ArrowUp: extendedKey(0x48, 0x26),
+  ArrowDown: extendedKey(0x50, 0x28),
+  ArrowLeft: extendedKey(0x4b, 0x25),
+  ArrowRight: extendedKey(0x4d, 0x27),
+
+  // Numpad
+  NumLock: key(0x45, 0x90),
+  NumpadDivide: extendedKey(0x35, 0x6f),
+  NumpadMultiply: key(0x37, 0x6a),
+  NumpadSubtract: key(0x4a, 0x6d),
+  NumpadAdd: key(0x4e, 0x6b),
+  NumpadEnter: extendedKey(0x1c, 0x0d),
+  NumpadDecimal: key(0x53, 0x6e),
+  NumpadEqual: key(0x59, 0x92),
+  NumpadComma: key(0x7e, 0xc2),
+  Numpad0: key(0x52, 0x60),
+  Numpad1: key(0x4f, 0x61),
+  Numpad2: key(0x50, 0x62),
+  Numpad3: key(0x51, 0x63),
+  Numpad4: key(0x4b, 0x64),
+  Numpad5: key(0x4c, 0x65),
+  Numpad6: key(0x4d, 0x66),
+  Numpad7: key(0x47, 0x67),
+  Numpad8: key(0x48, 0x68),
+  Numpad9: key(0x49, 0x69),
+
+  // Media and browser keys
+  AudioVolumeMute: extendedKey(0x20, 0xad),
+  AudioVolumeDown: extendedKey(0x2e, 0xae),
+  AudioVolumeUp: extendedKey(0x30, 0xaf),
+  MediaTrackNext: extendedKey(0x19, 0xb0),
+  MediaTrackPrevious: extendedKey(0x10, 0xb1),
+  MediaStop: extendedKey(0x24, 0xb2),
+  MediaPlayPause: extendedKey(0x22, 0xb3),
+  LaunchMail: extendedKey(0x6c, 0xb4),
+  MediaSelect: extendedKey(0x6d, 0xb5),
+  LaunchApp1: extendedKey(0x6b, 0xb6),
+  LaunchApp2: extendedKey(0x21, 0xb7),
+  BrowserSearch: extendedKey(0x65, 0xaa),
+  BrowserHome: extendedKey(0x32, 0xac),
+  BrowserBack: extendedKey(0x6a, 0xa6),
+  BrowserForward: extendedKey(0x69, 0xa7),
+  BrowserStop: extendedKey(0x68, 0xa9),
+  BrowserRefresh: extendedKey(0x67, 0xa8),
+  BrowserFavorites: extendedKey(0x66, 0xab),
+  Sleep: extendedKey(0x5f, 0x5f)
+};
+
+LETTER_SCAN_CODES.forEach((scanCode, index) => {
+  const letter = String.fromCharCode(0x41 + index);
+  KEY_MAP[`Key${letter}`] = key(scanCode, 0x41 + index);
+});
+
+// Digit1..Digit9 are 0x02..0x0a, Digit0 follows them
+for (let digit = 0; digit <= 9; digit++) {
+  KEY_MAP[`Digit${digit}`] = key(digit === 0 ? 0x0b : digit + 1, 0x30 + digit);
+}
+
+F13_TO_F24_SCAN_CODES.forEach((scanCode, index) => {
+  KEY_MAP[`F${13 + index}`] = key(scanCode, 0x7c + index);
+});
+
+export function mapKeyCode(code: string): KeyMapping | undefined {
+  return KEY_MAP[code];
+}
+
+// Arrangement of the letter block. Scan codes only type the intended
+// characters when client and host agree on it.
+export type LayoutFamily = 'qwerty' | 'azerty' | 'qwertz' | 'other';
+
+// By the language in the low word of a Windows keyboard layout id (KLID);
+// anything not listed is taken as QWERTY
+const LAYOUT_FAMILIES: Record<number, LayoutFamily> = {
+  0x040c: 'azerty', // French
+  0x080c: 'azerty', // Belgian French
+  0x0813: 'azerty', // Belgian (Dutch)
+  0x0407: 'qwertz', // German
+  0x0807: 'qwertz', // Swiss German
+  0x0c07: 'qwertz', // Austrian German
+  0x100c: 'qwertz', // Swiss French
+  0x0405: 'qwertz', // Czech
+  0x041b: 'qwertz', // Slovak
+  0x040e: 'qwertz', // Hungarian
+  0x0424: 'qwertz', // Slovenian
+  0x041a: 'qwertz' // Croatian
+};
+
+export function layoutFamilyOf(layoutId: number): LayoutFamily {
+  return LAYOUT_FAMILIES[layoutId & 0xffff] ?? 'qwerty';
+}
+
+// The client's own arrangement, from the Keyboard Map API; undefined where
+// the browser does not offer it. Non-Latin layouts come out as 'other'.
+export async function detectLayoutFamily(): Promise<LayoutFamily | undefined> {
+  const keyboard = (navigator as any).keyboard;
+  if (typeof keyboard?.getLayoutMap !== 'function') return undefined;
+
+  try {
+    const layout: Map<string, string> = await keyboard.getLayoutMap();
+    const q = layout.get('KeyQ');
+    const y = layout.get('KeyY');
+    if (!q || !/^[a-z]$/.test(q)) return 'other';
+    if (q === 'a') return 'azerty';
+    if (y === 'z') return 'qwertz';
+    return 'qwerty';
+  } catch {
+    return undefined;
+  }
+}
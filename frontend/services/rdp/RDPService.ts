@@ -1,6 +1,8 @@
 import { EventEmitter } from '../../utils/EventEmitter';
 import { Logger } from '../../utils/Logger';
 import { Config } from '../../utils/Config';
+import { BinaryProtocol, BINARY_SUBPROTOCOL, JSON_SUBPROTOCOL } from './BinaryProtocol';
+import { mapKeyCode, detectLayoutFamily, layoutFamilyOf, LayoutFamily, KBDFLAGS_DOWN, KBDFLAGS_EXTENDED, KBDFLAGS_EXTENDED1, KBDFLAGS_RELEASE } from './KeyboardMap';
 
 export interface RDPConnectionConfig {
   host: string;
@@ -27,7 +29,7 @@ export interface RDPConnectionConfig {
   colorDepth: number;
   width: number;
   height: number;
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   frameRate: number;
   compressionLevel: number;
   encryptionLevel: 'none' | 'low' | 'medium' | 'high';
@@ -35,18 +37,155 @@ export interface RDPConnectionConfig {
   timeout: number;
   reconnectAttempts: number;
   reconnectDelay: number;
+  initialDisplay?: number;
+  viewOnly?: boolean;
+  scaleMode?: ScaleMode;
+  keyboardMode?: KeyboardMode;
+}
+
+export type ScaleMode = 'fit' | 'fill' | 'stretch';
+// 'relative' sends motion deltas, for use while the canvas holds a pointer lock
+export type PointerMode = 'absolute' | 'relative';
+// 'position' sends scan codes for the host's layout to interpret;
+// 'character' types printable keys as Unicode so they come out as on the
+// client; 'auto' uses characters when client and host layouts differ
+export type KeyboardMode = 'auto' | 'position' | 'character';
+// Combinations the local OS keeps from the page; the gateway injects them
+export type KeyCombo = 'ctrl-alt-del' | 'ctrl-shift-esc' | 'win' | 'win-l' | 'alt-tab' | 'alt-f4';
+// What the gateway lets this session do; an admin can change it at any time
+export type SessionPermissions = Record<'mouse' | 'keyboard' | 'clipboard' | 'fileTransfer', boolean>;
+
+// Input recorded by the gateway with its timing; opaque to the client,
+// which stores it and sends it back to replay
+export interface InputMacro {
+  version: number;
+  recordedAt: string;
+  duration: number;
+  events: Array<{ at: number; kind: string; data: any }>;
+}
+
+export interface RDPDisplay {
+  id: number;
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  primary: boolean;
+}
+
+export type DisplaySubscriptionMode = 'single' | 'multiple' | 'virtual';
+
+export interface RDPFrame {
+  frameId?: number;
+  // Registered codec name on the gateway, e.g. 'deflate', 'raw' or 'png'
+  codec?: string;
+  displayId: number;
+  x: number;
+  y: number;
+  width?: number;
+  height?: number;
+  data: ArrayBuffer;
+  timestamp: number;
+}
+
+// One finger in a multi-touch frame: position and contact size normalized to
+// 0..65535 within the display, pressure 0..1024, orientation in degrees
+export interface TouchContact {
+  id: number;
+  action: 'down' | 'move' | 'up' | 'cancel';
+  x: number;
+  y: number;
+  pressure: number;
+  width: number;
+  height: number;
+  orientation: number;
+}
+
+// A stylus sample: position normalized to 0..65535 within the display,
+// pressure 0..1024, tilt -90..90 and rotation 0..359 degrees
+export interface PenSample {
+  action: 'down' | 'move' | 'up' | 'hover' | 'cancel';
+  x: number;
+  y: number;
+  pressure: number;
+  tiltX: number;
+  tiltY: number;
+  rotation: number;
+  barrel: boolean;
+  eraser: boolean;
+}
+
+export interface HostCapabilities {
+  codecs: string[];
+  hardwareEncoders: string[];
+  audio: boolean;
+  touchInput: boolean;
+  clipboard: boolean;
+  fileTransfer: boolean;
+  deviceRedirection: boolean;
+  multiMonitor: boolean;
+  displayCount?: number;
+}
+
+// Agreed with the gateway in the `hello` exchange
+export interface NegotiatedCapabilities {
+  protocolVersion: number;
+  codec: string;
+  codecs: string[];
+  maxWidth: number;
+  maxHeight: number;
+  audio: boolean;
+  clipboard: boolean;
+  fileTransfer: boolean;
+  deviceRedirection: boolean;
+  multiMonitor: boolean;
+}
+
+const CLIENT_PROTOCOL_VERSION = 1;
+// Survives page reloads within the tab, so a reload resumes the session
+const RESUME_STORAGE_KEY = 'rdp.resume';
+// CanvasRenderer decodes binary frame payloads as PNG images
+const SUPPORTED_CODECS = ['png'];
+// Activity without input is reported to the gateway's idle detection at most this often
+const ACTIVITY_REPORT_INTERVAL = 30000; // ms
+
+export interface DisconnectReason {
+  code: 'client_request' | 'admin_kick' | 'idle_timeout' | 'auth_revoked' | 'server_shutdown' | 'taken_over' | 'remote_closed' | 'connection_lost';
+  message: string;
+  reconnect: boolean;
+  detail?: string;
 }
 
 export interface RDPConnection {
   id: string;
-  status: 'connecting' | 'connected' | 'disconnected' | 'error';
+  // 'failed' means the gateway stopped sending heartbeats
+  status: 'connecting' | 'connected' | 'disconnected' | 'error' | 'failed';
   config: RDPConnectionConfig;
+  displays: RDPDisplay[];
+  subscribedDisplayIds: number[];
+  networkLinkType?: 'ethernet' | 'wifi' | 'cellular' | 'unknown';
+  maxBitrate?: number;
+  // Bitrate the gateway has adapted to under congestion
+  targetBitrate?: number;
+  // Delivery rate to this client as measured by the gateway, in bits/s
+  estimatedBandwidth?: number;
+  viewOnly: boolean;
+  permissions?: SessionPermissions;
+  // Viewers watch someone else's session; only the controller sends input
+  role: 'controller' | 'viewer';
+  viewers: string[];
+  scaleMode: ScaleMode;
+  pointerMode: PointerMode;
+  keyboardMode: KeyboardMode;
+  // Windows keyboard layout id active on the host, once it reports one
+  hostKeyboardLayout?: number;
   startTime: Date;
   lastActivity: Date;
   frameCount: number;
   bytesReceived: number;
   bytesSent: number;
   error?: string;
+  disconnectReason?: DisconnectReason;
 }
 
 export class RDPService extends EventEmitter {
@@ -54,10 +193,20 @@ export class RDPService extends EventEmitter {
   private config = Config.getInstance();
   private socket: WebSocket | null = null;
   private connection: RDPConnection | null = null;
+  private capabilities: HostCapabilities | null = null;
+  private negotiated: NegotiatedCapabilities | null = null;
+  // Letter block arrangement of the local keyboard, for the 'auto' keyboard mode
+  private clientLayoutFamily: LayoutFamily | undefined;
+  // Keys whose press went as text; their release must not go as a scan code
+  private textKeys = new Set<string>();
   private reconnectAttempts = 0;
   private reconnectTimer: NodeJS.Timeout | null = null;
-  private frameQueue: ArrayBuffer[] = [];
+  private heartbeatTimer: NodeJS.Timeout | null = null;
+  // How long the gateway asked clients to wait before reconnecting after it shut down
+  private shutdownRetryAfter = 0;
+  private frameQueue: RDPFrame[] = [];
   private isProcessingFrames = false;
+  private lastActivityReport = 0;
 
   constructor() {
     super();
@@ -67,6 +216,9 @@ export class RDPService extends EventEmitter {
   public async connect(rdpConfig: RDPConnectionConfig): Promise<RDPConnection> {
     try {
       this.logger.info(`Connecting to RDP server: ${rdpConfig.host}:${rdpConfig.port}`);
+      detectLayoutFamily().then(family => {
+        this.clientLayoutFamily = family;
+      });
 
       // First authenticate with the gateway
       const authResult = await this.authenticate();
@@ -77,17 +229,29 @@ export class RDPService extends EventEmitter {
       // Connect to WebSocket gateway
       await this.connectWebSocket();
 
-      // Send RDP connection request
-      this.socket!.send(JSON.stringify({
-        type: 'rdp:connect',
-        data: rdpConfig
-      }));
+      // Advertise what this client can handle before asking for a session
+      this.sendHello(rdpConfig);
+
+      // Resume the previous session to this host if the gateway still has it,
+      // otherwise start a new one
+      const resumeToken = this.getResumeToken(rdpConfig);
+      this.socket!.send(JSON.stringify(resumeToken
+        ? { type: 'rdp:resume', data: { resumeToken } }
+        : { type: 'rdp:connect', data: rdpConfig }));
 
       // Create connection object
       this.connection = {
         id: `rdp_${Date.now()}_${Math.random().toString(36).substr(2, 9)}`,
         status: 'connecting',
         config: rdpConfig,
+        displays: [],
+        subscribedDisplayIds: [],
+        viewOnly: rdpConfig.viewOnly === true,
+        role: 'controller',
+        viewers: [],
+        scaleMode: rdpConfig.scaleMode || 'fit',
+        pointerMode: 'absolute',
+        keyboardMode: rdpConfig.keyboardMode || 'auto',
         startTime: new Date(),
         lastActivity: new Date(),
         frameCount: 0,
@@ -104,6 +268,47 @@ export class RDPService extends EventEmitter {
     }
   }
 
+  // Watches a session another client controls; its frames arrive here too,
+  // but input is ignored until control is handed over
+  public async joinSession(sessionId: string, rdpConfig: RDPConnectionConfig): Promise<RDPConnection> {
+    const authResult = await this.authenticate();
+    if (!authResult.success) {
+      throw new Error('Authentication failed');
+    }
+
+    await this.connectWebSocket();
+    this.socket!.send(JSON.stringify({ type: 'session:join', data: { sessionId } }));
+
+    this.connection = {
+      id: sessionId,
+      status: 'connecting',
+      config: rdpConfig,
+      displays: [],
+      subscribedDisplayIds: [],
+      viewOnly: true,
+      role: 'viewer',
+      viewers: [],
+      scaleMode: rdpConfig.scaleMode || 'fit',
+      pointerMode: 'absolute',
+      keyboardMode: rdpConfig.keyboardMode || 'auto',
+      startTime: new Date(),
+      lastActivity: new Date(),
+      frameCount: 0,
+      bytesReceived: 0,
+      bytesSent: 0
+    };
+
+    this.emit('connecting', this.connection);
+    return this.connection;
+  }
+
+  // Hands input control to a viewer (by its connection id from 'viewersChanged')
+  public transferControl(socketId: string): void {
+    if (this.socket && this.connection?.role === 'controller') {
+      this.socket.send(JSON.stringify({ type: 'control:transfer', data: { socketId } }));
+    }
+  }
+
   private async authenticate(): Promise<{ success: boolean; token?: string }> {
     try {
       const response = await fetch(`${this.config.get('api').baseUrl}/auth/login`, {
@@ -134,7 +339,9 @@ export class RDPService extends EventEmitter {
   private async connectWebSocket(): Promise<void> {
     return new Promise((resolve, reject) => {
       const wsUrl = this.config.get('api').wsUrl;
-      this.socket = new WebSocket(wsUrl);
+      // Prefer the binary protocol; gateways without it fall back to JSON
+      this.socket = new WebSocket(wsUrl, [BINARY_SUBPROTOCOL, JSON_SUBPROTOCOL]);
+      this.socket.binaryType = 'arraybuffer';
 
       this.socket.onopen = () => {
         this.logger.info('WebSocket connected');
@@ -157,6 +364,7 @@ export class RDPService extends EventEmitter {
 
       this.socket.onclose = () => {
         this.logger.info('WebSocket disconnected');
+        this.clearHeartbeatWatchdog();
         this.handleDisconnection();
       };
 
@@ -168,32 +376,159 @@ export class RDPService extends EventEmitter {
 
   private handleWebSocketMessage(event: MessageEvent): void {
     try {
+      // Binary messages from the gateway are always frames, maybe batched
+      if (event.data instanceof ArrayBuffer) {
+        BinaryProtocol.decodeFrames(event.data).forEach(frame => this.queueFrame(frame));
+        return;
+      }
+
       const message = JSON.parse(event.data);
       
       switch (message.type) {
+        case 'heartbeat':
+          this.armHeartbeatWatchdog(message.data);
+          break;
         case 'authenticated':
-          this.handleAuthenticationResponse(message);
+          this.handleAuthenticationResponse(message.data);
+          break;
+        case 'hello':
+          this.negotiated = message.data.capabilities;
+          this.emit('negotiated', this.negotiated);
+          break;
+        case 'hello:error':
+          this.logger.error('Capability negotiation failed:', message.data.error);
+          this.emit('error', new Error(message.data.error));
           break;
         case 'rdp:connected':
-          this.handleRDPConnected(message);
+          this.storeResumeToken(message.data?.resumeToken);
+          this.handleRDPConnected(message.data);
+          break;
+        case 'rdp:resumed':
+          this.storeResumeToken(message.data?.resumeToken);
+          this.handleRDPConnected(message.data);
+          this.emit('resumed', this.connection);
+          break;
+        case 'session:joined':
+          this.handleRDPConnected(message.data);
+          break;
+        case 'session:viewers':
+          this.handleViewersChanged(message.data);
+          break;
+        case 'control:granted':
+          this.handleControlChanged('controller', message.data?.resumeToken);
+          break;
+        case 'control:revoked':
+          this.handleControlChanged('viewer');
+          break;
+        case 'session:error':
+          this.logger.error('Session error:', message.data?.error);
+          this.emit('error', new Error(message.data?.error));
+          break;
+        case 'rdp:resumeFailed':
+          this.handleResumeFailed(message);
           break;
         case 'rdp:error':
-          this.handleRDPError(message);
+          this.handleRDPError(message.data);
+          break;
+        case 'server:shutdown':
+          this.handleServerShutdown(message.data);
+          break;
+        case 'rdp:disconnected':
+          this.handleRDPDisconnected(message);
+          break;
+        case 'display:subscribed':
+          this.handleDisplaySubscribed(message.data);
+          break;
+        case 'keyboard:layout':
+          this.handleKeyboardLayout(message.data);
+          break;
+        case 'keyboard:mode:changed':
+          this.handleKeyboardModeChanged(message.data);
+          break;
+        case 'pointer:mode:changed':
+          this.handlePointerModeChanged(message.data);
+          break;
+        case 'session:idle':
+          this.logger.info(`Session idle for ${Math.round((message.data?.idleFor || 0) / 1000)}s (${message.data?.action})`);
+          this.emit('idle', message.data);
+          break;
+        case 'session:active':
+          this.emit('active');
+          break;
+        case 'input:blocked':
+          this.logger.warn(`Key combination blocked by the gateway: ${message.data?.hotkey}`);
+          this.emit('hotkeyBlocked', message.data?.hotkey);
+          break;
+        case 'macro:recorded':
+          this.emit('macroRecorded', message.data?.macro);
+          break;
+        case 'macro:replay:done':
+          this.emit('macroReplayFinished', { cancelled: message.data?.cancelled === true });
+          break;
+        case 'input:drag:done':
+          this.emit('dragCompleted', { cancelled: message.data?.cancelled === true });
+          break;
+        case 'permissions:changed':
+          this.handlePermissionsChanged(message.data);
+          break;
+        case 'display:changed':
+          this.handleDisplayChanged(message.data);
           break;
         case 'frame':
-          this.handleFrame(message);
+          this.handleFrame(message.data);
+          break;
+        case 'frames':
+          (message.data?.frames || []).forEach((frame: any) => this.handleFrame(frame));
           break;
         case 'clipboard:data':
           this.handleClipboardData(message);
           break;
+        case 'clipboard:conflict':
+          this.emit('clipboardConflict', message.data);
+          break;
         case 'file:data':
           this.handleFileData(message);
           break;
+        case 'file:offer':
+          this.emit('fileOffered', message.data);
+          break;
+        case 'file:offerResolved':
+          this.emit('fileOfferResolved', message.data);
+          break;
+        case 'webrtc:iceServers':
+          this.emit('iceServersUpdated', message.data);
+          break;
+        case 'audio:config':
+          this.emit('audioConfig', message.data);
+          break;
+        case 'audio:packet':
+          this.emit('audioPacket', {
+            sequence: message.data.sequence,
+            data: this.base64ToArrayBuffer(message.data.data),
+            timestamp: message.data.timestamp
+          });
+          break;
+        case 'thumbnail':
+          this.emit('thumbnail', message.data);
+          break;
+        case 'thumbnails:subscribed':
+          this.emit('thumbnailsSubscribed', message.data);
+          break;
+        case 'quality:adapted':
+          if (this.connection) {
+            this.connection.targetBitrate = message.data.bitrate;
+            this.connection.estimatedBandwidth = message.data.estimatedBandwidth;
+          }
+          this.emit('qualityAdapted', message.data);
+          break;
+        case 'metrics:backpressure':
+          this.emit('backpressure', message.data);
+          break;
         case 'device:data':
           this.handleDeviceData(message);
           break;
         case 'error':
-          this.handleError(message);
+          this.handleError(message.data);
           break;
         default:
           this.logger.warn('Unknown message type:', message.type);
@@ -203,18 +538,65 @@ export class RDPService extends EventEmitter {
     }
   }
 
-  private handleAuthenticationResponse(message: any): void {
-    if (message.success) {
+  private handleAuthenticationResponse(data: any): void {
+    if (data.success) {
       this.logger.info('WebSocket authenticated successfully');
+      this.updateCapabilities(data.capabilities);
     } else {
-      this.logger.error('WebSocket authentication failed:', message.error);
+      this.logger.error('WebSocket authentication failed:', data.error);
       this.emit('error', new Error('WebSocket authentication failed'));
     }
   }
 
-  private handleRDPConnected(message: any): void {
+  private updateCapabilities(capabilities?: HostCapabilities): void {
+    if (!capabilities) return;
+
+    this.capabilities = capabilities;
+    this.emit('capabilities', capabilities);
+  }
+
+  public getCapabilities(): HostCapabilities | null {
+    return this.capabilities;
+  }
+
+  public getNegotiatedCapabilities(): NegotiatedCapabilities | null {
+    return this.negotiated;
+  }
+
+  private sendHello(rdpConfig: RDPConnectionConfig): void {
+    const pixelRatio = window.devicePixelRatio || 1;
+
+    this.socket!.send(JSON.stringify({
+      type: 'hello',
+      data: {
+        protocolVersion: CLIENT_PROTOCOL_VERSION,
+        codecs: SUPPORTED_CODECS,
+        maxWidth: Math.round(window.screen.width * pixelRatio),
+        maxHeight: Math.round(window.screen.height * pixelRatio),
+        features: {
+          audio: rdpConfig.enableAudio,
+          clipboard: rdpConfig.enableClipboard,
+          fileTransfer: rdpConfig.enableFileTransfer,
+          deviceRedirection: rdpConfig.enableDeviceRedirection,
+          multiMonitor: rdpConfig.enableMultiMonitor
+        }
+      }
+    }));
+  }
+
+  private handleRDPConnected(data: any): void {
     if (this.connection) {
       this.connection.status = 'connected';
+      this.connection.displays = data.displays || [];
+      this.connection.subscribedDisplayIds = data.displaySubscription?.displayIds || [];
+      this.connection.networkLinkType = data.networkLink?.type || 'unknown';
+      this.connection.maxBitrate = data.maxBitrate;
+      this.connection.viewOnly = data.viewOnly === true;
+      this.connection.permissions = data.permissions;
+      this.connection.scaleMode = data.scaleMode || this.connection.scaleMode;
+      this.connection.keyboardMode = data.keyboardMode || this.connection.keyboardMode;
+      this.connection.hostKeyboardLayout = data.hostKeyboardLayout;
+      this.updateCapabilities(data.capabilities);
       this.connection.lastActivity = new Date();
       this.reconnectAttempts = 0;
       
@@ -223,24 +605,109 @@ export class RDPService extends EventEmitter {
     }
   }
 
-  private handleRDPError(message: any): void {
+  private handleViewersChanged(data: any): void {
+    if (!this.connection) return;
+
+    this.connection.viewers = data.viewers || [];
+    this.emit('viewersChanged', { controller: data.controller, viewers: this.connection.viewers });
+  }
+
+  private handleControlChanged(role: 'controller' | 'viewer', resumeToken?: string): void {
+    if (!this.connection) return;
+
+    this.connection.role = role;
+    this.connection.viewOnly = role === 'viewer';
+    // Only the controller can resume the session after a dropout
+    this.storeResumeToken(role === 'controller' ? resumeToken : undefined);
+
+    this.logger.info(role === 'controller' ? 'Input control granted' : 'Input control handed over');
+    this.emit('controlChanged', role);
+  }
+
+  private handleRDPError(data: any): void {
     if (this.connection) {
       this.connection.status = 'error';
-      this.connection.error = message.error;
+      this.connection.error = data.error;
       
-      this.logger.error('RDP connection error:', message.error);
-      this.emit('error', new Error(message.error));
+      this.logger.error('RDP connection error:', data.error);
+      this.emit('error', new Error(data.error));
     }
   }
 
-  private handleFrame(message: any): void {
+  private handleDisplaySubscribed(data: any): void {
+    if (this.connection) {
+      this.connection.subscribedDisplayIds = data.subscription.displayIds;
+      this.emit('displaysChanged', {
+        mode: data.subscription.mode,
+        displays: data.displays
+      });
+    }
+  }
+
+  private handleKeyboardLayout(data: any): void {
+    if (this.connection) {
+      this.connection.hostKeyboardLayout = data.layout;
+      this.emit('keyboardLayoutChanged', data.layout);
+    }
+  }
+
+  private handlePermissionsChanged(data: any): void {
+    if (this.connection) {
+      this.connection.permissions = data.permissions;
+      this.emit('permissionsChanged', data.permissions);
+    }
+  }
+
+  private handleKeyboardModeChanged(data: any): void {
+    if (this.connection) {
+      this.connection.keyboardMode = data.mode;
+      this.emit('keyboardModeChanged', data.mode);
+    }
+  }
+
+  private handlePointerModeChanged(data: any): void {
+    if (this.connection) {
+      this.connection.pointerMode = data.mode;
+      this.emit('pointerModeChanged', data.mode);
+    }
+  }
+
+  private handleDisplayChanged(data: any): void {
+    if (this.connection) {
+      this.connection.displays = data.displays || [];
+      if (data.displaySubscription) {
+        this.connection.subscribedDisplayIds = data.displaySubscription.displayIds;
+      }
+      // Frames queued before the change have the old dimensions
+      this.frameQueue = [];
+      this.emit('displayChanged', {
+        displays: this.connection.displays,
+        reason: data.reason
+      });
+    }
+  }
+
+  private handleFrame(frame: any): void {
+    // Convert base64 frame data to ArrayBuffer
+    this.queueFrame({
+      frameId: frame.frameId,
+      codec: frame.codec,
+      displayId: frame.displayId || 0,
+      x: frame.x || 0,
+      y: frame.y || 0,
+      width: frame.width,
+      height: frame.height,
+      data: this.base64ToArrayBuffer(frame.data),
+      timestamp: frame.timestamp || Date.now()
+    });
+  }
+
+  private queueFrame(frame: RDPFrame): void {
     if (this.connection) {
       this.connection.frameCount++;
       this.connection.lastActivity = new Date();
-      
-      // Convert base64 frame data to ArrayBuffer
-      const frameData = this.base64ToArrayBuffer(message.data);
-      this.frameQueue.push(frameData);
+
+      this.frameQueue.push(frame);
       
       // Process frames asynchronously
       if (!this.isProcessingFrames) {
@@ -256,6 +723,7 @@ export class RDPService extends EventEmitter {
       const frame = this.frameQueue.shift();
       if (frame) {
         this.emit('frame', frame);
+        this.acknowledgeFrame(frame);
       }
       
       // Allow other tasks to run
@@ -265,6 +733,16 @@ export class RDPService extends EventEmitter {
     this.isProcessingFrames = false;
   }
 
+  // Lets the gateway measure capture-to-display latency
+  private acknowledgeFrame(frame: RDPFrame): void {
+    if (this.socket && frame.frameId !== undefined) {
+      this.socket.send(JSON.stringify({
+        type: 'frame:ack',
+        data: { frameId: frame.frameId }
+      }));
+    }
+  }
+
   private handleClipboardData(message: any): void {
     this.emit('clipboardData', message.data);
   }
@@ -277,21 +755,133 @@ export class RDPService extends EventEmitter {
     this.emit('deviceData', message.data);
   }
 
-  private handleError(message: any): void {
-    this.logger.error('Server error:', message.error);
-    this.emit('error', new Error(message.error));
+  private handleError(data: any): void {
+    this.logger.error('Server error:', data.error);
+    this.emit('error', new Error(data.error));
+  }
+
+  private handleResumeFailed(message: any): void {
+    this.logger.info('Session could not be resumed, starting a new one:', message.data?.error);
+    this.storeResumeToken(undefined);
+
+    if (this.socket && this.connection) {
+      this.socket.send(JSON.stringify({
+        type: 'rdp:connect',
+        data: this.connection.config
+      }));
+    }
+  }
+
+  private getResumeToken(rdpConfig: RDPConnectionConfig): string | null {
+    try {
+      const stored = JSON.parse(sessionStorage.getItem(RESUME_STORAGE_KEY) || 'null');
+      const sameTarget = stored
+        && stored.host === rdpConfig.host
+        && stored.port === rdpConfig.port
+        && stored.username === rdpConfig.username;
+      return sameTarget ? stored.token : null;
+    } catch (error) {
+      return null;
+    }
+  }
+
+  private storeResumeToken(token?: string): void {
+    const config = this.connection?.config;
+    if (!token || !config) {
+      sessionStorage.removeItem(RESUME_STORAGE_KEY);
+      return;
+    }
+
+    sessionStorage.setItem(RESUME_STORAGE_KEY, JSON.stringify({
+      token,
+      host: config.host,
+      port: config.port,
+      username: config.username
+    }));
+  }
+
+  private handleRDPDisconnected(message: any): void {
+    if (!this.connection) return;
+
+    // The server ended the session, so there is nothing left to resume
+    this.storeResumeToken(undefined);
+    // Already reported by the shutdown notice
+    if (this.connection.disconnectReason?.code === 'server_shutdown') return;
+
+    this.connection.status = 'disconnected';
+    this.connection.lastActivity = new Date();
+    this.connection.disconnectReason = message.data;
+
+    this.logger.info(`RDP session ended by server (${message.data?.code})`);
+    this.emit('disconnected', this.connection);
+  }
+
+  // Sent before the gateway closes its sockets, so the user sees that the
+  // host went away rather than a generic connection error
+  private handleServerShutdown(data: any): void {
+    const hint = data?.reconnectHint || {};
+    this.shutdownRetryAfter = hint.reconnect ? hint.retryAfter || 0 : 0;
+    this.logger.info(`Gateway is shutting down (${data?.reason})`);
+    this.emit('serverShutdown', data);
+
+    if (!this.connection || this.connection.status === 'disconnected') return;
+
+    this.connection.status = 'disconnected';
+    this.connection.lastActivity = new Date();
+    this.connection.disconnectReason = {
+      code: 'server_shutdown',
+      message: data?.message || 'The gateway is shutting down',
+      reconnect: !!hint.reconnect
+    };
+    this.emit('disconnected', this.connection);
+  }
+
+  // Browsers cannot see WebSocket pings, so the gateway's heartbeat messages
+  // are the only way to notice a connection that died without closing
+  private armHeartbeatWatchdog(heartbeat: { interval: number; misses: number }): void {
+    this.clearHeartbeatWatchdog();
+    this.heartbeatTimer = setTimeout(() => {
+      this.heartbeatTimer = null;
+      this.logger.warn(`No heartbeat from the gateway for ${heartbeat.misses} intervals, closing the connection`);
+
+      if (this.connection) {
+        this.connection.status = 'failed';
+        this.connection.lastActivity = new Date();
+        this.emit('connectionFailed', this.connection);
+      }
+      // onclose may take until the TCP timeout on a dead link, so do not wait for it
+      const socket = this.socket;
+      this.socket = null;
+      if (socket) {
+        socket.onclose = null;
+        socket.close();
+      }
+      this.handleDisconnection();
+    }, heartbeat.interval * heartbeat.misses);
+  }
+
+  private clearHeartbeatWatchdog(): void {
+    if (this.heartbeatTimer) {
+      clearTimeout(this.heartbeatTimer);
+      this.heartbeatTimer = null;
+    }
   }
 
   private handleDisconnection(): void {
     if (this.connection) {
-      this.connection.status = 'disconnected';
-      this.connection.lastActivity = new Date();
-      
-      this.logger.info('RDP connection lost');
-      this.emit('disconnected', this.connection);
+      const reason = this.connection.disconnectReason;
+
+      // A server-initiated disconnect or a heartbeat failure has already been reported
+      if (!reason && this.connection.status !== 'failed') {
+        this.connection.status = 'disconnected';
+        this.connection.lastActivity = new Date();
+
+        this.logger.info('RDP connection lost');
+        this.emit('disconnected', this.connection);
+      }
       
-      // Attempt reconnection if configured
-      if (this.reconnectAttempts < this.connection.config.reconnectAttempts) {
+      // Attempt reconnection if configured and the server did not rule it out
+      if ((!reason || reason.reconnect) && this.reconnectAttempts < this.connection.config.reconnectAttempts) {
         this.scheduleReconnection();
       }
     }
@@ -299,7 +889,9 @@ export class RDPService extends EventEmitter {
 
   private scheduleReconnection(): void {
     this.reconnectAttempts++;
-    const delay = this.connection!.config.reconnectDelay * this.reconnectAttempts;
+    // A restarting gateway needs a moment before it accepts connections again
+    const delay = Math.max(this.connection!.config.reconnectDelay * this.reconnectAttempts, this.shutdownRetryAfter);
+    this.shutdownRetryAfter = 0;
     
     this.logger.info(`Scheduling reconnection attempt ${this.reconnectAttempts} in ${delay}ms`);
     
@@ -316,7 +908,12 @@ export class RDPService extends EventEmitter {
         this.connection.status = 'connecting';
         this.emit('reconnecting', this.connection);
         
-        await this.connect(this.connection.config);
+        // Viewers rejoin the session they watched rather than opening their own
+        if (this.connection.role === 'viewer') {
+          await this.joinSession(this.connection.id, this.connection.config);
+        } else {
+          await this.connect(this.connection.config);
+        }
       }
     } catch (error) {
       this.logger.error('Reconnection failed:', error);
@@ -324,8 +921,16 @@ export class RDPService extends EventEmitter {
     }
   }
 
+  private get isBinaryProtocol(): boolean {
+    return this.socket?.protocol === BINARY_SUBPROTOCOL;
+  }
+
   public sendMouseInput(x: number, y: number, button: number, action: string): void {
     if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodeMouseInput(x, y, button, action));
+        return;
+      }
       this.socket.send(JSON.stringify({
         type: 'input:mouse',
         data: { x, y, button, action }
@@ -333,8 +938,104 @@ export class RDPService extends EventEmitter {
     }
   }
 
+  // Pointer position on one remote display, normalized to 0..65535 on each
+  // axis; the gateway maps it onto the host's multi-monitor desktop
+  public sendPointerInput(displayId: number, x: number, y: number, button: number, action: string, wheel: number = 0): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodeMouseInput(x, y, button, action, wheel, displayId));
+        return;
+      }
+      this.socket.send(JSON.stringify({
+        type: 'input:mouse',
+        data: { displayId, x, y, button, action, wheel }
+      }));
+    }
+  }
+
+  // The gateway presses at `from`, moves to `to` at a human pace and releases,
+  // so applications with a drag threshold see a real drag. Points are
+  // normalized (0..65535) within the display; 'dragCompleted' follows.
+  public sendDrag(displayId: number, from: { x: number; y: number }, to: { x: number; y: number }, button: number = 0, duration?: number): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'input:drag',
+        data: { displayId, from, to, button, duration }
+      }));
+    }
+  }
+
+  // The user is at the client but not sending input, e.g. reading or using
+  // the client's own controls; keeps the session from going idle
+  public reportActivity(): void {
+    const now = Date.now();
+    if (now - this.lastActivityReport < ACTIVITY_REPORT_INTERVAL) return;
+
+    if (this.socket && this.connection?.status === 'connected') {
+      this.lastActivityReport = now;
+      this.socket.send(JSON.stringify({
+        type: 'client:activity'
+      }));
+    }
+  }
+
+  // Everything sent from here on is recorded until stopMacroRecording, which
+  // is answered with 'macroRecorded'
+  public startMacroRecording(): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'macro:record:start'
+      }));
+    }
+  }
+
+  public stopMacroRecording(): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'macro:record:stop'
+      }));
+    }
+  }
+
+  // `speed` scales the recorded timing, e.g. 2 plays twice as fast
+  public replayMacro(macro: InputMacro, speed: number = 1): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'macro:replay',
+        data: { macro, speed }
+      }));
+    }
+  }
+
+  public stopMacroReplay(): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'macro:replay:stop'
+      }));
+    }
+  }
+
+  // Pointer motion while the pointer is locked; only applied by the gateway
+  // once the session is in relative mode
+  public sendRelativePointerInput(deltaX: number, deltaY: number, button: number, action: string): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodeRelativeMouseInput(deltaX, deltaY, button, action));
+        return;
+      }
+      this.socket.send(JSON.stringify({
+        type: 'input:mouse',
+        data: { deltaX, deltaY, button, action }
+      }));
+    }
+  }
+
   public sendKeyboardInput(keyCode: number, scanCode: number, flags: number, unicode?: string): void {
     if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodeKeyboardInput(keyCode, scanCode, flags, unicode));
+        return;
+      }
       this.socket.send(JSON.stringify({
         type: 'input:keyboard',
         data: { keyCode, scanCode, flags, unicode }
@@ -342,8 +1043,86 @@ export class RDPService extends EventEmitter {
     }
   }
 
+  // Sends a physical key by its KeyboardEvent.code; returns false for keys
+  // with no scan code
+  public sendKeyEvent(code: string, pressed: boolean): boolean {
+    const mapping = mapKeyCode(code);
+    if (!mapping) {
+      this.logger.debug(`No scan code for key ${code}`);
+      return false;
+    }
+
+    const state = pressed ? KBDFLAGS_DOWN : KBDFLAGS_RELEASE;
+    if (mapping.extended1) {
+      // Pause is sent as E1-prefixed Ctrl followed by NumLock
+      this.sendKeyboardInput(mapping.virtualKey, 0x1d, state | KBDFLAGS_EXTENDED1);
+    }
+    this.sendKeyboardInput(mapping.virtualKey, mapping.scanCode, state | (mapping.extended ? KBDFLAGS_EXTENDED : 0));
+    return true;
+  }
+
+  // Routes a key by the session's keyboard mode. Shortcuts (with Ctrl, Alt or
+  // Meta held) and non-printable keys always go by position.
+  public sendKey(code: string, key: string, pressed: boolean, shortcut: boolean): void {
+    if (!pressed && this.textKeys.delete(code)) return;
+
+    const printable = Array.from(key || '').length === 1;
+    if (this.usesCharacterInput() && !shortcut) {
+      // A dead key only starts a composition; the composed character follows
+      if (key === 'Dead') return;
+      if (printable && pressed) {
+        this.textKeys.add(code);
+        this.sendTextInput(key);
+        return;
+      }
+    }
+
+    const sent = this.sendKeyEvent(code, pressed);
+    // Keys with no scan code (e.g. on virtual keyboards) still type their character
+    if (!sent && pressed && printable) {
+      this.sendTextInput(key);
+    }
+  }
+
+  public usesCharacterInput(): boolean {
+    const connection = this.connection;
+    if (!connection) return false;
+    if (connection.keyboardMode !== 'auto') return connection.keyboardMode === 'character';
+
+    // Without both layouts there is nothing to compare; scan codes are the default
+    if (connection.hostKeyboardLayout === undefined || !this.clientLayoutFamily) return false;
+    return layoutFamilyOf(connection.hostKeyboardLayout) !== this.clientLayoutFamily;
+  }
+
+  public setKeyboardMode(mode: KeyboardMode): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'keyboard:mode',
+        data: { mode }
+      }));
+    }
+  }
+
+  // Types arbitrary Unicode on the host, independent of its keyboard layout
+  public sendTextInput(text: string): void {
+    if (this.socket && this.connection?.status === 'connected' && text) {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodeTextInput(text));
+        return;
+      }
+      this.socket.send(JSON.stringify({
+        type: 'input:text',
+        data: { text }
+      }));
+    }
+  }
+
   public sendTouchInput(x: number, y: number, pressure: number, action: string): void {
     if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodeTouchInput(x, y, pressure, action));
+        return;
+      }
       this.socket.send(JSON.stringify({
         type: 'input:touch',
         data: { x, y, pressure, action }
@@ -351,8 +1130,40 @@ export class RDPService extends EventEmitter {
     }
   }
 
+  // Sends every finger currently on the screen, so the host sees gestures
+  // such as pinch and two-finger scroll as one multi-touch stream
+  public sendTouchFrame(displayId: number, contacts: TouchContact[]): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodeTouchFrame(displayId, contacts));
+        return;
+      }
+      this.socket.send(JSON.stringify({
+        type: 'input:touch',
+        data: { displayId, contacts }
+      }));
+    }
+  }
+
+  public sendPenInput(displayId: number, pen: PenSample): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol) {
+        this.socket.send(BinaryProtocol.encodePenInput(displayId, pen));
+        return;
+      }
+      this.socket.send(JSON.stringify({
+        type: 'input:pen',
+        data: { displayId, ...pen }
+      }));
+    }
+  }
+
   public setClipboard(data: any): void {
     if (this.socket && this.connection?.status === 'connected') {
+      if (this.isBinaryProtocol && typeof data?.text === 'string') {
+        this.socket.send(BinaryProtocol.encodeClipboard(data.text));
+        return;
+      }
       this.socket.send(JSON.stringify({
         type: 'clipboard:set',
         data
@@ -396,6 +1207,24 @@ export class RDPService extends EventEmitter {
     }
   }
 
+  public acceptFileOffer(offerId: string): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'file:offer:accept',
+        data: { offerId }
+      }));
+    }
+  }
+
+  public declineFileOffer(offerId: string): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'file:offer:decline',
+        data: { offerId }
+      }));
+    }
+  }
+
   public connectDevice(deviceType: string, deviceName: string, deviceData: any): void {
     if (this.socket && this.connection?.status === 'connected') {
       this.socket.send(JSON.stringify({
@@ -445,16 +1274,84 @@ export class RDPService extends EventEmitter {
     }
   }
 
+  public requestResize(width: number, height: number, displayId: number = 0): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'display:resize',
+        data: { width, height, displayId }
+      }));
+    }
+  }
+
+  // Asks the gateway for a full frame, e.g. after a frame failed to decode
+  public requestRefresh(displayIds?: number[]): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'frame:refresh',
+        data: { displayIds }
+      }));
+    }
+  }
+
+  // Thumbnails can be watched for any accessible session, so no RDP connection is required
+  public subscribeThumbnails(sessionId: string): void {
+    if (this.socket) {
+      this.socket.send(JSON.stringify({
+        type: 'thumbnails:subscribe',
+        data: { sessionId }
+      }));
+    }
+  }
+
+  public unsubscribeThumbnails(sessionId: string): void {
+    if (this.socket) {
+      this.socket.send(JSON.stringify({
+        type: 'thumbnails:unsubscribe',
+        data: { sessionId }
+      }));
+    }
+  }
+
+  public subscribeDisplays(mode: DisplaySubscriptionMode, displayIds: number[] = []): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'display:subscribe',
+        data: { mode, displayIds }
+      }));
+    }
+  }
+
+  public sendKeyCombo(combo: KeyCombo): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'input:combo',
+        data: { combo }
+      }));
+    }
+  }
+
+  public setPointerMode(mode: PointerMode): void {
+    if (this.socket && this.connection?.status === 'connected') {
+      this.socket.send(JSON.stringify({
+        type: 'pointer:mode',
+        data: { mode }
+      }));
+    }
+  }
+
   public disconnect(): void {
     if (this.socket) {
       this.socket.send(JSON.stringify({
         type: 'rdp:disconnect'
       }));
       
-      this.socket.close();
+      this.socket.close(1000);
       this.socket = null;
     }
 
+    this.clearHeartbeatWatchdog();
+    this.storeResumeToken(undefined);
+
     if (this.reconnectTimer) {
       clearTimeout(this.reconnectTimer);
       this.reconnectTimer = null;
@@ -8,10 +8,13 @@ export interface WebRTCOptions {
   port: number;
   secure: boolean;
   token?: string;
+  // The RDP session this connection belongs to, which is also its signaling
+  // room; the gateway admits only users who may join that session
+  room: string;
   iceServers: RTCIceServer[];
   maxBitrate: number;
   maxFramerate: number;
-  quality?: 'low' | 'medium' | 'high' | 'ultra';
+  quality?: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   enableAudio?: boolean;
   enableVideo?: boolean;
 }
@@ -34,6 +37,19 @@ export interface DataChannelMessage {
   timestamp: number;
 }
 
+const CHANNEL_PRIORITIES: Record<string, string> = {
+  input: 'high',
+  control: 'high',
+  clipboard: 'medium',
+  metrics: 'low',
+  file: 'very-low'
+};
+
+// ICE 'disconnected' often recovers by itself; restart only if it lasts this long
+const ICE_DISCONNECT_GRACE = 3000;
+// ICE restarts tried before the peer connection is rebuilt from scratch
+const MAX_ICE_RESTARTS = 3;
+
 export class WebRTCService extends EventEmitter {
   private logger = new Logger('WebRTCService');
   
@@ -41,11 +57,23 @@ export class WebRTCService extends EventEmitter {
   private dataChannels: Map<string, RTCDataChannel> = new Map();
   private mediaStream: MediaStream | null = null;
   private signalingSocket: WebSocket | null = null;
+  // Remote candidates that arrive before the remote description is applied
+  private pendingCandidates: RTCIceCandidateInit[] = [];
+  // Our id in the signaling room; kept when only the signaling socket is
+  // reopened, so the remote peer's messages still reach us
+  private peerId: string | null = null;
+  // Perfect negotiation: whether an offer of ours is in flight, and whether
+  // the last remote offer lost a collision and was ignored
+  private makingOffer = false;
+  private ignoreOffer = false;
   
   private connectionOptions: WebRTCOptions | null = null;
   private isConnected = false;
   private reconnectAttempts = 0;
   private maxReconnectAttempts = 5;
+  private iceRestartAttempts = 0;
+  private iceDisconnectTimer: NodeJS.Timeout | null = null;
+  private networkListenersAttached = false;
   
   private stats: WebRTCStats = {
     bytesReceived: 0,
@@ -63,6 +91,9 @@ export class WebRTCService extends EventEmitter {
     try {
       this.logger.info('Initializing WebRTC connection', options);
       this.connectionOptions = options;
+
+      // A reconnect replaces the previous peer connection and signaling socket
+      this.closeConnection();
       
       // Create peer connection
       this.peerConnection = new RTCPeerConnection({
@@ -72,6 +103,7 @@ export class WebRTCService extends EventEmitter {
       
       // Setup event handlers
       this.setupPeerConnectionHandlers();
+      this.attachNetworkListeners();
       
       // Connect to signaling server
       await this.connectSignalingServer();
@@ -95,26 +127,9 @@ export class WebRTCService extends EventEmitter {
 
   disconnect(): void {
     this.logger.info('Disconnecting WebRTC');
-    
-    // Close data channels
-    this.dataChannels.forEach(channel => {
-      if (channel.readyState === 'open') {
-        channel.close();
-      }
-    });
-    this.dataChannels.clear();
-    
-    // Close peer connection
-    if (this.peerConnection) {
-      this.peerConnection.close();
-      this.peerConnection = null;
-    }
-    
-    // Close signaling socket
-    if (this.signalingSocket) {
-      this.signalingSocket.close();
-      this.signalingSocket = null;
-    }
+
+    this.closeConnection();
+    this.detachNetworkListeners();
     
     // Stop media stream
     if (this.mediaStream) {
@@ -181,7 +196,7 @@ export class WebRTCService extends EventEmitter {
     });
   }
 
-  updateQuality(quality: 'low' | 'medium' | 'high' | 'ultra'): void {
+  updateQuality(quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless'): void {
     if (!this.connectionOptions) return;
     
     this.connectionOptions.quality = quality;
@@ -197,15 +212,103 @@ export class WebRTCService extends EventEmitter {
     }
   }
 
+  updateIceServers(iceServers: RTCIceServer[], restartIce: boolean = false): void {
+    // New peer connections pick these up from the connection options
+    if (this.connectionOptions) {
+      this.connectionOptions.iceServers = iceServers;
+    }
+
+    if (!this.peerConnection) return;
+
+    try {
+      this.peerConnection.setConfiguration({
+        ...this.peerConnection.getConfiguration(),
+        iceServers
+      });
+      this.logger.info(`ICE servers updated (${iceServers.length} servers)`);
+
+      if (restartIce && this.peerConnection.connectionState !== 'closed') {
+        this.logger.info('Restarting ICE with updated servers');
+        this.peerConnection.restartIce();
+      }
+    } catch (error) {
+      this.logger.error('Failed to update ICE servers', error);
+    }
+  }
+
   getStats(): WebRTCStats | null {
     if (!this.isConnected) return null;
     return { ...this.stats };
   }
 
+  // Tears down the peer connection and signaling socket without triggering
+  // the reconnect path from their close handlers
+  private closeConnection(): void {
+    this.clearIceDisconnectTimer();
+    this.iceRestartAttempts = 0;
+
+    // Close data channels
+    this.dataChannels.forEach(channel => {
+      channel.onclose = null;
+      if (channel.readyState === 'open') {
+        channel.close();
+      }
+    });
+    this.dataChannels.clear();
+    
+    // Close peer connection
+    if (this.peerConnection) {
+      this.peerConnection.oniceconnectionstatechange = null;
+      this.peerConnection.onconnectionstatechange = null;
+      this.peerConnection.onicecandidate = null;
+      this.peerConnection.onnegotiationneeded = null;
+      this.peerConnection.close();
+      this.peerConnection = null;
+    }
+    
+    // Close signaling socket
+    if (this.signalingSocket) {
+      this.signalingSocket.onclose = null;
+      this.signalingSocket.close();
+      this.signalingSocket = null;
+    }
+
+    this.pendingCandidates = [];
+    this.peerId = null;
+    this.makingOffer = false;
+    this.ignoreOffer = false;
+  }
+
+  private async createOffer(): Promise<void> {
+    if (!this.peerConnection) return;
+
+    try {
+      this.makingOffer = true;
+      await this.peerConnection.setLocalDescription();
+
+      this.sendSignalingMessage({
+        type: 'offer',
+        offer: this.peerConnection.localDescription
+      });
+    } catch (error) {
+      this.logger.error('Failed to create offer', error);
+      throw error;
+    } finally {
+      this.makingOffer = false;
+    }
+  }
+
+  // Both ends run the same code, so roles come from comparing peer ids: the
+  // polite peer yields on an offer collision, the impolite one keeps its offer
+  private isPolite(remotePeerId?: string): boolean {
+    if (!this.peerId || !remotePeerId) return true;
+    return this.peerId > remotePeerId;
+  }
+
   private async connectSignalingServer(): Promise<void> {
-    const { host, port, secure, token } = this.connectionOptions!;
+    const { host, port, secure, token, room } = this.connectionOptions!;
     const protocol = secure ? 'wss' : 'ws';
-    const url = `${protocol}://${host}:${port}/webrtc-signaling`;
+    const url = `${protocol}://${host}:${port}/webrtc-signaling?room=${encodeURIComponent(room)}`;
     
     return new Promise((resolve, reject) => {
       this.signalingSocket = new WebSocket(url);
@@ -213,13 +316,13 @@ export class WebRTCService extends EventEmitter {
       this.signalingSocket.onopen = () => {
         this.logger.info('Signaling connection established');
         
-        // Send authentication
-        if (token) {
-          this.signalingSocket!.send(JSON.stringify({
-            type: 'auth',
-            token
-          }));
-        }
+        // Send authentication; without a token the gateway may allow anonymous
+        // access. A known peer id asks to rejoin under it.
+        this.signalingSocket!.send(JSON.stringify({
+          type: 'auth',
+          token,
+          peerId: this.peerId ?? undefined
+        }));
         
         resolve();
       };
@@ -257,19 +360,40 @@ export class WebRTCService extends EventEmitter {
       const state = this.peerConnection!.iceConnectionState;
       this.logger.info('ICE connection state changed', state);
       
-      if (state === 'connected') {
-        this.isConnected = true;
-        this.emit('connected');
-      } else if (state === 'disconnected' || state === 'failed') {
-        this.isConnected = false;
-        this.emit('disconnected');
-        this.handleDisconnection();
+      if (state === 'connected' || state === 'completed') {
+        this.clearIceDisconnectTimer();
+        const recovered = this.iceRestartAttempts > 0;
+        this.iceRestartAttempts = 0;
+        this.reconnectAttempts = 0;
+        if (!this.isConnected) {
+          this.isConnected = true;
+          this.emit(recovered ? 'reconnected' : 'connected');
+        }
+      } else if (state === 'disconnected') {
+        // Give ICE a moment to recover on its own (e.g. a brief Wi-Fi dropout)
+        if (!this.iceDisconnectTimer) {
+          this.iceDisconnectTimer = setTimeout(() => {
+            this.iceDisconnectTimer = null;
+            if (this.peerConnection?.iceConnectionState === 'disconnected') {
+              this.restartIce('connectivity lost');
+            }
+          }, ICE_DISCONNECT_GRACE);
+        }
+      } else if (state === 'failed') {
+        this.clearIceDisconnectTimer();
+        this.restartIce('ICE failed');
       }
     };
     
     this.peerConnection.onconnectionstatechange = () => {
       const state = this.peerConnection!.connectionState;
       this.logger.info('Connection state changed', state);
+      this.emit('connectionStateChange', state);
+    };
+    
+    // Fires once the data channels exist, and again for ICE restarts
+    this.peerConnection.onnegotiationneeded = () => {
+      this.createOffer().catch(() => {});
     };
     
     this.peerConnection.ondatachannel = (event) => {
@@ -288,10 +412,13 @@ export class WebRTCService extends EventEmitter {
     const channels = ['control', 'input', 'clipboard', 'file', 'metrics'];
     
     channels.forEach(channelName => {
+      // SCTP schedules higher priority channels first and marks their packets
+      // with a higher DSCP class, so input and control never wait behind bulk data
       const channel = this.peerConnection!.createDataChannel(channelName, {
         ordered: channelName === 'control' || channelName === 'input',
-        maxRetransmits: channelName === 'control' ? 3 : 1
-      });
+        maxRetransmits: channelName === 'control' ? 3 : 1,
+        priority: CHANNEL_PRIORITIES[channelName] || 'low'
+      } as RTCDataChannelInit);
       
       this.handleDataChannel(channel);
     });
@@ -384,14 +511,29 @@ export class WebRTCService extends EventEmitter {
   private handleSignalingMessage(message: any): void {
     switch (message.type) {
       case 'offer':
-        this.handleOffer(message.offer);
+        this.handleDescription(message.offer, message.from).catch(() => {});
         break;
       case 'answer':
-        this.handleAnswer(message.answer);
+        this.handleDescription(message.answer, message.from).catch(() => {});
         break;
       case 'ice-candidate':
         this.handleIceCandidate(message.candidate);
         break;
+      case 'joined':
+        this.peerId = message.peerId;
+        this.logger.info(`${message.resumed ? 'Rejoined' : 'Joined'} signaling room ${message.room} with ${message.peers.length} peer(s)`);
+        break;
+      case 'peer-joined':
+        // Our earlier offer went to an empty room
+        this.logger.info('Remote peer joined, sending a new offer');
+        this.createOffer().catch(() => {});
+        break;
+      case 'peer-left':
+        this.logger.info('Remote peer left the signaling room');
+        break;
+      case 'ice-servers':
+        this.updateIceServers(message.iceServers, message.restartIce === true);
+        break;
       case 'error':
         this.logger.error('Signaling error', message.error);
         this.emit('error', new Error(message.error));
@@ -401,43 +543,59 @@ export class WebRTCService extends EventEmitter {
     }
   }
 
-  private async handleOffer(offer: RTCSessionDescriptionInit): Promise<void> {
+  private async handleDescription(description: RTCSessionDescriptionInit, from?: string): Promise<void> {
     if (!this.peerConnection) return;
-    
-    try {
-      await this.peerConnection.setRemoteDescription(offer);
-      const answer = await this.peerConnection.createAnswer();
-      await this.peerConnection.setLocalDescription(answer);
-      
-      this.sendSignalingMessage({
-        type: 'answer',
-        answer
-      });
-      
-    } catch (error) {
-      this.logger.error('Failed to handle offer', error);
-      throw error;
-    }
-  }
 
-  private async handleAnswer(answer: RTCSessionDescriptionInit): Promise<void> {
-    if (!this.peerConnection) return;
-    
     try {
-      await this.peerConnection.setRemoteDescription(answer);
+      const offerCollision = description.type === 'offer' &&
+        (this.makingOffer || this.peerConnection.signalingState !== 'stable');
+      this.ignoreOffer = offerCollision && !this.isPolite(from);
+      if (this.ignoreOffer) {
+        this.logger.debug('Ignoring colliding offer; ours takes precedence');
+        return;
+      }
+
+      // A polite peer's pending offer is rolled back implicitly
+      await this.peerConnection.setRemoteDescription(description);
+      await this.flushPendingCandidates();
+
+      if (description.type === 'offer') {
+        await this.peerConnection.setLocalDescription();
+        this.sendSignalingMessage({
+          type: 'answer',
+          answer: this.peerConnection.localDescription
+        });
+      }
     } catch (error) {
-      this.logger.error('Failed to handle answer', error);
+      this.logger.error(`Failed to handle ${description.type}`, error);
       throw error;
     }
   }
 
   private async handleIceCandidate(candidate: RTCIceCandidateInit): Promise<void> {
     if (!this.peerConnection) return;
+
+    if (!this.peerConnection.remoteDescription) {
+      this.pendingCandidates.push(candidate);
+      return;
+    }
     
     try {
       await this.peerConnection.addIceCandidate(candidate);
     } catch (error) {
-      this.logger.error('Failed to add ICE candidate', error);
+      // Candidates for an offer we ignored are expected to fail
+      if (!this.ignoreOffer) {
+        this.logger.error('Failed to add ICE candidate', error);
+      }
+    }
+  }
+
+  private async flushPendingCandidates(): Promise<void> {
+    const candidates = this.pendingCandidates;
+    this.pendingCandidates = [];
+
+    for (const candidate of candidates) {
+      await this.handleIceCandidate(candidate);
     }
   }
 
@@ -475,8 +633,109 @@ export class WebRTCService extends EventEmitter {
     this.emit('metrics', this.stats);
   }
 
+  // Keeps the session alive across network changes by gathering new
+  // candidates on the current interfaces instead of dropping the connection
+  private restartIce(reason: string): void {
+    if (!this.peerConnection || this.peerConnection.connectionState === 'closed') return;
+
+    if (this.iceRestartAttempts >= MAX_ICE_RESTARTS) {
+      this.logger.warn(`ICE restart did not recover the connection after ${MAX_ICE_RESTARTS} attempts`);
+      this.isConnected = false;
+      this.emit('disconnected');
+      this.handleDisconnection();
+      return;
+    }
+
+    this.iceRestartAttempts++;
+    this.logger.info(`Restarting ICE (${reason}), attempt ${this.iceRestartAttempts}/${MAX_ICE_RESTARTS}`);
+    this.emit('reconnecting', { reason, attempt: this.iceRestartAttempts });
+
+    // The offer with new ICE credentials goes out via onnegotiationneeded
+    this.peerConnection.restartIce();
+
+    // A restart that goes nowhere is retried like a failure
+    this.clearIceDisconnectTimer();
+    this.iceDisconnectTimer = setTimeout(() => {
+      this.iceDisconnectTimer = null;
+      const state = this.peerConnection?.iceConnectionState;
+      if (state !== 'connected' && state !== 'completed') {
+        this.restartIce('restart timed out');
+      }
+    }, ICE_DISCONNECT_GRACE * 2);
+  }
+
+  private clearIceDisconnectTimer(): void {
+    if (this.iceDisconnectTimer) {
+      clearTimeout(this.iceDisconnectTimer);
+      this.iceDisconnectTimer = null;
+    }
+  }
+
+  // A new network interface (Wi-Fi to Ethernet, VPN up) leaves ICE on stale
+  // candidates, so restart as soon as the browser reports the change
+  private handleNetworkChange = (): void => {
+    if (!this.peerConnection || !navigator.onLine) return;
+
+    this.logger.info('Network change detected');
+    if (this.signalingSocket?.readyState !== WebSocket.OPEN) {
+      this.reconnectSignaling();
+      return;
+    }
+    this.iceRestartAttempts = 0;
+    this.restartIce('network changed');
+  };
+
+  private attachNetworkListeners(): void {
+    if (this.networkListenersAttached || typeof window === 'undefined') return;
+
+    window.addEventListener('online', this.handleNetworkChange);
+    (navigator as any).connection?.addEventListener?.('change', this.handleNetworkChange);
+    this.networkListenersAttached = true;
+  }
+
+  private detachNetworkListeners(): void {
+    if (!this.networkListenersAttached) return;
+
+    window.removeEventListener('online', this.handleNetworkChange);
+    (navigator as any).connection?.removeEventListener?.('change', this.handleNetworkChange);
+    this.networkListenersAttached = false;
+  }
+
+  // The signaling socket usually dies with the old network too; reopen it
+  // without touching the peer connection, rejoining under the same peer id,
+  // then renegotiate ICE over it
+  private async reconnectSignaling(): Promise<void> {
+    if (this.signalingSocket) {
+      this.signalingSocket.onclose = null;
+      this.signalingSocket.close();
+      this.signalingSocket = null;
+    }
+
+    try {
+      await this.connectSignalingServer();
+      this.reconnectAttempts = 0;
+      this.iceRestartAttempts = 0;
+      this.restartIce('signaling reconnected');
+    } catch (error) {
+      // The socket's close handler schedules the next attempt
+      this.logger.error('Failed to reconnect signaling', error);
+    }
+  }
+
   private handleSignalingDisconnection(): void {
     this.logger.warn('Signaling connection lost');
+
+    // A live media path does not need signaling; reopen it in the background
+    if (this.peerConnection && this.peerConnection.connectionState !== 'closed' &&
+        this.reconnectAttempts < this.maxReconnectAttempts) {
+      this.reconnectAttempts++;
+      setTimeout(() => {
+        if (this.peerConnection && this.signalingSocket?.readyState !== WebSocket.OPEN) {
+          this.reconnectSignaling();
+        }
+      }, 1000 * this.reconnectAttempts);
+      return;
+    }
     this.handleDisconnection();
   }
 
@@ -515,6 +774,9 @@ export class WebRTCService extends EventEmitter {
         width: { ideal: 2560 },
         height: { ideal: 1440 },
         frameRate: { max: 60 }
+      },
+      lossless: {
+        frameRate: { max: 30 }
       }
     };
     
@@ -31,7 +31,7 @@ export interface ConnectionInfo {
   framesReceived: number;
   framesDropped: number;
   latency: number;
-  quality: 'low' | 'medium' | 'high' | 'ultra';
+  quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   screenShareActive: boolean;
   fileTransferActive: boolean;
   deviceRedirectionActive: boolean;
@@ -40,7 +40,7 @@ export interface ConnectionInfo {
 export interface ConnectionOptions {
   isHost: boolean;
   connectionCode?: string;
-  quality?: 'low' | 'medium' | 'high' | 'ultra';
+  quality?: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   enableAudio?: boolean;
   enableVideo?: boolean;
   enableClipboard?: boolean;
@@ -311,7 +311,7 @@ export class ConnectionManager extends EventEmitter {
     return this.connectionCode;
   }
 
-  updateQuality(quality: 'low' | 'medium' | 'high' | 'ultra'): void {
+  updateQuality(quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless'): void {
     if (!this.currentConnection) {
       this.logger.warn('No active connection to update quality');
       return;
@@ -9,7 +9,7 @@ export interface WebSocketOptions {
   secure: boolean;
   token?: string;
   reconnectAttempts?: number;
-  quality?: 'low' | 'medium' | 'high' | 'ultra';
+  quality?: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   enableAudio?: boolean;
   enableVideo?: boolean;
 }
@@ -141,7 +141,7 @@ export class WebSocketService extends EventEmitter {
     });
   }
 
-  updateQuality(quality: 'low' | 'medium' | 'high' | 'ultra'): void {
+  updateQuality(quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless'): void {
     if (!this.connectionOptions) return;
     
     this.connectionOptions.quality = quality;
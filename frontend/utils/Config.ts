@@ -19,11 +19,13 @@ export interface WebRTCConfig {
 }
 
 export interface DisplayConfig {
-  defaultQuality: 'low' | 'medium' | 'high' | 'ultra';
+  defaultQuality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless';
   defaultScaleMode: 'fit' | 'fill' | 'stretch';
   maintainAspectRatio: boolean;
   enableHardwareAcceleration: boolean;
   maxFrameQueueSize: number;
+  dynamicResolution: boolean;
+  resizeDebounce: number;
 }
 
 export interface UIConfig {
@@ -161,7 +163,9 @@ export class Config {
         defaultScaleMode: 'fit',
         maintainAspectRatio: true,
         enableHardwareAcceleration: true,
-        maxFrameQueueSize: 10
+        maxFrameQueueSize: 10,
+        dynamicResolution: false,
+        resizeDebounce: 500
       },
       ui: {
         theme: 'auto',
@@ -263,11 +267,11 @@ export class Config {
   }
 
   // Quality management
-  getQuality(): 'low' | 'medium' | 'high' | 'ultra' {
+  getQuality(): 'low' | 'medium' | 'high' | 'ultra' | 'lossless' {
     return this.config.display.defaultQuality;
   }
 
-  setQuality(quality: 'low' | 'medium' | 'high' | 'ultra'): void {
+  setQuality(quality: 'low' | 'medium' | 'high' | 'ultra' | 'lossless'): void {
     this.set('display.defaultQuality', quality);
   }
 
@@ -295,7 +299,7 @@ export class Config {
       }
 
       // Validate display config
-      const validQualities = ['low', 'medium', 'high', 'ultra'];
+      const validQualities = ['low', 'medium', 'high', 'ultra', 'lossless'];
       if (!validQualities.includes(this.config.display.defaultQuality)) {
         throw new Error('Invalid default quality');
       }